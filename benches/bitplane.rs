@@ -0,0 +1,92 @@
+//! Word-level path of the built-in strategies against the generic bit-by-bit path.
+//!
+//! The generic path is reached with `EmbedStrategy::Custom(embed_lsb)`, which computes the
+//! same thing as `EmbedStrategy::Lsb` but is opaque to the fast path.
+//!
+//! Run with `cargo bench --bench bitplane`.
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use stegano_rs::bitplane::{
+    BitplaneOptions, EmbedStrategy, ExtractStrategy, bitplane_embed, bitplane_embed_iter,
+    bitplane_embed_with_locator, bitplane_extract, bitplane_extract_iter, embed_lsb, extract_lsb,
+};
+use stegano_rs::embedding_locator::LinearTraversal;
+
+const HOST_LEN: usize = 1 << 20;
+
+fn paths(bits_to_operate: u8) -> [(&'static str, BitplaneOptions); 2] {
+    let word = BitplaneOptions {
+        bits_to_operate,
+        ..BitplaneOptions::default()
+    };
+    let generic = BitplaneOptions {
+        embed_strategy: Some(EmbedStrategy::Custom(embed_lsb)),
+        extract_strategy: Some(ExtractStrategy::Custom(extract_lsb)),
+        ..word
+    };
+    [("word", word), ("generic", generic)]
+}
+
+fn bench_embed(c: &mut Criterion) {
+    let mut host: Vec<u8> = (0..HOST_LEN).map(|i| (i * 31 % 251) as u8).collect();
+    let contiguous: Vec<usize> = (0..HOST_LEN).collect();
+    let strided: Vec<usize> = (1..HOST_LEN).step_by(3).collect();
+
+    let mut group = c.benchmark_group("embed");
+    for bits_to_operate in [1, 2] {
+        let secret = vec![0xA5u8; strided.len() * bits_to_operate as usize / 8];
+        group.throughput(Throughput::Bytes(secret.len() as u64));
+        for (path, options) in paths(bits_to_operate) {
+            let id = |name: &str| BenchmarkId::new(format!("{}/{}", name, path), bits_to_operate);
+            group.bench_function(id("slice_contiguous"), |b| {
+                b.iter(|| bitplane_embed(&mut host, black_box(&secret), &options, &contiguous))
+            });
+            group.bench_function(id("slice_strided"), |b| {
+                b.iter(|| bitplane_embed(&mut host, black_box(&secret), &options, &strided))
+            });
+            group.bench_function(id("iter_strided"), |b| {
+                let indices = || (1..HOST_LEN).step_by(3);
+                b.iter(|| bitplane_embed_iter(&mut host, black_box(&secret), &options, indices()))
+            });
+            group.bench_function(id("locator"), |b| {
+                b.iter(|| {
+                    bitplane_embed_with_locator(
+                        &mut host,
+                        black_box(&secret),
+                        &options,
+                        &LinearTraversal,
+                    )
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_extract(c: &mut Criterion) {
+    let host: Vec<u8> = (0..HOST_LEN).map(|i| (i * 31 % 251) as u8).collect();
+    let strided: Vec<usize> = (1..HOST_LEN).step_by(3).collect();
+
+    let mut group = c.benchmark_group("extract");
+    for bits_to_operate in [1, 2] {
+        group.throughput(Throughput::Bytes(
+            (strided.len() * bits_to_operate as usize / 8) as u64,
+        ));
+        for (path, options) in paths(bits_to_operate) {
+            let id = |name: &str| BenchmarkId::new(format!("{}/{}", name, path), bits_to_operate);
+            group.bench_function(id("slice_strided"), |b| {
+                b.iter(|| bitplane_extract(black_box(&host), &options, &strided))
+            });
+            group.bench_function(id("iter_strided"), |b| {
+                let indices = || (1..HOST_LEN).step_by(3);
+                b.iter(|| bitplane_extract_iter(black_box(&host), &options, indices()))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_embed, bench_extract);
+criterion_main!(benches);
@@ -0,0 +1,106 @@
+use alloc::{vec, vec::Vec};
+
+/// Order in which the bits of a group are read or written.
+///
+/// Used by [`BitplaneOptions`](crate::bitplane::BitplaneOptions) and
+/// [`PvdOptions`](crate::pvd::PvdOptions) for two independent settings:
+/// - the order in which the bits of each secret byte are serialized,
+/// - the order in which those bits are placed inside the chunk written to a host
+///   sample (the `bits_to_operate` bits of a sample, or the bits of a PVD difference).
+///
+/// Embedding and extraction apply the same order, so both sides must agree on it.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::bit_order::BitOrder;
+///
+/// // Bit 0 of a byte is its most significant bit in MSB-first order
+/// assert_eq!(BitOrder::MsbFirst.shift(0, 8), 7);
+/// assert_eq!(BitOrder::LsbFirst.shift(0, 8), 0);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The first bit is the most significant one (the historical behavior).
+    #[default]
+    MsbFirst,
+    /// The first bit is the least significant one.
+    LsbFirst,
+}
+
+impl BitOrder {
+    /// Returns the shift (0 = least significant bit) of the `index`-th bit of a
+    /// group of `width` bits.
+    pub fn shift(self, index: usize, width: usize) -> usize {
+        match self {
+            BitOrder::MsbFirst => width - 1 - index,
+            BitOrder::LsbFirst => index,
+        }
+    }
+}
+
+/// Returns the bit at position `bit_pos` of `secret`, serialized in `order`.
+pub(crate) fn read_bit(secret: &[u8], bit_pos: usize, order: BitOrder) -> u8 {
+    (secret[bit_pos / 8] >> order.shift(bit_pos % 8, 8)) & 1
+}
+
+/// Packs bits into bytes in a given order. A partial last byte is padded with zeros.
+pub(crate) struct BitWriter {
+    bytes: Vec<u8>,
+    bit_index: usize,
+    order: BitOrder,
+}
+
+impl BitWriter {
+    pub(crate) fn new(order: BitOrder) -> Self {
+        Self {
+            bytes: Vec::new(),
+            bit_index: 0,
+            order,
+        }
+    }
+
+    /// Returns a writer whose first bit goes to position `phase` (0 to 7) of its first byte,
+    /// for streams packed in pieces that start in the middle of a byte.
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    pub(crate) fn with_phase(order: BitOrder, phase: usize) -> Self {
+        Self {
+            bytes: if phase > 0 { vec![0] } else { Vec::new() },
+            bit_index: phase,
+            order,
+        }
+    }
+
+    pub(crate) fn push(&mut self, bit: u8) {
+        if self.bit_index.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        let last = self.bytes.len() - 1;
+        self.bytes[last] |= (bit & 1) << self.order.shift(self.bit_index % 8, 8);
+        self.bit_index += 1;
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_and_write_round_trip() {
+        let secret = [0b1000_0010, 0x5A];
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            let mut writer = BitWriter::new(order);
+            for pos in 0..16 {
+                writer.push(read_bit(&secret, pos, order));
+            }
+            assert_eq!(writer.into_bytes(), secret);
+        }
+
+        assert_eq!(read_bit(&secret, 0, BitOrder::MsbFirst), 1);
+        assert_eq!(read_bit(&secret, 0, BitOrder::LsbFirst), 0);
+        assert_eq!(read_bit(&secret, 1, BitOrder::LsbFirst), 1);
+    }
+}
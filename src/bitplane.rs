@@ -1,33 +1,56 @@
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::bit_order::{BitOrder, BitWriter, read_bit};
+use crate::embedding_locator::EmbeddingLocator;
+use crate::framing::LengthPrefix;
+use crate::keystream::{Keystream, sha256};
+use crate::report::{EmbedReport, SkipReason};
+use crate::sample::Sample;
+use crate::stego_method::StegoMethod;
+
+mod word;
+
 /// Options for configuring bitplane-based steganography operations.
 ///
 /// The `BitplaneOptions` struct encapsulates parameters that control how secret data
-/// is embedded into or extracted from a host byte stream using bit-level operations.
+/// is embedded into or extracted from a host sample stream using bit-level operations.
+///
+/// The options are generic over the host [`Sample`] type (`u8` by default), so the same
+/// functions work on 8-bit pixels, 16-bit PNG channels, 32-bit audio samples, etc.
 ///
 /// # Fields
 ///
 /// - `bits_to_operate`:  
-///   Specifies how many bits of the secret message should be embedded or extracted per host byte.  
-///   This must be a value between `1` and `8` (inclusive). Higher values increase
-///   embedding capacity but may result in more noticeable changes in the host data.
+///   Specifies how many bits of the secret message should be embedded or extracted per host sample.  
+///   This must be a value between `1` and the sample width (`8` for `u8`, `16` for `u16`, ...).
+///   Higher values increase embedding capacity but may result in more noticeable changes in the host data.
 ///
 /// - `embed_strategy`:  
-///   An optional function pointer that defines the embedding strategy.  
-///   The function takes three `u8` arguments:
-///   1. The host byte.
-///   2. The secret byte (or portion thereof).
+///   An optional [`EmbedStrategy`]: the built-in LSB or MSB strategy, or a custom function
+///   pointer. A custom function takes three arguments:
+///   1. The host sample.
+///   2. The secret bits (right-aligned in a sample).
 ///   3. The number of bits to operate.
 ///   
-///   It returns a new `u8` that represents the host byte after embedding the secret bits.
-///   If `None`, the default strategy (e.g., LSB) should be used.
+///   It returns a new sample that represents the host sample after embedding the secret bits.
+///   Embedding fails if it is `None`.
 ///
 /// - `extract_strategy`:  
-///   An optional function pointer that defines the extraction strategy.  
-///   The function takes two `u8` arguments:
-///   1. The host byte.
+///   An optional [`ExtractStrategy`]: the built-in LSB or MSB strategy, or a custom function
+///   pointer. A custom function takes two arguments:
+///   1. The host sample.
 ///   2. The number of bits to operate.
 ///   
-///   It returns a `u8` representing the extracted bits from the host byte.
-///   If `None`, the default strategy (e.g., LSB) should be used.
+///   It returns a sample representing the extracted bits from the host sample.
+///   Extraction fails if it is `None`.
+///
+/// - `secret_bit_order` / `chunk_bit_order`:  
+///   [`BitOrder`] of the bits inside each secret byte, and of the secret bits inside the
+///   chunk written to each host sample. Both default to MSB first.
+///
+/// - `remainder_fill`:  
+///   What to write at the positions left after the secret ([`RemainderFill`]). By default
+///   they are left untouched.
 ///
 /// # Example
 /// ```rust
@@ -42,124 +65,251 @@
 ///
 /// //If you just want to embed, you don't need to enter extract, and vice versa
 ///
-/// pub use stegano_rs::bitplane::BitplaneOptions;
+/// pub use stegano_rs::bitplane::{BitplaneOptions, EmbedStrategy, ExtractStrategy};
 /// let options = BitplaneOptions {
 ///     bits_to_operate: 2,
-///     embed_strategy: Some(embed_lsb),
-///     extract_strategy: Some(extract_lsb),
+///     embed_strategy: Some(EmbedStrategy::Custom(embed_lsb)),
+///     extract_strategy: Some(ExtractStrategy::Custom(extract_lsb)),
+///     ..BitplaneOptions::default()
+/// };
+/// ```
+///
+/// Using 16-bit samples with the built-in strategies:
+/// ```rust
+/// use stegano_rs::bitplane::{BitplaneOptions, EmbedStrategy, ExtractStrategy};
+///
+/// let options: BitplaneOptions<u16> = BitplaneOptions {
+///     bits_to_operate: 12,
+///     embed_strategy: Some(EmbedStrategy::Lsb),
+///     extract_strategy: Some(ExtractStrategy::Lsb),
+///     ..BitplaneOptions::default()
 /// };
 /// ```
 #[derive(Debug, Clone, Copy)]
-pub struct BitplaneOptions {
-    /// The number of bits to operate per host byte (must be between 1 and 8).
+pub struct BitplaneOptions<S: Sample = u8> {
+    /// The number of bits to operate per host sample (must be between 1 and the sample width).
     pub bits_to_operate: u8,
 
-    /// Optional embedding strategy function.
-    pub embed_strategy: Option<fn(u8, u8, u8) -> u8>,
-
-    /// Optional extraction strategy function.
+    /// Optional embedding strategy.
+    pub embed_strategy: Option<EmbedStrategy<S>>,
 
+    /// Optional extraction strategy.
     ///
-    /// This function takes a host byte and the number of bits to extract,
-    /// and must return a `u8` where the extracted `bits_to_operate`
+    /// A custom function takes a host sample and the number of bits to extract,
+    /// and must return a sample where the extracted `bits_to_operate`
     /// are aligned to the **least significant bits** (i.e., right-aligned).
     ///
     /// This alignment is required for compatibility with the default `bitplane_extract` function.
     ///
     /// Example: if `bits_to_operate = 3` and the embedded bits are `101`,
     /// the function must return `0b00000101`.
-    pub extract_strategy: Option<fn(u8, u8) -> u8>,
+    pub extract_strategy: Option<ExtractStrategy<S>>,
+
+    /// Order in which the bits of each secret byte are serialized (MSB first by default).
+    pub secret_bit_order: BitOrder,
+
+    /// Order in which the secret bits are placed inside the `bits_to_operate` bits of a
+    /// host sample (MSB first by default: the first secret bit goes to the highest bit).
+    pub chunk_bit_order: BitOrder,
+
+    /// What to write at the positions left after the secret.
+    pub remainder_fill: RemainderFill,
+}
+
+/// How the secret bits are written into a host sample.
+///
+/// The built-in strategies let embedding take a word-level fast path on contiguous or
+/// strided indices; a custom function always goes through the generic path.
+#[derive(Debug, Clone, Copy)]
+pub enum EmbedStrategy<S: Sample = u8> {
+    /// The lowest `bits_to_operate` bits ([`embed_lsb`]).
+    Lsb,
+    /// The highest `bits_to_operate` bits ([`embed_msb`]).
+    Msb,
+    /// A custom function of the host sample, the secret bits and the number of bits.
+    Custom(fn(S, S, u8) -> S),
+}
+
+impl<S: Sample> EmbedStrategy<S> {
+    /// Returns the function applying the strategy.
+    pub fn function(self) -> fn(S, S, u8) -> S {
+        match self {
+            EmbedStrategy::Lsb => embed_lsb,
+            EmbedStrategy::Msb => embed_msb,
+            EmbedStrategy::Custom(function) => function,
+        }
+    }
+}
+
+/// How the secret bits are read from a host sample. See [`EmbedStrategy`].
+#[derive(Debug, Clone, Copy)]
+pub enum ExtractStrategy<S: Sample = u8> {
+    /// The lowest `bits_to_operate` bits ([`extract_lsb`]).
+    Lsb,
+    /// The highest `bits_to_operate` bits ([`extract_msb`]).
+    Msb,
+    /// A custom function of the host sample and the number of bits.
+    Custom(fn(S, u8) -> S),
+}
+
+impl<S: Sample> ExtractStrategy<S> {
+    /// Returns the function applying the strategy.
+    pub fn function(self) -> fn(S, u8) -> S {
+        match self {
+            ExtractStrategy::Lsb => extract_lsb,
+            ExtractStrategy::Msb => extract_msb,
+            ExtractStrategy::Custom(function) => function,
+        }
+    }
+}
+
+/// What embedding writes at the locator positions left after the secret.
+///
+/// A short secret in a large host changes the host up to the last used index only, and
+/// the sharp boundary shows where the message ends. Filling the rest of the positions
+/// with random bits spreads the changes over the whole host. The extractor is not
+/// affected: with a length prefix it stops at the framed length
+/// ([`bitplane_extract_framed`]).
+///
+/// Filled positions count as used and changed in an [`EmbedReport`], but not in
+/// `bits_embedded`. Filling stops after `host.len()` indices past the secret, so that an
+/// unbounded index iterator ([`bitplane_embed_iter`]) still terminates; a locator that
+/// yields out-of-bounds indices may therefore leave some positions unfilled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RemainderFill {
+    /// The remaining positions are left untouched.
+    #[default]
+    Keep,
+    /// Random bits, from a keystream with a random key ([`Keystream::from_entropy`]).
+    /// Embedding returns an error with this fill when the `std` feature is disabled.
+    Random,
+    /// Bits of the ChaCha20 keystream under the given key, for a reproducible output.
+    ///
+    /// The nonce is derived from the secret (the first 12 bytes of
+    /// `SHA-256("fill" || secret)`), so two embeddings under the same key only share their
+    /// filling when they hide the same secret, which would then show in both carriers.
+    /// The nonce differs from the ones of the cipher that encrypts the message, so the
+    /// fill can share its key without reusing its keystream.
+    Keystream([u8; 32]),
+}
+
+impl RemainderFill {
+    /// Returns an error if the fill is not available in this build.
+    fn check(self) -> Result<(), String> {
+        match self {
+            #[cfg(not(feature = "std"))]
+            RemainderFill::Random => Err("RemainderFill::Random needs the std feature".into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn stream(self, secret: &[u8]) -> Option<Keystream> {
+        match self {
+            RemainderFill::Keep => None,
+            #[cfg(feature = "std")]
+            RemainderFill::Random => Some(Keystream::from_entropy()),
+            // Rejected by `check` before embedding
+            #[cfg(not(feature = "std"))]
+            RemainderFill::Random => None,
+            RemainderFill::Keystream(key) => {
+                let digest = sha256(&[&b"fill"[..], secret].concat());
+                let mut nonce = [0u8; 12];
+                nonce.copy_from_slice(&digest[..12]);
+                Some(Keystream::new(&key, &nonce))
+            }
+        }
+    }
 }
 
-impl Default for BitplaneOptions {
+impl<S: Sample> Default for BitplaneOptions<S> {
     /// Returns a default `BitplaneOptions` with:
     /// - `bits_to_operate` = 1,
-    /// - `embed_strategy` = `embed_lsb`,
-    /// - `extract_strategy` = `extract_lsb`.
+    /// - `embed_strategy` = `EmbedStrategy::Lsb`,
+    /// - `extract_strategy` = `ExtractStrategy::Lsb`,
+    /// - `secret_bit_order` and `chunk_bit_order` = `BitOrder::MsbFirst`,
+    /// - `remainder_fill` = `RemainderFill::Keep`.
     fn default() -> Self {
         Self {
             bits_to_operate: 1,
-            embed_strategy: Some(embed_lsb),
-            extract_strategy: Some(extract_lsb),
+            embed_strategy: Some(EmbedStrategy::Lsb),
+            extract_strategy: Some(ExtractStrategy::Lsb),
+            secret_bit_order: BitOrder::MsbFirst,
+            chunk_bit_order: BitOrder::MsbFirst,
+            remainder_fill: RemainderFill::Keep,
         }
     }
 }
 
-/// Embed secret bits in the least significant bits of the host byte (LSB).
+/// Embed secret bits in the least significant bits of the host sample (LSB).
 ///
 /// Strategy for BitplaneOptions
 ///
 /// # Arguments
-/// - `host_byte`: The byte from the host data to modify.
+/// - `host_byte`: The sample from the host data to modify.
 /// - `secret_bits`: The bits from the secret to embed.
 /// - `bits`: Number of bits to embed.
 ///
 /// # Returns
-/// A new byte with the secret bits embedded in the least significant bits.
-pub fn embed_lsb(host_byte: u8, secret_bits: u8, bits: u8) -> u8 {
-    let mask = if bits == 8 { 0xFF } else { (1 << bits) - 1 };
-    (host_byte & !mask) | (secret_bits & mask)
+/// A new sample with the secret bits embedded in the least significant bits.
+pub fn embed_lsb<S: Sample>(host_byte: S, secret_bits: S, bits: u8) -> S {
+    let mask = S::low_mask(bits);
+    S::from_raw((host_byte.to_raw() & !mask) | (secret_bits.to_raw() & mask))
 }
 
-/// Embed secret bits in the most significant bits of the host byte (MSB).
+/// Embed secret bits in the most significant bits of the host sample (MSB).
 ///
 /// Strategy for BitplaneOptions
 ///
 /// # Arguments
-/// - `host_byte`: The byte from the host data to modify.
+/// - `host_byte`: The sample from the host data to modify.
 /// - `secret_bits`: The bits from the secret to embed.
 /// - `bits`: Number of bits to embed.
 ///
 /// # Returns
-/// A new byte with the secret bits embedded in the most significant bits.
-pub fn embed_msb(host_byte: u8, secret_bits: u8, bits: u8) -> u8 {
-    let mask = if bits == 8 {
-        0xFF
-    } else {
-        ((1 << bits) - 1) << (8 - bits)
-    };
-    (host_byte & !mask) | (secret_bits << (8 - bits))
+/// A new sample with the secret bits embedded in the most significant bits.
+pub fn embed_msb<S: Sample>(host_byte: S, secret_bits: S, bits: u8) -> S {
+    let bits = bits.min(S::BITS);
+    let shift = S::BITS - bits;
+    let mask = S::low_mask(bits) << shift;
+    S::from_raw((host_byte.to_raw() & !mask) | ((secret_bits.to_raw() << shift) & mask))
 }
 
-/// Extract secret bits embedded in the least significant bits (LSB) of the host byte.
+/// Extract secret bits embedded in the least significant bits (LSB) of the host sample.
 ///
 /// # Arguments
-/// - `host_byte`: The byte containing the embedded secret.
+/// - `host_byte`: The sample containing the embedded secret.
 /// - `bits`: Number of bits embedded in the least significant bits.
 ///
 /// # Returns
 /// The extracted secret bits, aligned to the least significant bits.
-pub fn extract_lsb(host_byte: u8, bits: u8) -> u8 {
-    let mask = if bits == 8 { 0xFF } else { (1 << bits) - 1 };
-    host_byte & mask
+pub fn extract_lsb<S: Sample>(host_byte: S, bits: u8) -> S {
+    S::from_raw(host_byte.to_raw() & S::low_mask(bits))
 }
 
-/// Extract secret bits embedded in the most significant bits (MSB) of the host byte.
+/// Extract secret bits embedded in the most significant bits (MSB) of the host sample.
 ///
 /// # Arguments
-/// - `host_byte`: The byte containing the embedded secret.
+/// - `host_byte`: The sample containing the embedded secret.
 /// - `bits`: Number of bits embedded in the most significant bits.
 ///
 /// # Returns
 /// The extracted secret bits, shifted down to the least significant bits.
-pub fn extract_msb(host_byte: u8, bits: u8) -> u8 {
-    if bits == 8 {
-        host_byte
-    } else {
-        host_byte >> (8 - bits)
-    }
+pub fn extract_msb<S: Sample>(host_byte: S, bits: u8) -> S {
+    let bits = bits.min(S::BITS);
+    S::from_raw(host_byte.to_raw() >> (S::BITS - bits))
 }
 
-/// Embeds a secret message into a host buffer by modifying specific bits of each host byte
+/// Embeds a secret message into a host buffer by modifying specific bits of each host sample
 /// according to the provided bitplane embedding options.
 ///
 /// # Arguments
 ///
-/// * `host` - A mutable slice of bytes representing the host data where the secret will be embedded.
+/// * `host` - A mutable slice of samples (`u8`, `u16`, `i16`, `i32`, ...) representing the host data
+///   where the secret will be embedded.
 /// * `secret` - A slice of bytes containing the secret message to embed.
 /// * `options` - A reference to a `BitplaneOptions` struct that configures the embedding process,
-///               including the number of bits to modify per host byte and the embedding strategy function.
-/// * `embedding_indices` - A slice of `usize` indices indicating where in the host the secret bits should be embedded.
+///   including the number of bits to modify per host sample and the embedding strategy function.
+/// * `embedding_indices` - A slice of `usize` sample positions indicating where in the host the secret bits should be embedded.
 ///
 /// # Returns
 ///
@@ -168,15 +318,16 @@ pub fn extract_msb(host_byte: u8, bits: u8) -> u8 {
 /// # Example usage
 ///
 /// ```rust
-/// use stegano_rs::bitplane::{embed_lsb, bitplane_embed, BitplaneOptions};
+/// use stegano_rs::bitplane::{bitplane_embed, BitplaneOptions, EmbedStrategy};
 /// use stegano_rs::embedding_locator::*;
 ///
 /// let mut host_data = vec![0u8; 30];
 /// let secret_message = b"hidden";
 /// let options = BitplaneOptions {
 ///     bits_to_operate: 2,
-///     embed_strategy: Some(embed_lsb),
+///     embed_strategy: Some(EmbedStrategy::Lsb),
 ///     extract_strategy: None,
+///     ..BitplaneOptions::default()
 /// };
 ///
 /// // Define your own embedding indices, for example using a linear traversal
@@ -187,168 +338,694 @@ pub fn extract_msb(host_byte: u8, bits: u8) -> u8 {
 /// bitplane_embed(&mut host_data, secret_message, &options, &embedding_indices);
 /// ```
 ///
+/// Embedding 12 bits per sample into a 16-bit host:
+///
+/// ```rust
+/// use stegano_rs::bitplane::{bitplane_embed, bitplane_extract, BitplaneOptions};
+/// use stegano_rs::embedding_locator::*;
+///
+/// let mut host: Vec<u16> = vec![0xF000; 4];
+/// let options = BitplaneOptions::<u16> { bits_to_operate: 12, ..BitplaneOptions::default() };
+/// let indices: Vec<usize> = LinearTraversal.iter_indices(host.len()).collect();
+///
+/// bitplane_embed(&mut host, b"abc", &options, &indices).unwrap();
+/// assert_eq!(host[0] & 0xF000, 0xF000); // the 4 high bits are untouched
+/// assert_eq!(&bitplane_extract(&host, &options, &indices).unwrap()[..3], b"abc");
+/// ```
+///
 /// # Errors
 ///
 /// The function returns an error string in the following cases:
-/// - If `bits_to_operate` is not between 1 and 8.
+/// - If `bits_to_operate` is not between 1 and the sample width.
 /// - If no embedding strategy function is provided.
 /// - If the host buffer is too small to embed the entire secret message.
 ///
 /// # See also
 ///
 /// `BitplaneOptions` struct, embedding strategy functions.
-pub fn bitplane_embed(
-    host: &mut [u8],
+pub fn bitplane_embed<S: Sample>(
+    host: &mut [S],
     secret: &[u8],
-    options: &BitplaneOptions,
+    options: &BitplaneOptions<S>,
     embedding_indices: &[usize],
 ) -> Result<(), String> {
     // Validate bits_to_operate
-    if options.bits_to_operate == 0 || options.bits_to_operate > 8 {
-        return Err("options.bits_to_operate must be between 1 and 8".into());
-    }
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
 
     // Validate embed_strategy
-    let embed_fn = match options.embed_strategy {
-        Some(f) => f,
-        None => return Err("options.embed_strategy function must be provided".into()),
-    };
+    let embed_fn = embed_strategy(options)?;
+    options.remainder_fill.check()?;
 
     let total_bits = secret.len() * 8;
     let capacity = embedding_indices.len() * options.bits_to_operate as usize;
+    check_capacity(capacity, total_bits)?;
 
-    if capacity < total_bits {
-        return Err(format!(
-            "Not enough space in host to hide the secret message: capacity={} bits, message={} bits",
-            capacity, total_bits
-        ));
+    // The built-in strategies take the word-level path
+    if !word::try_embed(host, secret, options, embedding_indices) {
+        embed_bits(host, secret, embed_fn, options, embedding_indices.iter().copied(), None);
     }
+    Ok(())
+}
 
-    let mut bit_index = 0;
-
-    for &idx in embedding_indices {
-        if bit_index >= total_bits {
-            break;
-        }
+/// Embeds a secret message into a host buffer, reading the positions lazily from an
+/// [`EmbeddingLocator`].
+///
+/// This behaves like [`bitplane_embed`], but never collects the indices into a
+/// `Vec<usize>`: the locator is iterated once to check the capacity, then once more
+/// to embed the secret.
+///
+/// # Example
+///
+/// ```rust
+/// use stegano_rs::bitplane::{bitplane_embed_with_locator, bitplane_extract_with_locator, BitplaneOptions};
+/// use stegano_rs::embedding_locator::{HeatmapTraversal, LinearTraversal};
+///
+/// let mut host = vec![0u8; 16];
+/// let options = BitplaneOptions::default();
+///
+/// bitplane_embed_with_locator(&mut host, b"Hi", &options, &LinearTraversal).unwrap();
+/// let extracted = bitplane_extract_with_locator(&host, &options, &LinearTraversal).unwrap();
+/// assert_eq!(extracted, b"Hi");
+///
+/// // Borrowed locators work the same way
+/// let heatmap = [255u8; 16];
+/// let locator = HeatmapTraversal { heatmap: &heatmap, threshold: 128 };
+/// bitplane_embed_with_locator(&mut host, b"Yo", &options, &locator).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// Same as [`bitplane_embed`]. The host is left untouched when an error is returned.
+pub fn bitplane_embed_with_locator<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &mut [S],
+    secret: &[u8],
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<(), String> {
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
+    let embed_fn = embed_strategy(options)?;
+    options.remainder_fill.check()?;
 
-        if idx >= host.len() {
-            continue; // Skip invalid indices
-        }
+    let len = host.len();
+    let total_bits = secret.len() * 8;
+    let capacity = locator.iter_indices(len).filter(|&idx| idx < len).count()
+        * options.bits_to_operate as usize;
+    check_capacity(capacity, total_bits)?;
 
-        // Extract up to `bits_to_operate` bits from the secret
-        let mut secret_bits: u8 = 0;
-        for i in 0..options.bits_to_operate {
-            let bit_pos = bit_index + i as usize;
-            if bit_pos >= total_bits {
-                break;
-            }
+    if let Err(indices) = word::try_embed_iter(host, secret, options, locator.iter_indices(len)) {
+        embed_bits(host, secret, embed_fn, options, indices, None);
+    }
+    Ok(())
+}
 
-            let byte = secret[bit_pos / 8];
-            let bit = (byte >> (7 - (bit_pos % 8))) & 1;
-            secret_bits |= bit << (options.bits_to_operate - 1 - i);
-        }
+/// Embeds a secret message like [`bitplane_embed_with_locator`] and returns an
+/// [`EmbedReport`] describing which positions were used and how much the host changed.
+///
+/// # Example
+///
+/// ```rust
+/// use stegano_rs::bitplane::{bitplane_embed_with_report, BitplaneOptions};
+/// use stegano_rs::embedding_locator::PositionListTraversal;
+///
+/// let mut host = vec![0u8; 8];
+/// let options = BitplaneOptions { bits_to_operate: 4, ..BitplaneOptions::default() };
+/// let locator = PositionListTraversal { positions: &[3, 5] };
+///
+/// let report = bitplane_embed_with_report(&mut host, &[0x0F], &options, &locator).unwrap();
+/// assert_eq!(report.positions_used, vec![3, 5]);
+/// assert_eq!(report.positions_changed, vec![5]);
+/// assert_eq!(report.last_index, Some(5));
+/// assert_eq!(report.total_abs_distortion, 15);
+/// ```
+///
+/// # Errors
+///
+/// Same as [`bitplane_embed_with_locator`].
+pub fn bitplane_embed_with_report<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &mut [S],
+    secret: &[u8],
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<EmbedReport, String> {
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
+    let embed_fn = embed_strategy(options)?;
+    options.remainder_fill.check()?;
+
+    let len = host.len();
+    let total_bits = secret.len() * 8;
+    let capacity = locator.iter_indices(len).filter(|&idx| idx < len).count()
+        * options.bits_to_operate as usize;
+    check_capacity(capacity, total_bits)?;
+
+    let mut report = EmbedReport::default();
+    embed_bits(
+        host,
+        secret,
+        embed_fn,
+        options,
+        locator.iter_indices(len),
+        Some(&mut report),
+    );
+    Ok(report)
+}
 
-        // Apply the selected embedding strategy
-        host[idx] = (embed_fn)(host[idx], secret_bits, options.bits_to_operate);
-        bit_index += options.bits_to_operate as usize;
-    }
+/// Embeds a secret message into a host buffer at the positions yielded by any iterator.
+///
+/// The indices are consumed lazily and only as far as needed. Since the capacity of an
+/// arbitrary iterator is only known once it is exhausted, the host may have been partially
+/// modified when the "Not enough space" error is returned.
+///
+/// # Example
+///
+/// ```rust
+/// use stegano_rs::bitplane::{bitplane_embed_iter, bitplane_extract_iter, BitplaneOptions};
+///
+/// let mut host = vec![0u8; 32];
+/// let options = BitplaneOptions::default();
+///
+/// // Use every other byte
+/// bitplane_embed_iter(&mut host, b"Hi", &options, (0..32).step_by(2)).unwrap();
+/// assert_eq!(bitplane_extract_iter(&host, &options, (0..32).step_by(2)).unwrap(), b"Hi");
+/// ```
+pub fn bitplane_embed_iter<S: Sample>(
+    host: &mut [S],
+    secret: &[u8],
+    options: &BitplaneOptions<S>,
+    embedding_indices: impl IntoIterator<Item = usize>,
+) -> Result<(), String> {
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
+    let embed_fn = embed_strategy(options)?;
+    options.remainder_fill.check()?;
 
-    Ok(())
+    let total_bits = secret.len() * 8;
+    let indices = embedding_indices.into_iter();
+    let embedded = word::try_embed_iter(host, secret, options, indices)
+        .unwrap_or_else(|indices| embed_bits(host, secret, embed_fn, options, indices, None));
+    // The iterator was exhausted before the end of the secret: `embedded` is the full capacity
+    check_capacity(embedded, total_bits)
 }
 
 /// Extracts a secret message from the host buffer using a bitplane extraction strategy.
 ///
 /// # Arguments
-/// - `host`: The byte buffer containing the embedded secret.
+/// - `host`: The sample buffer containing the embedded secret.
 /// - `options`: The options indicating how to extract the secret, including the number
 ///   of bits to operate on and the extraction strategy function.
-/// - `extraction_indices`: A slice of indices indicating which bytes in the host buffer to extract from.
+/// - `extraction_indices`: A slice of indices indicating which samples in the host buffer to extract from.
 ///
 /// # Returns
 /// A vector of bytes representing the extracted secret.
 ///
 /// # Requirements
-/// - `options.bits_to_operate` must be between 1 and 8.
-/// - `options.extract_strategy` must be provided and must return a `u8` where
+/// - `options.bits_to_operate` must be between 1 and the sample width.
+/// - `options.extract_strategy` must be provided and must return a sample where
 ///   the extracted bits are aligned to the least significant bits (right-aligned).
 ///   For example, if `bits_to_operate = 3` and the extracted bits are `101`,
 ///   the function should return `0b00000101`.
 ///
 /// # Example
 /// ```rust
-/// use stegano_rs::bitplane::{extract_lsb, bitplane_extract, BitplaneOptions};
+/// use stegano_rs::bitplane::{bitplane_extract, BitplaneOptions, ExtractStrategy};
 /// use stegano_rs::embedding_locator::{LinearTraversal, EmbeddingLocator};
 ///
 /// let host_data = vec![0b10101100, 0b01110010];
 /// let options = BitplaneOptions {
 ///     bits_to_operate: 2,
 ///     embed_strategy: None,
-///     extract_strategy: Some(extract_lsb),
+///     extract_strategy: Some(ExtractStrategy::Lsb),
+///     ..BitplaneOptions::default()
 /// };
 /// let locator = LinearTraversal;
 /// let extraction_indices: Vec<usize> = locator.iter_indices(host_data.len()).collect();
 ///
 /// let secret = bitplane_extract(&host_data, &options, &extraction_indices).unwrap();
 /// ```
-pub fn bitplane_extract(
-    host: &[u8],
-    options: &BitplaneOptions,
+pub fn bitplane_extract<S: Sample>(
+    host: &[S],
+    options: &BitplaneOptions<S>,
     extraction_indices: &[usize],
 ) -> Result<Vec<u8>, String> {
     // Validate bits_to_operate
-    if options.bits_to_operate == 0 || options.bits_to_operate > 8 {
-        return Err("options.bits_to_operate must be between 1 and 8".into());
-    }
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
 
     // Get the extraction function
-    let extract_fn = match options.extract_strategy {
-        Some(f) => f,
-        None => return Err("No extract strategy provided".into()),
-    };
+    let extract_fn = extract_strategy(options)?;
 
     // Estimate the maximum number of bits we can extract from the host (based on extraction indices)
     let total_bits = extraction_indices.len() * options.bits_to_operate as usize;
 
     // Compute how many full bytes that corresponds to
-    let total_bytes = (total_bits + 7) / 8;
-    let mut secret = vec![0u8; total_bytes];
+    let total_bytes = total_bits.div_ceil(8);
+
+    // The built-in strategies take the word-level path
+    let mut secret = word::try_extract(host, options, extraction_indices).unwrap_or_else(|| {
+        extract_bits(
+            host,
+            extract_fn,
+            options,
+            extraction_indices.iter().copied(),
+        )
+    });
+    // Invalid indices are skipped, the missing bits are padded with zeros
+    secret.resize(total_bytes, 0);
+
+    Ok(secret)
+}
+
+/// Extracts a secret message from the host buffer, reading the positions lazily from an
+/// [`EmbeddingLocator`].
+///
+/// This behaves like [`bitplane_extract`] without collecting the indices into a `Vec<usize>`.
+/// Indices returned by the locator that fall outside the host are ignored.
+///
+/// See [`bitplane_embed_with_locator`] for an example.
+pub fn bitplane_extract_with_locator<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &[S],
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<Vec<u8>, String> {
+    bitplane_extract_iter(host, options, locator.iter_indices(host.len()))
+}
+
+/// Extracts a secret message from the host buffer at the positions yielded by any iterator.
+///
+/// Indices outside the host are ignored. The last byte is padded with zeros on the right
+/// if the number of extracted bits is not a multiple of 8.
+///
+/// See [`bitplane_embed_iter`] for an example.
+pub fn bitplane_extract_iter<S: Sample>(
+    host: &[S],
+    options: &BitplaneOptions<S>,
+    extraction_indices: impl IntoIterator<Item = usize>,
+) -> Result<Vec<u8>, String> {
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
+    let extract_fn = extract_strategy(options)?;
+
+    let indices = extraction_indices.into_iter();
+    Ok(word::try_extract_iter(host, options, indices)
+        .unwrap_or_else(|indices| extract_bits(host, extract_fn, options, indices)))
+}
 
+/// Frames the secret with a length prefix and embeds it like [`bitplane_embed_with_locator`].
+///
+/// # Example
+///
+/// ```rust
+/// use stegano_rs::bitplane::{
+///     BitplaneOptions, RemainderFill, bitplane_embed_framed, bitplane_extract_framed,
+/// };
+/// use stegano_rs::embedding_locator::LinearTraversal;
+/// use stegano_rs::framing::LengthPrefix;
+///
+/// let mut host = vec![0u8; 1024];
+/// let options = BitplaneOptions {
+///     remainder_fill: RemainderFill::Keystream([7; 32]),
+///     ..BitplaneOptions::default()
+/// };
+/// let prefix = LengthPrefix::default();
+/// bitplane_embed_framed(&mut host, b"short", &options, &LinearTraversal, &prefix).unwrap();
+///
+/// // The whole host was written, but the extractor stops after the announced length
+/// assert!(host[900..].iter().any(|&b| b == 1));
+/// let extracted = bitplane_extract_framed(&host, &options, &LinearTraversal, &prefix);
+/// assert_eq!(extracted.unwrap(), b"short");
+/// ```
+///
+/// # Errors
+///
+/// Same as [`bitplane_embed_with_locator`], and if the length does not fit in the prefix.
+pub fn bitplane_embed_framed<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &mut [S],
+    secret: &[u8],
+    options: &BitplaneOptions<S>,
+    locator: &L,
+    prefix: &LengthPrefix,
+) -> Result<(), String> {
+    let framed = prefix.frame(secret)?;
+    bitplane_embed_with_locator(host, &framed, options, locator)
+}
+
+/// Reads a length prefix, then extracts exactly the payload it announces, ignoring the
+/// rest of the positions.
+///
+/// See [`bitplane_embed_framed`] for an example.
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid, or if the length prefix announces more
+/// data than the host holds.
+pub fn bitplane_extract_framed<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &[S],
+    options: &BitplaneOptions<S>,
+    locator: &L,
+    prefix: &LengthPrefix,
+) -> Result<Vec<u8>, String> {
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
+    let bits_per_sample = options.bits_to_operate as usize;
+    let len = host.len();
+    let indices = || locator.iter_indices(len).filter(move |&idx| idx < len);
+
+    let header_bits = prefix.size * 8;
+    let header = bitplane_extract_iter(
+        host,
+        options,
+        indices().take(header_bits.div_ceil(bits_per_sample)),
+    )?;
+    let total_bits = header_bits + prefix.payload_bits(&header)?;
+
+    let samples = total_bits.div_ceil(bits_per_sample);
+    let available = indices().count();
+    if samples > available {
+        return Err(format!(
+            "Length prefix announces {} bits but the host only holds {} bits",
+            total_bits,
+            available * bits_per_sample
+        ));
+    }
+
+    let framed = bitplane_extract_iter(host, options, indices().take(samples))?;
+    prefix.unframe(&framed)
+}
+
+/// Number of samples per chunk of the parallel functions. It is a multiple of 8, so every
+/// chunk but the last one extracts whole bytes.
+#[cfg(feature = "parallel")]
+const PARALLEL_CHUNK: usize = 1 << 16;
+
+/// Embeds a secret message like [`bitplane_embed`], on the rayon thread pool.
+///
+/// The valid indices are split into chunks of fixed size, so chunk `c` starts at secret bit
+/// `c * chunk * bits_to_operate` and the chunks are independent. The new samples are
+/// computed in parallel, then written in index order: the host is byte-identical to the one
+/// produced by [`bitplane_embed`], including the [`RemainderFill`] bits (each chunk seeks
+/// the filler stream to its own offset). Index lists that visit a sample twice are embedded
+/// sequentially.
+///
+/// # Example
+///
+/// ```rust
+/// use stegano_rs::bitplane::{BitplaneOptions, bitplane_embed, bitplane_embed_parallel};
+///
+/// let indices: Vec<usize> = (0..200_000).collect();
+/// let options = BitplaneOptions { bits_to_operate: 2, ..BitplaneOptions::default() };
+/// let secret = vec![0xA5; 40_000];
+///
+/// let mut sequential = vec![0x33u8; 200_000];
+/// bitplane_embed(&mut sequential, &secret, &options, &indices).unwrap();
+/// let mut parallel = vec![0x33u8; 200_000];
+/// bitplane_embed_parallel(&mut parallel, &secret, &options, &indices).unwrap();
+/// assert_eq!(parallel, sequential);
+/// ```
+///
+/// # Errors
+///
+/// Same as [`bitplane_embed`]. The host is left untouched when an error is returned.
+#[cfg(feature = "parallel")]
+pub fn bitplane_embed_parallel<S: Sample + Send + Sync>(
+    host: &mut [S],
+    secret: &[u8],
+    options: &BitplaneOptions<S>,
+    embedding_indices: &[usize],
+) -> Result<(), String> {
+    use rayon::prelude::*;
+
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
+    let embed_fn = embed_strategy(options)?;
+    options.remainder_fill.check()?;
+    let bits = options.bits_to_operate as usize;
+    let total_bits = secret.len() * 8;
+    check_capacity(embedding_indices.len() * bits, total_bits)?;
+
+    let len = host.len();
+    if options.remainder_fill != RemainderFill::Keep && embedding_indices.len() > len {
+        // The filling may stop before the end of the indices
+        return bitplane_embed(host, secret, options, embedding_indices);
+    }
+    let mut seen = vec![false; len];
+    let mut valid = Vec::with_capacity(embedding_indices.len());
+    for &idx in embedding_indices.iter().filter(|&&idx| idx < len) {
+        if seen[idx] {
+            // A later write depends on an earlier one
+            return bitplane_embed(host, secret, options, embedding_indices);
+        }
+        seen[idx] = true;
+        valid.push(idx);
+    }
+    if options.remainder_fill == RemainderFill::Keep {
+        valid.truncate(total_bits.div_ceil(bits));
+    }
+
+    // The filler is advanced once per sample from the one holding the last secret bit on
+    let filler = options.remainder_fill.stream(secret);
+    let first_filled = total_bits / bits;
+    let source: &[S] = host;
+    let chunks: Vec<Vec<S>> = valid
+        .par_chunks(PARALLEL_CHUNK)
+        .enumerate()
+        .map(|(c, chunk)| {
+            let start = c * PARALLEL_CHUNK;
+            let mut filler = filler.clone();
+            if let Some(stream) = filler.as_mut() {
+                stream.seek(8 * start.saturating_sub(first_filled) as u64);
+            }
+            chunk
+                .iter()
+                .enumerate()
+                .map(|(j, &idx)| {
+                    let secret_bits =
+                        chunk_bits(secret, (start + j) * bits, options, filler.as_mut());
+                    (embed_fn)(
+                        source[idx],
+                        S::from_raw(secret_bits),
+                        options.bits_to_operate,
+                    )
+                })
+                .collect()
+        })
+        .collect();
+
+    for (&idx, value) in valid.iter().zip(chunks.into_iter().flatten()) {
+        host[idx] = value;
+    }
+    Ok(())
+}
+
+/// Extracts a secret message like [`bitplane_extract`], on the rayon thread pool.
+///
+/// The valid indices are split into chunks that each extract whole bytes, and the bytes
+/// are concatenated in order: the result is byte-identical to [`bitplane_extract`],
+/// including the zero padding for invalid indices.
+///
+/// # Errors
+///
+/// Same as [`bitplane_extract`].
+#[cfg(feature = "parallel")]
+pub fn bitplane_extract_parallel<S: Sample + Send + Sync>(
+    host: &[S],
+    options: &BitplaneOptions<S>,
+    extraction_indices: &[usize],
+) -> Result<Vec<u8>, String> {
+    use rayon::prelude::*;
+
+    validate_bits_to_operate::<S>(options.bits_to_operate)?;
+    let extract_fn = extract_strategy(options)?;
+    let valid: Vec<usize> = extraction_indices
+        .iter()
+        .copied()
+        .filter(|&idx| idx < host.len())
+        .collect();
+    let chunks: Vec<Vec<u8>> = valid
+        .par_chunks(PARALLEL_CHUNK)
+        .map(|chunk| extract_bits(host, extract_fn, options, chunk.iter().copied()))
+        .collect();
+    let mut secret = chunks.concat();
+    // Invalid indices are skipped, the missing bits are padded with zeros
+    secret.resize(
+        (extraction_indices.len() * options.bits_to_operate as usize).div_ceil(8),
+        0,
+    );
+    Ok(secret)
+}
+
+impl<S: Sample> StegoMethod<S> for BitplaneOptions<S> {
+    /// Each valid index returned by the locator holds `bits_to_operate` bits.
+    fn capacity(&self, host: &[S], locator: &dyn EmbeddingLocator) -> usize {
+        locator
+            .iter_indices(host.len())
+            .filter(|&idx| idx < host.len())
+            .count()
+            * self.bits_to_operate as usize
+    }
+
+    /// Calls [`bitplane_embed_with_locator`] and returns the number of secret bits embedded.
+    fn embed(
+        &self,
+        host: &mut [S],
+        secret: &[u8],
+        locator: &dyn EmbeddingLocator,
+    ) -> Result<usize, String> {
+        bitplane_embed_with_locator(host, secret, self, locator)?;
+        Ok(secret.len() * 8)
+    }
+
+    /// Calls [`bitplane_extract_with_locator`].
+    fn extract(&self, host: &[S], locator: &dyn EmbeddingLocator) -> Result<Vec<u8>, String> {
+        bitplane_extract_with_locator(host, self, locator)
+    }
+}
+
+/// Embeds the secret bits (MSB first) at the given indices, skipping indices outside the host.
+///
+/// Stops as soon as the whole secret is embedded, unless `options.remainder_fill` asks to
+/// fill the remaining indices (at most `host.len()` of them), and returns the number of
+/// secret bits embedded.
+fn embed_bits<S: Sample>(
+    host: &mut [S],
+    secret: &[u8],
+    embed_fn: fn(S, S, u8) -> S,
+    options: &BitplaneOptions<S>,
+    indices: impl Iterator<Item = usize>,
+    mut report: Option<&mut EmbedReport>,
+) -> usize {
+    let bits_to_operate = options.bits_to_operate;
+    let total_bits = secret.len() * 8;
     let mut bit_index = 0;
+    let mut filler = options.remainder_fill.stream(secret);
+    let mut filled = 0;
 
-    // Iterate over the specified indices in the host buffer
-    for &idx in extraction_indices {
+    for idx in indices {
         if bit_index >= total_bits {
-            break;
+            if filler.is_none() || filled == host.len() {
+                break;
+            }
+            filled += 1;
+        }
+
+        if let Some(report) = report.as_deref_mut() {
+            report.last_index = Some(idx);
+        }
+
+        if idx >= host.len() {
+            if let Some(report) = report.as_deref_mut() {
+                report.record_skip(vec![idx], SkipReason::OutOfBounds);
+            }
+            continue; // Skip invalid indices
+        }
+
+        // Apply the selected embedding strategy
+        let secret_bits = chunk_bits(secret, bit_index, options, filler.as_mut());
+        let before = host[idx];
+        host[idx] = (embed_fn)(before, S::from_raw(secret_bits), bits_to_operate);
+        bit_index += bits_to_operate as usize;
+
+        if let Some(report) = report.as_deref_mut() {
+            report.record_position(idx, before.to_i128(), host[idx].to_i128());
         }
+    }
+
+    let embedded = bit_index.min(total_bits);
+    if let Some(report) = report {
+        report.bits_embedded = embedded;
+    }
+    embedded
+}
+
+/// Returns the `bits_to_operate` bits written at secret bit `bit_index`, in chunk order.
+///
+/// Bits past the end of the secret come from the filler, which is advanced by 8 bytes for
+/// each chunk that needs it, or are 0 without a filler.
+fn chunk_bits<S: Sample>(
+    secret: &[u8],
+    bit_index: usize,
+    options: &BitplaneOptions<S>,
+    filler: Option<&mut Keystream>,
+) -> u64 {
+    let bits_to_operate = options.bits_to_operate as usize;
+    let total_bits = secret.len() * 8;
+    let random = match filler {
+        Some(stream) if bit_index + bits_to_operate > total_bits => stream.next_u64(),
+        _ => 0,
+    };
+    let mut secret_bits: u64 = 0;
+    for i in 0..bits_to_operate {
+        let bit_pos = bit_index + i;
+        let bit = if bit_pos < total_bits {
+            read_bit(secret, bit_pos, options.secret_bit_order) as u64
+        } else {
+            (random >> i) & 1
+        };
+        secret_bits |= bit << options.chunk_bit_order.shift(i, bits_to_operate);
+    }
+    secret_bits
+}
+
+/// Extracts `bits_to_operate` bits from each valid index and packs them MSB first.
+///
+/// The last byte is padded with zeros on the right.
+fn extract_bits<S: Sample>(
+    host: &[S],
+    extract_fn: fn(S, u8) -> S,
+    options: &BitplaneOptions<S>,
+    indices: impl Iterator<Item = usize>,
+) -> Vec<u8> {
+    let bits_to_operate = options.bits_to_operate as usize;
+    let mut secret = BitWriter::new(options.secret_bit_order);
 
+    // Iterate over the specified indices in the host buffer
+    for idx in indices {
         if idx >= host.len() {
             continue; // Skip invalid indices
         }
 
         // Extract only the bits_to_operate bits using the extraction strategy
-        let extracted_bits = (extract_fn)(host[idx], options.bits_to_operate);
-
-        // Go through each bit extracted from the current host byte
-        for i in 0..options.bits_to_operate {
-            // Extract the bit at position i
-            let bit = (extracted_bits >> (options.bits_to_operate - 1 - i)) & 1;
-
-            // Calculate the index in the secret buffer
-            let byte_index = bit_index / 8;
-            // Calculate the bit offset within that byte
-            let bit_offset = 7 - (bit_index % 8);
-
-            // Ensure we don't write out of bounds
-            // We shift the bit to its correct position without erasing the others already written
-            if byte_index < secret.len() {
-                secret[byte_index] |= bit << bit_offset;
-            }
+        let extracted_bits = (extract_fn)(host[idx], options.bits_to_operate).to_raw();
 
-            bit_index += 1;
+        // Go through each bit extracted from the current host sample
+        for i in 0..bits_to_operate {
+            let bit = (extracted_bits >> options.chunk_bit_order.shift(i, bits_to_operate)) & 1;
+            secret.push(bit as u8);
         }
     }
 
-    Ok(secret)
+    secret.into_bytes()
+}
+
+/// Returns the embedding strategy, or an error if none is set.
+fn embed_strategy<S: Sample>(options: &BitplaneOptions<S>) -> Result<fn(S, S, u8) -> S, String> {
+    options
+        .embed_strategy
+        .map(EmbedStrategy::function)
+        .ok_or_else(|| "options.embed_strategy function must be provided".into())
+}
+
+/// Returns the extraction strategy, or an error if none is set.
+fn extract_strategy<S: Sample>(options: &BitplaneOptions<S>) -> Result<fn(S, u8) -> S, String> {
+    options
+        .extract_strategy
+        .map(ExtractStrategy::function)
+        .ok_or_else(|| "No extract strategy provided".into())
+}
+
+/// Checks that `capacity` bits are enough to hold a `total_bits` long secret.
+fn check_capacity(capacity: usize, total_bits: usize) -> Result<(), String> {
+    if capacity < total_bits {
+        return Err(format!(
+            "Not enough space in host to hide the secret message: capacity={} bits, message={} bits",
+            capacity, total_bits
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that `bits` is a valid number of bits to operate on for samples of type `S`.
+fn validate_bits_to_operate<S: Sample>(bits: u8) -> Result<(), String> {
+    if bits == 0 || bits > S::BITS {
+        return Err(format!(
+            "options.bits_to_operate must be between 1 and {}",
+            S::BITS
+        ));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -378,17 +1055,17 @@ mod tests {
     #[test]
     fn test_embed_msb() {
         // host_byte = 0b00001111, secret_bits = 0b00000011, bits = 2
-        let result = embed_msb(0b00001111, 0b00000011, 2);
+        let result: u8 = embed_msb(0b00001111, 0b00000011, 2);
         // Replace the 2 most significant bits with 11 -> expected result: 0b11001111 (207)
         assert_eq!(result, 0b11001111);
 
         // bits = 4, secret_bits = 0b1010
-        let result = embed_msb(0b11110000, 0b00001010, 4);
+        let result: u8 = embed_msb(0b11110000, 0b00001010, 4);
         // Result: most significant 4 bits replaced by 1010 -> 0b10100000 (160)
         assert_eq!(result, 0b10100000);
 
         // bits = 8, replace all bits
-        let result = embed_msb(0b11111111, 0b01010101, 8);
+        let result: u8 = embed_msb(0b11111111, 0b01010101, 8);
         assert_eq!(result, 0b01010101);
     }
 
@@ -398,8 +1075,9 @@ mod tests {
         let secret = vec![0b1010_1100]; // 8 bits secret
         let options = BitplaneOptions {
             bits_to_operate: 2,
-            embed_strategy: Some(embed_lsb),
+            embed_strategy: Some(EmbedStrategy::Lsb),
             extract_strategy: None,
+            ..BitplaneOptions::default()
         };
 
         let locator: LinearTraversal = LinearTraversal;
@@ -417,8 +1095,9 @@ mod tests {
         let secret = vec![0u8; 1];
         let options = BitplaneOptions {
             bits_to_operate: 0,
-            embed_strategy: Some(embed_lsb),
+            embed_strategy: Some(EmbedStrategy::Lsb),
             extract_strategy: None,
+            ..BitplaneOptions::default()
         };
 
         let locator: LinearTraversal = LinearTraversal;
@@ -437,8 +1116,9 @@ mod tests {
         let secret = vec![0u8; 1];
         let options = BitplaneOptions {
             bits_to_operate: 9,
-            embed_strategy: Some(embed_lsb),
+            embed_strategy: Some(EmbedStrategy::Lsb),
             extract_strategy: None,
+            ..BitplaneOptions::default()
         };
 
         let locator: LinearTraversal = LinearTraversal;
@@ -459,6 +1139,7 @@ mod tests {
             bits_to_operate: 2,
             embed_strategy: None,
             extract_strategy: None,
+            ..BitplaneOptions::default()
         };
 
         let locator: LinearTraversal = LinearTraversal;
@@ -477,8 +1158,9 @@ mod tests {
         let secret = vec![0u8; 2]; // 16 bits secret
         let options = BitplaneOptions {
             bits_to_operate: 2,
-            embed_strategy: Some(embed_lsb),
+            embed_strategy: Some(EmbedStrategy::Lsb),
             extract_strategy: None,
+            ..BitplaneOptions::default()
         };
 
         // Capacity = 1 * 2 = 2 bits < 16 bits of secret, should error
@@ -497,31 +1179,31 @@ mod tests {
     #[test]
     fn test_extract_lsb() {
         // Extract 1 bit from LSB (should be 1)
-        assert_eq!(extract_lsb(0b0000_0001, 1), 0b1);
+        assert_eq!(extract_lsb::<u8>(0b0000_0001, 1), 0b1);
 
         // Extract 3 bits from LSB (should be 0b101)
-        assert_eq!(extract_lsb(0b0000_0101, 3), 0b101);
+        assert_eq!(extract_lsb::<u8>(0b0000_0101, 3), 0b101);
 
         // Extract 5 bits from LSB (should be 0b10101)
-        assert_eq!(extract_lsb(0b0001_0101, 5), 0b10101);
+        assert_eq!(extract_lsb::<u8>(0b0001_0101, 5), 0b10101);
 
         // Extract 8 bits from LSB (whole byte)
-        assert_eq!(extract_lsb(0b1010_1010, 8), 0b1010_1010);
+        assert_eq!(extract_lsb::<u8>(0b1010_1010, 8), 0b1010_1010);
     }
 
     #[test]
     fn test_extract_msb() {
         // Extract 1 bit from MSB (should be 1)
-        assert_eq!(extract_msb(0b1000_0000, 1), 0b1);
+        assert_eq!(extract_msb::<u8>(0b1000_0000, 1), 0b1);
 
         // Extract 3 bits from MSB (should be 0b101)
-        assert_eq!(extract_msb(0b1010_0000, 3), 0b101);
+        assert_eq!(extract_msb::<u8>(0b1010_0000, 3), 0b101);
 
         // Extract 5 bits from MSB (should be 0b10101)
-        assert_eq!(extract_msb(0b1010_1000, 5), 0b10101);
+        assert_eq!(extract_msb::<u8>(0b1010_1000, 5), 0b10101);
 
         // Extract 8 bits from MSB (whole byte)
-        assert_eq!(extract_msb(0b0101_0101, 8), 0b0101_0101);
+        assert_eq!(extract_msb::<u8>(0b0101_0101, 8), 0b0101_0101);
     }
 
     // Bitplane extraction tests
@@ -529,10 +1211,10 @@ mod tests {
     #[test]
     fn test_extract_lsb_simple() {
         // Host bytes with 3 secret bits embedded in LSB: 0b00000101 (bits=101) and 0b00000011 (bits=011)
-        let host = [0b00000101, 0b00000011];
+        let host: [u8; 2] = [0b00000101, 0b00000011];
         let options = BitplaneOptions {
             bits_to_operate: 3,
-            extract_strategy: Some(extract_lsb),
+            extract_strategy: Some(ExtractStrategy::Lsb),
             ..BitplaneOptions::default()
         };
 
@@ -548,10 +1230,10 @@ mod tests {
     #[test]
     fn test_extract_msb_simple() {
         // Host bytes with 3 secret bits embedded in MSB: 0b10100000 (bits=101) and 0b01100000 (bits=011)
-        let host = [0b10100000, 0b01100000];
+        let host: [u8; 2] = [0b10100000, 0b01100000];
         let options = BitplaneOptions {
             bits_to_operate: 3,
-            extract_strategy: Some(extract_msb),
+            extract_strategy: Some(ExtractStrategy::Msb),
             ..BitplaneOptions::default()
         };
 
@@ -567,10 +1249,10 @@ mod tests {
     #[test]
     fn test_extract_full_byte() {
         // Host bytes with full bytes embedded in LSB (8 bits)
-        let host = [0b10101010, 0b11001100];
+        let host: [u8; 2] = [0b10101010, 0b11001100];
         let options = BitplaneOptions {
             bits_to_operate: 8,
-            extract_strategy: Some(extract_lsb),
+            extract_strategy: Some(ExtractStrategy::Lsb),
             ..BitplaneOptions::default()
         };
 
@@ -596,8 +1278,9 @@ mod tests {
         // Bitplane configuration: use 2 bits, with LSB strategy
         let options = BitplaneOptions {
             bits_to_operate: 2,
-            embed_strategy: Some(embed_lsb),
-            extract_strategy: Some(extract_lsb),
+            embed_strategy: Some(EmbedStrategy::Lsb),
+            extract_strategy: Some(ExtractStrategy::Lsb),
+            ..BitplaneOptions::default()
         };
 
         // Linear traversal over host
@@ -625,4 +1308,349 @@ mod tests {
         // Check that the extracted message matches the original
         assert_eq!(extracted, message);
     }
+
+    // Generic sample tests
+
+    #[test]
+    fn test_embed_and_extract_u16_samples() {
+        // 16-bit PNG channel: hide 12 bits per sample, keep the 4 high bits
+        let mut host: Vec<u16> = vec![0xA000, 0xBFFF, 0xC123, 0xD456];
+        let options = BitplaneOptions::<u16> {
+            bits_to_operate: 12,
+            ..BitplaneOptions::default()
+        };
+
+        let locator = LinearTraversal;
+        let indices: Vec<usize> = locator.iter_indices(host.len()).collect();
+        bitplane_embed(&mut host, b"stego", &options, &indices).unwrap();
+
+        assert_eq!(host.iter().map(|s| s >> 12).collect::<Vec<_>>(), vec![0xA, 0xB, 0xC, 0xD]);
+        let extracted = bitplane_extract(&host, &options, &indices).unwrap();
+        assert_eq!(&extracted[..5], b"stego");
+    }
+
+    #[test]
+    fn test_embed_and_extract_signed_samples() {
+        // 16-bit audio, negative samples keep their sign with LSB embedding
+        let mut host: Vec<i16> = vec![-1000, -2, 0, 1, 500, -32768, 32767, -7];
+        let options = BitplaneOptions::<i16>::default();
+
+        let locator = LinearTraversal;
+        let indices: Vec<usize> = locator.iter_indices(host.len()).collect();
+        bitplane_embed(&mut host, &[0b1010_0101], &options, &indices).unwrap();
+
+        assert_eq!(host, vec![-999, -2, 1, 0, 500, -32767, 32766, -7]);
+        assert_eq!(bitplane_extract(&host, &options, &indices).unwrap(), vec![0b1010_0101]);
+    }
+
+    #[test]
+    fn test_embed_msb_i32_samples() {
+        // The MSB strategy works on the full 32-bit pattern of signed samples
+        let mut host: Vec<i32> = vec![0x0012_3456; 2];
+        let options = BitplaneOptions::<i32> {
+            bits_to_operate: 4,
+            embed_strategy: Some(EmbedStrategy::Msb),
+            extract_strategy: Some(ExtractStrategy::Msb),
+            ..BitplaneOptions::default()
+        };
+
+        let indices = vec![0, 1];
+        bitplane_embed(&mut host, &[0xF1], &options, &indices).unwrap();
+
+        assert_eq!(host, vec![0xF012_3456u32 as i32, 0x1012_3456]);
+        assert_eq!(bitplane_extract(&host, &options, &indices).unwrap(), vec![0xF1]);
+    }
+
+    #[test]
+    fn test_bits_to_operate_above_sample_width_error() {
+        let mut host: Vec<u16> = vec![0; 4];
+        let options = BitplaneOptions::<u16> {
+            bits_to_operate: 17,
+            ..BitplaneOptions::default()
+        };
+
+        let res = bitplane_embed(&mut host, b"x", &options, &[0, 1, 2, 3]);
+        assert_eq!(
+            res.unwrap_err(),
+            "options.bits_to_operate must be between 1 and 16"
+        );
+    }
+
+    #[test]
+    fn test_stego_method_interface() {
+        let mut host = vec![0u8; 16];
+        let options = BitplaneOptions {
+            bits_to_operate: 2,
+            ..BitplaneOptions::default()
+        };
+        let locator = LinearTraversal;
+
+        assert_eq!(options.capacity(&host, &locator), 32);
+        assert_eq!(options.embed(&mut host, b"ok", &locator).unwrap(), 16);
+        assert!(options.extract(&host, &locator).unwrap().starts_with(b"ok"));
+        assert!(options.embed(&mut host, b"too long", &locator).is_err());
+    }
+
+    // Locator and iterator entry points
+
+    #[test]
+    fn test_embed_with_locator_matches_slice_version() {
+        let heatmap = [0u8, 200, 10, 255, 255, 90, 180, 255, 1, 200, 220, 255];
+        let locator = HeatmapTraversal {
+            heatmap: &heatmap,
+            threshold: 100,
+        };
+        let options = BitplaneOptions {
+            bits_to_operate: 3,
+            ..BitplaneOptions::default()
+        };
+
+        let mut host_slice = vec![0x55u8; heatmap.len()];
+        let indices: Vec<usize> = locator.iter_indices(host_slice.len()).collect();
+        bitplane_embed(&mut host_slice, b"ab", &options, &indices).unwrap();
+
+        let mut host_lazy = vec![0x55u8; heatmap.len()];
+        bitplane_embed_with_locator(&mut host_lazy, b"ab", &options, &locator).unwrap();
+
+        assert_eq!(host_lazy, host_slice);
+        assert_eq!(
+            bitplane_extract_with_locator(&host_lazy, &options, &locator).unwrap(),
+            bitplane_extract(&host_slice, &options, &indices).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_embed_with_locator_insufficient_capacity_leaves_host_untouched() {
+        let mut host = vec![0xFFu8; 4];
+        let options = BitplaneOptions::default();
+
+        let res = bitplane_embed_with_locator(&mut host, b"Hi", &options, &LinearTraversal);
+        assert!(
+            res.unwrap_err()
+                .starts_with("Not enough space in host to hide the secret message")
+        );
+        assert_eq!(host, vec![0xFF; 4]);
+    }
+
+    #[test]
+    fn test_embed_iter_insufficient_capacity_error() {
+        let mut host = vec![0u8; 8];
+        let options = BitplaneOptions::default();
+
+        let res = bitplane_embed_iter(&mut host, b"Hi", &options, 0..8);
+        assert_eq!(
+            res.unwrap_err(),
+            "Not enough space in host to hide the secret message: capacity=8 bits, message=16 bits"
+        );
+    }
+
+    #[test]
+    fn test_embed_with_report_matches_host_changes() {
+        let original: Vec<i16> = vec![-3, 7, 100, -128, 0, 55, 12, -1];
+        let mut host = original.clone();
+        let options = BitplaneOptions::<i16> { bits_to_operate: 2, ..BitplaneOptions::default() };
+
+        let report = bitplane_embed_with_report(&mut host, b"Z", &options, &LinearTraversal).unwrap();
+
+        let changed: Vec<usize> = (0..host.len()).filter(|&i| host[i] != original[i]).collect();
+        let distortion: u64 = original
+            .iter()
+            .zip(&host)
+            .map(|(&a, &b)| (a as i64 - b as i64).unsigned_abs())
+            .sum();
+        assert_eq!(report.bits_embedded, 8);
+        assert_eq!(report.positions_used, vec![0, 1, 2, 3]);
+        assert_eq!(report.positions_changed, changed);
+        assert_eq!(report.total_abs_distortion, distortion);
+        assert_eq!(report.last_index, Some(3));
+        assert!(report.bin_usage.is_empty());
+
+        // The report variant embeds exactly like the plain one
+        let mut plain = original.clone();
+        bitplane_embed_with_locator(&mut plain, b"Z", &options, &LinearTraversal).unwrap();
+        assert_eq!(plain, host);
+    }
+
+    #[test]
+    fn test_bit_orders() {
+        // LSB-first secret: the first host sample receives bit 0 of the byte
+        let mut host = vec![0u8; 8];
+        let options = BitplaneOptions {
+            secret_bit_order: BitOrder::LsbFirst,
+            ..BitplaneOptions::default()
+        };
+        bitplane_embed_with_locator(&mut host, &[0b0000_0011], &options, &LinearTraversal).unwrap();
+        assert_eq!(host, vec![1, 1, 0, 0, 0, 0, 0, 0]);
+
+        // LSB-first chunk: the first secret bit goes to the lowest bit of the chunk
+        let mut host = vec![0u8; 2];
+        let options = BitplaneOptions {
+            bits_to_operate: 4,
+            chunk_bit_order: BitOrder::LsbFirst,
+            ..BitplaneOptions::default()
+        };
+        bitplane_embed_with_locator(&mut host, &[0b1100_1000], &options, &LinearTraversal).unwrap();
+        assert_eq!(host, vec![0b0011, 0b0001]);
+
+        // Every combination round-trips, including a partial last chunk
+        for secret_bit_order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            for chunk_bit_order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+                let options = BitplaneOptions::<u16> {
+                    bits_to_operate: 3,
+                    secret_bit_order,
+                    chunk_bit_order,
+                    ..BitplaneOptions::default()
+                };
+                let mut host = vec![0xAAAAu16; 6];
+                bitplane_embed_with_locator(&mut host, b"Ok", &options, &LinearTraversal).unwrap();
+                let extracted =
+                    bitplane_extract_with_locator(&host, &options, &LinearTraversal).unwrap();
+                assert_eq!(&extracted[..2], b"Ok");
+            }
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[test]
+    fn test_random_fill_needs_std() {
+        let options = BitplaneOptions {
+            remainder_fill: RemainderFill::Random,
+            ..BitplaneOptions::default()
+        };
+        let mut host = vec![0u8; 64];
+        assert_eq!(
+            bitplane_embed_with_locator(&mut host, b"x", &options, &LinearTraversal).unwrap_err(),
+            "RemainderFill::Random needs the std feature"
+        );
+        assert!(host.iter().all(|&b| b == 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_remainder_fill() {
+        let prefix = LengthPrefix::default();
+        let embed = |remainder_fill: RemainderFill| {
+            let options = BitplaneOptions {
+                bits_to_operate: 3,
+                remainder_fill,
+                ..BitplaneOptions::default()
+            };
+            let mut host = vec![0u8; 2000];
+            let report = bitplane_embed_with_report(
+                &mut host,
+                &prefix.frame(b"short").unwrap(),
+                &options,
+                &LinearTraversal,
+            )
+            .unwrap();
+            let extracted = bitplane_extract_framed(&host, &options, &LinearTraversal, &prefix);
+            assert_eq!(extracted.unwrap(), b"short");
+            assert_eq!(report.bits_embedded, 72);
+            (host, report)
+        };
+
+        // 72 bits in 24 samples: everything after is untouched
+        let (kept, report) = embed(RemainderFill::Keep);
+        assert_eq!(report.positions_used.len(), 24);
+        assert!(kept[24..].iter().all(|&b| b == 0));
+
+        let (random, report) = embed(RemainderFill::Random);
+        assert_eq!(report.positions_used.len(), 2000);
+        assert_eq!(random[..24], kept[..24]);
+        let changed = random[24..].iter().filter(|&&b| b != 0).count();
+        assert!(changed.abs_diff(1976 * 7 / 8) < 100);
+        assert!(random.iter().all(|&b| b < 8));
+
+        // A keyed fill is reproducible, and differs from another key and from random
+        let (keyed, _) = embed(RemainderFill::Keystream([7; 32]));
+        assert_eq!(keyed, embed(RemainderFill::Keystream([7; 32])).0);
+        assert_ne!(keyed, embed(RemainderFill::Keystream([8; 32])).0);
+        assert_ne!(keyed, random);
+
+        // The same key fills differently for another secret
+        let options = BitplaneOptions {
+            bits_to_operate: 3,
+            remainder_fill: RemainderFill::Keystream([7; 32]),
+            ..BitplaneOptions::default()
+        };
+        let mut other = vec![0u8; 2000];
+        let framed = prefix.frame(b"other").unwrap();
+        bitplane_embed_with_locator(&mut other, &framed, &options, &LinearTraversal).unwrap();
+        let same = (24..2000).filter(|&i| other[i] == keyed[i]).count();
+        assert!(same < 1976 / 4);
+
+        // An unbounded iterator stops after host.len() filled indices
+        let mut host = vec![0u8; 100];
+        bitplane_embed_iter(&mut host, b"x", &options, 0..).unwrap();
+        assert_eq!(bitplane_extract_iter(&host, &options, 0..3).unwrap()[0], b'x');
+        assert!(host[3..].iter().filter(|&&b| b != 0).count() > 97 / 2);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let mut rng = crate::test_utils::TestRng::new(48);
+        let host: Vec<u16> = (0..150_000).map(|_| rng.next_u64() as u16).collect();
+        // Shuffled indices with some out of the host, over several chunks
+        let mut indices = rng.permutation(host.len());
+        indices.truncate(140_000);
+        indices.extend([host.len(), host.len() + 7]);
+        indices.rotate_right(1);
+
+        for (bits_to_operate, remainder_fill) in [
+            (3, RemainderFill::Keep),
+            (5, RemainderFill::Keystream([1; 32])),
+            (16, RemainderFill::Keep),
+        ] {
+            let options = BitplaneOptions::<u16> {
+                bits_to_operate,
+                remainder_fill,
+                ..BitplaneOptions::default()
+            };
+            // The filling starts in the second chunk and goes on in the third one
+            let secret = rng.bytes(100_000 * bits_to_operate as usize / 8 - 1);
+
+            let mut sequential = host.clone();
+            bitplane_embed(&mut sequential, &secret, &options, &indices).unwrap();
+            let mut parallel = host.clone();
+            bitplane_embed_parallel(&mut parallel, &secret, &options, &indices).unwrap();
+            assert_eq!(parallel, sequential, "{} bits", bits_to_operate);
+
+            assert_eq!(
+                bitplane_extract_parallel(&parallel, &options, &indices).unwrap(),
+                bitplane_extract(&sequential, &options, &indices).unwrap()
+            );
+        }
+
+        // Repeated indices take the sequential path
+        let options = BitplaneOptions::<u16>::default();
+        let repeated = [0, 1, 0, 2, 1, 3, 4, 5, 6, 7];
+        let mut sequential = host.clone();
+        bitplane_embed(&mut sequential, b"x", &options, &repeated).unwrap();
+        let mut parallel = host.clone();
+        bitplane_embed_parallel(&mut parallel, b"x", &options, &repeated).unwrap();
+        assert_eq!(parallel, sequential);
+        assert!(bitplane_embed_parallel(&mut parallel, b"xy", &options, &repeated).is_err());
+    }
+
+    #[test]
+    fn test_extract_framed_errors() {
+        let options = BitplaneOptions::default();
+        let prefix = LengthPrefix::default();
+        let mut host = vec![0u8; 64];
+        bitplane_embed_framed(&mut host, b"Hi", &options, &LinearTraversal, &prefix).unwrap();
+        assert_eq!(
+            bitplane_extract_framed(&host, &options, &LinearTraversal, &prefix).unwrap(),
+            b"Hi"
+        );
+
+        // The announced length does not fit in the host
+        host[..32].iter_mut().for_each(|b| *b = 1);
+        assert!(
+            bitplane_extract_framed(&host, &options, &LinearTraversal, &prefix)
+                .unwrap_err()
+                .starts_with("Length prefix announces")
+        );
+        assert!(bitplane_extract_framed(&host[..16], &options, &LinearTraversal, &prefix).is_err());
+    }
 }
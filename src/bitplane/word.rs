@@ -0,0 +1,500 @@
+//! Word-level fast path of [`bitplane_embed`](super::bitplane_embed) and
+//! [`bitplane_extract`](super::bitplane_extract).
+//!
+//! The generic path reads and writes the secret one bit at a time and goes through the
+//! strategy function pointers. When the strategy is [`EmbedStrategy::Lsb`] /
+//! [`EmbedStrategy::Msb`] (or [`ExtractStrategy::Lsb`] / [`ExtractStrategy::Msb`]), the
+//! secret is instead consumed and produced in 64-bit words, and every sample is updated
+//! with a single mask. Contiguous or strided index slices are walked with `step_by` and no
+//! bounds check per index; other slices and index iterators are visited one index at a
+//! time, skipping the indices outside the host like the generic path. The result is the
+//! same as the generic path.
+//!
+//! The loops are plain scalar code: there is no SIMD, and no `unsafe`.
+
+use alloc::vec::Vec;
+
+use super::{BitplaneOptions, EmbedStrategy, ExtractStrategy, RemainderFill};
+use crate::bit_order::BitOrder;
+use crate::sample::Sample;
+
+/// Samples visited by indices of the form `start + i * step`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stride {
+    start: usize,
+    step: usize,
+    count: usize,
+}
+
+impl Stride {
+    /// Returns the stride of `indices` if they are strided and all inside the host.
+    fn of(indices: &[usize], len: usize) -> Option<Self> {
+        let (&start, &last) = (indices.first()?, indices.last()?);
+        let step = match indices.get(1) {
+            Some(&second) => second.checked_sub(start)?,
+            None => 1,
+        };
+        let strided = indices.iter().enumerate().all(|(i, &idx)| {
+            i.checked_mul(step)
+                .and_then(|offset| start.checked_add(offset))
+                == Some(idx)
+        });
+        (step > 0 && last < len && strided).then_some(Self {
+            start,
+            step,
+            count: indices.len(),
+        })
+    }
+
+    fn samples<'a, S>(&self, host: &'a mut [S]) -> impl Iterator<Item = &'a mut S> {
+        host[self.start..]
+            .iter_mut()
+            .step_by(self.step)
+            .take(self.count)
+    }
+}
+
+/// Returns the position of the lowest bit of the plane written by the built-in embedding
+/// strategies, or `None` for a custom strategy.
+fn embed_shift<S: Sample>(options: &BitplaneOptions<S>) -> Option<u32> {
+    match options.embed_strategy? {
+        EmbedStrategy::Lsb => Some(0),
+        EmbedStrategy::Msb => Some((S::BITS - options.bits_to_operate) as u32),
+        EmbedStrategy::Custom(_) => None,
+    }
+}
+
+/// Same as [`embed_shift`], for the extraction strategies.
+fn extract_shift<S: Sample>(options: &BitplaneOptions<S>) -> Option<u32> {
+    match options.extract_strategy? {
+        ExtractStrategy::Lsb => Some(0),
+        ExtractStrategy::Msb => Some((S::BITS - options.bits_to_operate) as u32),
+        ExtractStrategy::Custom(_) => None,
+    }
+}
+
+/// Converts between a chunk in stream order (first bit in the highest position) and a
+/// chunk in `order`. The conversion is its own inverse.
+fn reorder(chunk: u64, bits: u32, order: BitOrder) -> u64 {
+    match order {
+        BitOrder::MsbFirst => chunk,
+        BitOrder::LsbFirst => chunk.reverse_bits() >> (64 - bits),
+    }
+}
+
+/// Converts 8 bytes between MSB-first and LSB-first serialization.
+fn reorder_bytes(word: u64, order: BitOrder) -> u64 {
+    match order {
+        BitOrder::MsbFirst => word,
+        BitOrder::LsbFirst => word.reverse_bits().swap_bytes(),
+    }
+}
+
+/// Reads the secret in chunks of up to 64 bits, loading it 8 bytes at a time. Bits past
+/// the end of the secret are 0.
+struct WordReader<'a> {
+    secret: &'a [u8],
+    position: usize,
+    buffer: u128,
+    buffered: u32,
+    order: BitOrder,
+}
+
+impl<'a> WordReader<'a> {
+    fn new(secret: &'a [u8], order: BitOrder) -> Self {
+        Self {
+            secret,
+            position: 0,
+            buffer: 0,
+            buffered: 0,
+            order,
+        }
+    }
+
+    fn take(&mut self, bits: u32) -> u64 {
+        if self.buffered < bits {
+            let mut word = [0u8; 8];
+            let start = self.position.min(self.secret.len());
+            let end = (start + 8).min(self.secret.len());
+            word[..end - start].copy_from_slice(&self.secret[start..end]);
+            self.position += 8;
+            let word = reorder_bytes(u64::from_be_bytes(word), self.order);
+            self.buffer = self.buffer << 64 | word as u128;
+            self.buffered += 64;
+        }
+        self.buffered -= bits;
+        let chunk = (self.buffer >> self.buffered) as u64 & u64::low_mask(bits as u8);
+        self.buffer &= (1u128 << self.buffered) - 1;
+        chunk
+    }
+}
+
+/// Packs chunks of up to 64 bits, storing them 8 bytes at a time. A partial last byte is
+/// padded with zeros.
+struct WordWriter {
+    bytes: Vec<u8>,
+    buffer: u128,
+    buffered: u32,
+    order: BitOrder,
+}
+
+impl WordWriter {
+    fn new(capacity: usize, order: BitOrder) -> Self {
+        Self {
+            bytes: Vec::with_capacity(capacity),
+            buffer: 0,
+            buffered: 0,
+            order,
+        }
+    }
+
+    fn push(&mut self, chunk: u64, bits: u32) {
+        self.buffer = self.buffer << bits | chunk as u128;
+        self.buffered += bits;
+        if self.buffered >= 64 {
+            self.buffered -= 64;
+            let word = reorder_bytes((self.buffer >> self.buffered) as u64, self.order);
+            self.bytes.extend_from_slice(&word.to_be_bytes());
+            self.buffer &= (1u128 << self.buffered) - 1;
+        }
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        if self.buffered > 0 {
+            let word = reorder_bytes((self.buffer << (64 - self.buffered)) as u64, self.order);
+            let used = self.buffered.div_ceil(8) as usize;
+            self.bytes.extend_from_slice(&word.to_be_bytes()[..used]);
+        }
+        self.bytes
+    }
+}
+
+/// Writes the secret chunks into the plane of the built-in embedding strategies.
+struct PlaneWriter<'a, S: Sample> {
+    reader: WordReader<'a>,
+    mask: u64,
+    shift: u32,
+    bits: u32,
+    options: &'a BitplaneOptions<S>,
+}
+
+impl<'a, S: Sample> PlaneWriter<'a, S> {
+    /// Returns `None` unless the options allow the word-level path.
+    fn new(secret: &'a [u8], options: &'a BitplaneOptions<S>) -> Option<Self> {
+        if options.remainder_fill != RemainderFill::Keep {
+            return None;
+        }
+        let shift = embed_shift(options)?;
+        let bits = options.bits_to_operate as u32;
+        Some(Self {
+            reader: WordReader::new(secret, options.secret_bit_order),
+            mask: S::low_mask(bits as u8) << shift,
+            shift,
+            bits,
+            options,
+        })
+    }
+
+    fn write(&mut self, sample: &mut S) {
+        let chunk = reorder(
+            self.reader.take(self.bits),
+            self.bits,
+            self.options.chunk_bit_order,
+        );
+        *sample = S::from_raw((sample.to_raw() & !self.mask) | (chunk << self.shift));
+    }
+}
+
+/// Reads the secret chunks from the plane of the built-in extraction strategies.
+struct PlaneReader<'a, S: Sample> {
+    writer: WordWriter,
+    mask: u64,
+    shift: u32,
+    bits: u32,
+    options: &'a BitplaneOptions<S>,
+}
+
+impl<'a, S: Sample> PlaneReader<'a, S> {
+    /// Returns `None` unless the options allow the word-level path.
+    fn new(options: &'a BitplaneOptions<S>, capacity: usize) -> Option<Self> {
+        let shift = extract_shift(options)?;
+        let bits = options.bits_to_operate as u32;
+        Some(Self {
+            writer: WordWriter::new(capacity, options.secret_bit_order),
+            mask: S::low_mask(bits as u8),
+            shift,
+            bits,
+            options,
+        })
+    }
+
+    fn read(&mut self, sample: S) {
+        let chunk = (sample.to_raw() >> self.shift) & self.mask;
+        let chunk = reorder(chunk, self.bits, self.options.chunk_bit_order);
+        self.writer.push(chunk, self.bits);
+    }
+}
+
+/// Embeds the secret on the word-level path if the options allow it, and returns whether
+/// it did. The options must have been validated, and the indices must hold the secret.
+pub(super) fn try_embed<S: Sample>(
+    host: &mut [S],
+    secret: &[u8],
+    options: &BitplaneOptions<S>,
+    indices: &[usize],
+) -> bool {
+    let Some(mut plane) = PlaneWriter::new(secret, options) else {
+        return false;
+    };
+    // Only the samples that receive secret bits are visited
+    let used = (secret.len() * 8)
+        .div_ceil(plane.bits as usize)
+        .min(indices.len());
+    match Stride::of(&indices[..used], host.len()) {
+        Some(stride) => stride.samples(host).for_each(|sample| plane.write(sample)),
+        None => {
+            embed_indices(host, secret, &mut plane, indices.iter().copied());
+        }
+    }
+    true
+}
+
+/// Embeds the secret on the word-level path at the positions yielded by an iterator, and
+/// returns the number of secret bits embedded. The iterator is handed back untouched if
+/// the options do not allow the word-level path.
+pub(super) fn try_embed_iter<S: Sample, I: Iterator<Item = usize>>(
+    host: &mut [S],
+    secret: &[u8],
+    options: &BitplaneOptions<S>,
+    indices: I,
+) -> Result<usize, I> {
+    match PlaneWriter::new(secret, options) {
+        Some(mut plane) => Ok(embed_indices(host, secret, &mut plane, indices)),
+        None => Err(indices),
+    }
+}
+
+/// Visits the indices until the secret is embedded, skipping the ones outside the host.
+fn embed_indices<S: Sample>(
+    host: &mut [S],
+    secret: &[u8],
+    plane: &mut PlaneWriter<S>,
+    indices: impl Iterator<Item = usize>,
+) -> usize {
+    let total_bits = secret.len() * 8;
+    let mut bit_index = 0;
+    for idx in indices {
+        if bit_index >= total_bits {
+            break;
+        }
+        if let Some(sample) = host.get_mut(idx) {
+            plane.write(sample);
+            bit_index += plane.bits as usize;
+        }
+    }
+    bit_index.min(total_bits)
+}
+
+/// Extracts the secret on the word-level path if the options allow it.
+pub(super) fn try_extract<S: Sample>(
+    host: &[S],
+    options: &BitplaneOptions<S>,
+    indices: &[usize],
+) -> Option<Vec<u8>> {
+    let total_bytes = (indices.len() * options.bits_to_operate as usize).div_ceil(8);
+    let mut plane = PlaneReader::new(options, total_bytes)?;
+    match Stride::of(indices, host.len()) {
+        Some(stride) => host[stride.start..]
+            .iter()
+            .step_by(stride.step)
+            .take(stride.count)
+            .for_each(|&sample| plane.read(sample)),
+        None => indices
+            .iter()
+            .filter_map(|&idx| host.get(idx))
+            .for_each(|&sample| plane.read(sample)),
+    }
+    Some(plane.writer.into_bytes())
+}
+
+/// Extracts the secret on the word-level path at the positions yielded by an iterator,
+/// skipping the ones outside the host. The iterator is handed back untouched if the
+/// options do not allow the word-level path.
+pub(super) fn try_extract_iter<S: Sample, I: Iterator<Item = usize>>(
+    host: &[S],
+    options: &BitplaneOptions<S>,
+    indices: I,
+) -> Result<Vec<u8>, I> {
+    let Some(mut plane) = PlaneReader::new(options, 0) else {
+        return Err(indices);
+    };
+    indices
+        .filter_map(|idx| host.get(idx))
+        .for_each(|&sample| plane.read(sample));
+    Ok(plane.writer.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitplane::{embed_bits, extract_bits};
+    use crate::test_utils::TestRng;
+
+    /// Runs both paths on the same host and checks that they agree.
+    fn check<S: Sample>(host: &[S], options: &BitplaneOptions<S>, indices: &[usize], seed: u64) {
+        let valid = indices.iter().filter(|&&idx| idx < host.len()).count();
+        let capacity = valid * options.bits_to_operate as usize / 8;
+        let secret = TestRng::new(seed).bytes(capacity - seed as usize % 3);
+
+        let mut generic = host.to_vec();
+        let embed_fn = options.embed_strategy.unwrap().function();
+        embed_bits(
+            &mut generic,
+            &secret,
+            embed_fn,
+            options,
+            indices.iter().copied(),
+            None,
+        );
+        let mut fast = host.to_vec();
+        assert!(try_embed(&mut fast, &secret, options, indices));
+        assert_eq!(fast, generic, "{:?}", options);
+
+        let mut fast_iter = host.to_vec();
+        let embedded = try_embed_iter(&mut fast_iter, &secret, options, indices.iter().copied());
+        assert_eq!(embedded.ok(), Some(secret.len() * 8));
+        assert_eq!(fast_iter, generic, "{:?}", options);
+
+        let extract_fn = options.extract_strategy.unwrap().function();
+        let expected = extract_bits(&generic, extract_fn, options, indices.iter().copied());
+        assert_eq!(try_extract(&fast, options, indices).unwrap(), expected);
+        let extracted = try_extract_iter(&fast, options, indices.iter().copied());
+        assert_eq!(extracted.ok(), Some(expected));
+    }
+
+    #[test]
+    fn test_matches_generic_path() {
+        let mut rng = TestRng::new(49);
+        let bytes = rng.bytes(4099);
+        let words: Vec<i32> = (0..4099).map(|_| rng.next_u64() as i32).collect();
+        let contiguous: Vec<usize> = (0..4099).collect();
+        let strided: Vec<usize> = (5..4099).step_by(3).collect();
+        // Not strided, with indices outside the host
+        let mut shuffled = rng.permutation(4099);
+        shuffled.truncate(3000);
+        shuffled[7] = 5000;
+        shuffled[2500] = usize::MAX;
+
+        let mut seed = 0;
+        for secret_bit_order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            for chunk_bit_order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+                for msb in [false, true] {
+                    for indices in [&contiguous, &strided, &shuffled] {
+                        for bits_to_operate in [1, 3, 8] {
+                            let mut options = BitplaneOptions::<u8> {
+                                bits_to_operate,
+                                secret_bit_order,
+                                chunk_bit_order,
+                                ..BitplaneOptions::default()
+                            };
+                            if msb {
+                                options.embed_strategy = Some(EmbedStrategy::Msb);
+                                options.extract_strategy = Some(ExtractStrategy::Msb);
+                            }
+                            seed += 1;
+                            check(&bytes, &options, indices, seed);
+                        }
+                        for bits_to_operate in [5, 17, 32] {
+                            let options = BitplaneOptions::<i32> {
+                                bits_to_operate,
+                                secret_bit_order,
+                                chunk_bit_order,
+                                embed_strategy: Some(if msb {
+                                    EmbedStrategy::Msb
+                                } else {
+                                    EmbedStrategy::Lsb
+                                }),
+                                extract_strategy: Some(if msb {
+                                    ExtractStrategy::Msb
+                                } else {
+                                    ExtractStrategy::Lsb
+                                }),
+                                ..BitplaneOptions::default()
+                            };
+                            seed += 1;
+                            check(&words, &options, indices, seed);
+                        }
+                    }
+                }
+            }
+        }
+
+        let wide: Vec<u64> = (0..300).map(|_| rng.next_u64()).collect();
+        let options = BitplaneOptions::<u64> {
+            bits_to_operate: 64,
+            ..BitplaneOptions::default()
+        };
+        check(&wide, &options, &(0..300).collect::<Vec<_>>(), 1);
+    }
+
+    #[test]
+    fn test_fallback_to_generic_path() {
+        let host = vec![0u8; 64];
+        assert_eq!(
+            Stride::of(&[4, 6, 8], 64),
+            Some(Stride {
+                start: 4,
+                step: 2,
+                count: 3
+            })
+        );
+        for indices in [&[4, 6, 9][..], &[4, 4, 4], &[8, 6, 4], &[60, 62, 64]] {
+            assert_eq!(Stride::of(indices, host.len()), None, "{:?}", indices);
+        }
+
+        // Custom strategies and filling always take the generic path
+        let custom = BitplaneOptions::<u8> {
+            embed_strategy: Some(EmbedStrategy::Custom(|host, secret, _| host ^ secret)),
+            extract_strategy: Some(ExtractStrategy::Custom(|host, _| host & 1)),
+            ..BitplaneOptions::default()
+        };
+        let indices: Vec<usize> = (0..64).collect();
+        assert!(!try_embed(&mut host.clone(), b"ab", &custom, &indices));
+        assert!(try_embed_iter(&mut host.clone(), b"ab", &custom, 0..64).is_err());
+        assert!(try_extract(&host, &custom, &indices).is_none());
+        assert!(try_extract_iter(&host, &custom, 0..64).is_err());
+        let filled = BitplaneOptions::<u8> {
+            remainder_fill: RemainderFill::Keystream([0; 32]),
+            ..BitplaneOptions::default()
+        };
+        assert!(!try_embed(&mut host.clone(), b"ab", &filled, &indices));
+        assert!(try_embed_iter(&mut host.clone(), b"ab", &filled, 0..64).is_err());
+    }
+
+    #[test]
+    fn test_huge_indices_match_generic_path() {
+        // `start + i * step` overflows for these indices
+        for indices in [
+            &[0, 1 << 63, 5, 1, 2, 3, 4, 6, 7, 8][..],
+            &[1, usize::MAX, 2, 3, 4, 5, 6, 7, 8, 9],
+        ] {
+            assert_eq!(Stride::of(indices, 16), None, "{:?}", indices);
+
+            let lsb = BitplaneOptions::<u8>::default();
+            let custom = BitplaneOptions::<u8> {
+                embed_strategy: Some(EmbedStrategy::Custom(crate::bitplane::embed_lsb)),
+                extract_strategy: Some(ExtractStrategy::Custom(crate::bitplane::extract_lsb)),
+                ..BitplaneOptions::default()
+            };
+            let mut fast = [0u8; 16];
+            let mut generic = [0u8; 16];
+            crate::bitplane::bitplane_embed(&mut fast, b"a", &lsb, indices).unwrap();
+            crate::bitplane::bitplane_embed(&mut generic, b"a", &custom, indices).unwrap();
+            assert_eq!(fast, generic);
+            assert_eq!(
+                crate::bitplane::bitplane_extract(&fast, &lsb, indices).unwrap(),
+                crate::bitplane::bitplane_extract(&generic, &custom, indices).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,431 @@
+//! zlib streams (RFC 1950) with the DEFLATE format (RFC 1951), shared by the palette PNG
+//! carrier of [`crate::palette`] and the compressed payloads of [`crate::text::whitespace`].
+//!
+//! The decoder supports the three block types. The encoder uses LZ77 with hash chains and
+//! the fixed Huffman codes, which is enough for the index data of palette images and for
+//! short texts.
+
+use alloc::{string::String, vec, vec::Vec};
+
+/// Base lengths of the length symbols 257 to 285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// Base distances of the distance symbols 0 to 29.
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order of the code length code lengths in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const WINDOW: usize = 32768;
+const MAX_MATCH: usize = 258;
+const MIN_MATCH: usize = 3;
+/// Longest hash chain followed when looking for a match.
+const MAX_CHAIN: usize = 64;
+
+/// Adler-32 checksum of `data`.
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// Decompresses a zlib stream.
+pub(crate) fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 6 {
+        return Err("Invalid zlib stream: too short".into());
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
+        return Err("Invalid zlib stream: bad header".into());
+    }
+    if flg & 0x20 != 0 {
+        return Err("Invalid zlib stream: preset dictionaries are not supported".into());
+    }
+
+    let mut reader = BitReader {
+        data: &data[2..],
+        pos: 0,
+        bit: 0,
+    };
+    let out = inflate(&mut reader)?;
+    let end = 2 + reader.pos + (reader.bit > 0) as usize;
+    let checksum = data
+        .get(end..end + 4)
+        .ok_or("Invalid zlib stream: missing checksum")?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&out) {
+        return Err("Invalid zlib stream: bad checksum".into());
+    }
+    Ok(out)
+}
+
+/// Compresses `data` into a zlib stream.
+pub(crate) fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        out: vec![0x78, 0x9C],
+        acc: 0,
+        count: 0,
+    };
+    // A single final block with the fixed codes
+    writer.put(1, 1);
+    writer.put(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << 15];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let hash = |i: usize| {
+        ((data[i] as usize) << 10 ^ (data[i + 1] as usize) << 5 ^ data[i + 2] as usize) & 0x7FFF
+    };
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            prev[i % WINDOW] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        // Longest match in the window, following the hash chain
+        let (mut best_len, mut best_dist) = (0, 0);
+        if i + MIN_MATCH <= data.len() {
+            let mut candidate = head[hash(i)];
+            let max_len = MAX_MATCH.min(data.len() - i);
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || i - candidate > WINDOW - 1 {
+                    break;
+                }
+                let len = (0..max_len)
+                    .take_while(|&k| data[candidate + k] == data[i + k])
+                    .count();
+                if len > best_len {
+                    (best_len, best_dist) = (len, i - candidate);
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            writer.length(best_len);
+            writer.distance(best_dist);
+            for k in i..i + best_len {
+                insert(k, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            writer.fixed_symbol(data[i] as u16);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    writer.fixed_symbol(256);
+
+    let mut out = writer.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Reads the bits of a DEFLATE stream, least significant bit first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u8,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u8) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or("Invalid zlib stream: unexpected end of data")?;
+            value |= (((byte >> self.bit) & 1) as u32) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+/// Canonical Huffman code of a DEFLATE block.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols = Vec::new();
+        for len in 1..16 {
+            symbols.extend(
+                (0..lengths.len())
+                    .filter(|&s| lengths[s] as usize == len)
+                    .map(|s| s as u16),
+            );
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("Invalid zlib stream: bad Huffman code".into())
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [8u8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+
+    let mut lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[i] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&lengths);
+
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let symbol = code_length_code.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let last = *lengths
+                    .last()
+                    .ok_or("Invalid zlib stream: repeat without a previous length")?;
+                (last, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(core::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literals + distances {
+        return Err("Invalid zlib stream: too many code lengths".into());
+    }
+    Ok((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+fn inflate(reader: &mut BitReader) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = reader
+                    .data
+                    .get(reader.pos..reader.pos + 4)
+                    .ok_or("Invalid zlib stream: unexpected end of data")?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                if len != !u16::from_le_bytes([header[2], header[3]]) as usize {
+                    return Err("Invalid zlib stream: bad stored block length".into());
+                }
+                reader.pos += 4;
+                let block = reader
+                    .data
+                    .get(reader.pos..reader.pos + len)
+                    .ok_or("Invalid zlib stream: unexpected end of data")?;
+                out.extend_from_slice(block);
+                reader.pos += len;
+            }
+            kind @ (1 | 2) => {
+                let (literals, distances) = if kind == 1 {
+                    fixed_codes()
+                } else {
+                    dynamic_codes(reader)?
+                };
+                loop {
+                    let symbol = literals.decode(reader)? as usize;
+                    if symbol < 256 {
+                        out.push(symbol as u8);
+                        continue;
+                    }
+                    if symbol == 256 {
+                        break;
+                    }
+                    let i = symbol - 257;
+                    if i >= LENGTH_BASE.len() {
+                        return Err("Invalid zlib stream: bad length symbol".into());
+                    }
+                    let len = LENGTH_BASE[i] as usize + reader.bits(LENGTH_EXTRA[i])? as usize;
+                    let d = distances.decode(reader)? as usize;
+                    if d >= DIST_BASE.len() {
+                        return Err("Invalid zlib stream: bad distance symbol".into());
+                    }
+                    let dist = DIST_BASE[d] as usize + reader.bits(DIST_EXTRA[d])? as usize;
+                    if dist > out.len() {
+                        return Err("Invalid zlib stream: distance too far back".into());
+                    }
+                    let start = out.len() - dist;
+                    for k in 0..len {
+                        out.push(out[start + k]);
+                    }
+                }
+            }
+            _ => return Err("Invalid zlib stream: bad block type".into()),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// Writes the bits of a DEFLATE stream, least significant bit first.
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, count: u8) {
+        self.acc |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, whose bits are stored most significant bit first.
+    fn code(&mut self, code: u32, len: u8) {
+        let reversed = code.reverse_bits() >> (32 - len);
+        self.put(reversed, len);
+    }
+
+    /// Writes a literal/length symbol with the fixed code.
+    fn fixed_symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xC0 + symbol - 280, 8),
+        }
+    }
+
+    fn length(&mut self, len: usize) {
+        let i = LENGTH_BASE
+            .iter()
+            .rposition(|&base| base as usize <= len)
+            .unwrap();
+        self.fixed_symbol(257 + i as u16);
+        self.put((len - LENGTH_BASE[i] as usize) as u32, LENGTH_EXTRA[i]);
+    }
+
+    fn distance(&mut self, dist: usize) {
+        let i = DIST_BASE
+            .iter()
+            .rposition(|&base| base as usize <= dist)
+            .unwrap();
+        self.code(i as u32, 5);
+        self.put((dist - DIST_BASE[i] as usize) as u32, DIST_EXTRA[i]);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestRng;
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = TestRng::new(4);
+        let inputs = [
+            Vec::new(),
+            b"a".to_vec(),
+            vec![7u8; 100_000],
+            rng.bytes(5000),
+            (0..70_000).map(|i| (i % 251 + i / 1000) as u8).collect(),
+        ];
+        for data in inputs {
+            let compressed = zlib_compress(&data);
+            assert_eq!(zlib_decompress(&compressed).unwrap(), data);
+        }
+        assert!(zlib_compress(&[7u8; 100_000]).len() < 1000);
+    }
+
+    #[test]
+    fn test_stored_block_and_errors() {
+        // Stored block, as written by zlib at level 0
+        let stream = [
+            0x78, 0x01, 0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c', 0x02, 0x4D, 0x01, 0x27,
+        ];
+        assert_eq!(zlib_decompress(&stream).unwrap(), b"abc");
+
+        let mut bad_checksum = stream;
+        bad_checksum[13] ^= 1;
+        assert!(zlib_decompress(&bad_checksum).is_err());
+        assert!(zlib_decompress(&stream[..9]).is_err());
+        assert!(zlib_decompress(b"not zlib").is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Plausible deniability: a decoy payload, and optionally a hidden one.
+//!
+//! The carrier is split into two [`multi_slot`](crate::multi_slot) slots. The decoy
+//! password opens the first one; the hidden password opens the second one. When there is no
+//! hidden payload, the second slot is filled with random bits instead, so a stego carrier
+//! always looks the same: the decoy slot, then random-looking bits. Someone who obtains the
+//! decoy password cannot show whether those bits are a hidden payload or filling.
+//!
+//! The decoy slot is built in the same way whether or not a hidden payload exists, and both
+//! passwords go through the same extraction, which tries both slots.
+//!
+//! Each layer has its own random salt, and the passwords go through Argon2id
+//! ([`KdfParams`]): see the [threat model](crate::multi_slot#threat-model) of
+//! `multi_slot`. Deniability does not hold against someone who can guess the hidden
+//! password, so it should be a long passphrase.
+
+use crate::bitplane::BitplaneOptions;
+use crate::embedding_locator::EmbeddingLocator;
+use crate::multi_slot::{
+    KdfParams, SlotLayout, SlotPayload, multi_slot_capacity, multi_slot_embed, multi_slot_extract,
+};
+use crate::sample::Sample;
+
+/// Relative sizes of the decoy and hidden layers, and password hashing settings.
+///
+/// The layout must be the same for embedding and extraction. Keeping the default makes it
+/// part of the tool rather than something a user has to reveal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeniableLayout {
+    /// Relative size of the decoy layer.
+    pub decoy_share: usize,
+    /// Relative size of the hidden layer.
+    pub hidden_share: usize,
+    /// Parameters of the password hashing.
+    pub kdf: KdfParams,
+}
+
+impl Default for DeniableLayout {
+    /// Returns two layers of equal size.
+    fn default() -> Self {
+        Self {
+            decoy_share: 1,
+            hidden_share: 1,
+            kdf: KdfParams::default(),
+        }
+    }
+}
+
+impl DeniableLayout {
+    fn slots(&self) -> SlotLayout {
+        SlotLayout {
+            shares: vec![self.decoy_share, self.hidden_share],
+            kdf: self.kdf,
+        }
+    }
+}
+
+/// Returns the number of secret bits of the decoy and hidden layers.
+///
+/// # Errors
+/// Returns an error if both shares are zero.
+pub fn deniable_capacity<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &[S],
+    layout: &DeniableLayout,
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<(usize, usize), String> {
+    let capacity = multi_slot_capacity(host, &layout.slots(), options, locator)?;
+    Ok((capacity[0], capacity[1]))
+}
+
+/// Embeds a decoy payload and, if given, a hidden payload. The hidden layer is filled with
+/// random bits when `hidden` is `None`.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::bitplane::BitplaneOptions;
+/// use stegano_rs::deniable::{DeniableLayout, deniable_embed, deniable_extract};
+/// use stegano_rs::embedding_locator::LinearTraversal;
+/// use stegano_rs::multi_slot::SlotPayload;
+///
+/// let mut host = vec![0x80u8; 1024];
+/// let layout = DeniableLayout::default();
+/// let options = BitplaneOptions::default();
+/// let decoy = SlotPayload { key: b"birthday", secret: b"shopping list" };
+/// let hidden = SlotPayload { key: b"long passphrase", secret: b"the real message" };
+/// deniable_embed(&mut host, decoy, Some(hidden), &layout, &options, &LinearTraversal).unwrap();
+///
+/// let open = |password: &[u8]| deniable_extract(&host, password, &layout, &options, &LinearTraversal);
+/// assert_eq!(open(b"birthday").unwrap(), b"shopping list");
+/// assert_eq!(open(b"long passphrase").unwrap(), b"the real message");
+/// ```
+///
+/// # Errors
+/// Returns an error if both payloads use the same password, if a secret does not fit in its
+/// layer, or if the options are invalid. The host is left untouched when an error is
+/// returned.
+pub fn deniable_embed<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &mut [S],
+    decoy: SlotPayload,
+    hidden: Option<SlotPayload>,
+    layout: &DeniableLayout,
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<(), String> {
+    let payloads: Vec<SlotPayload> = std::iter::once(decoy).chain(hidden).collect();
+    multi_slot_embed(host, &payloads, &layout.slots(), options, locator)
+}
+
+/// Extracts the decoy or the hidden payload, depending on the password.
+///
+/// # Errors
+/// Returns an error if neither layer holds a payload for this password.
+pub fn deniable_extract<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &[S],
+    password: &[u8],
+    layout: &DeniableLayout,
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<Vec<u8>, String> {
+    multi_slot_extract(host, password, &layout.slots(), options, locator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding_locator::LinearTraversal;
+    use crate::test_utils::natural_cover;
+
+    const DECOY: SlotPayload = SlotPayload {
+        key: b"decoy password",
+        secret: b"nothing to see here",
+    };
+    const HIDDEN: SlotPayload = SlotPayload {
+        key: b"hidden password",
+        secret: b"meet at the old mill at midnight",
+    };
+    /// Cheap parameters, to keep the tests fast.
+    const LAYOUT: DeniableLayout = DeniableLayout {
+        decoy_share: 1,
+        hidden_share: 1,
+        kdf: KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        },
+    };
+
+    fn embed(cover: &[u8], hidden: Option<SlotPayload>) -> Vec<u8> {
+        let mut host = cover.to_vec();
+        let options = BitplaneOptions::default();
+        deniable_embed(
+            &mut host,
+            DECOY,
+            hidden,
+            &LAYOUT,
+            &options,
+            &LinearTraversal,
+        )
+        .unwrap();
+        host
+    }
+
+    fn open(host: &[u8], password: &[u8]) -> Result<Vec<u8>, String> {
+        let options = BitplaneOptions::default();
+        deniable_extract(host, password, &LAYOUT, &options, &LinearTraversal)
+    }
+
+    #[test]
+    fn test_each_password_opens_its_layer() {
+        let host = embed(&natural_cover(64, 32, 4), Some(HIDDEN));
+        assert_eq!(open(&host, DECOY.key).unwrap(), DECOY.secret);
+        assert_eq!(open(&host, HIDDEN.key).unwrap(), HIDDEN.secret);
+        assert!(open(&host, b"guess").is_err());
+    }
+
+    #[test]
+    fn test_hidden_layer_looks_like_filling() {
+        let cover = natural_cover(64, 32, 9);
+        let with_hidden = embed(&cover, Some(HIDDEN));
+        let without = embed(&cover, None);
+
+        // The decoy layer opens with or without a hidden payload
+        let half = cover.len() / 2;
+        assert_eq!(open(&with_hidden, DECOY.key).unwrap(), DECOY.secret);
+        assert_eq!(open(&without, DECOY.key).unwrap(), DECOY.secret);
+        assert_eq!(
+            open(&without, HIDDEN.key).unwrap_err(),
+            open(&with_hidden, b"guess").unwrap_err()
+        );
+
+        // Both hidden layers are balanced random bits, and are not the cover bits
+        for host in [&with_hidden, &without] {
+            let layer = &host[half..];
+            let ones = layer.iter().filter(|&&b| b & 1 == 1).count();
+            assert!(ones.abs_diff(layer.len() / 2) < layer.len() / 10);
+            let same = layer
+                .iter()
+                .zip(&cover[half..])
+                .filter(|(a, b)| a == b)
+                .count();
+            assert!(same.abs_diff(layer.len() / 2) < layer.len() / 10);
+        }
+        assert_ne!(with_hidden[half..], without[half..]);
+    }
+
+    #[test]
+    fn test_capacity_and_errors() {
+        let mut host = natural_cover(64, 32, 2);
+        let layout = DeniableLayout {
+            hidden_share: 3,
+            ..LAYOUT
+        };
+        let options = BitplaneOptions::default();
+        let capacity = deniable_capacity(&host, &layout, &options, &LinearTraversal).unwrap();
+        // 16 bytes of salt and 20 bytes of overhead per layer
+        assert_eq!(capacity, ((64 - 36) * 8, (192 - 36) * 8));
+
+        let same_key = SlotPayload {
+            key: DECOY.key,
+            secret: b"x",
+        };
+        let cover = host.clone();
+        let result = deniable_embed(
+            &mut host,
+            DECOY,
+            Some(same_key),
+            &layout,
+            &options,
+            &LinearTraversal,
+        );
+        assert!(result.is_err());
+        assert_eq!(host, cover);
+    }
+}
@@ -1,8 +1,18 @@
+use alloc::boxed::Box;
+
+use crate::geometry::ImageGeometry;
+
 /// Trait to locate the indices where embedding should be performed.
 ///
 /// This trait defines a method that returns an iterator over valid indices
 /// in a host container (`host`) where embedding operations can be applied.
 ///
+/// Indices are produced lazily, so embedding functions such as
+/// [`bitplane_embed_with_locator`](crate::bitplane::bitplane_embed_with_locator) can consume
+/// them without collecting them into a `Vec<usize>` first. The returned iterator only
+/// borrows the locator, and references (`&L`) and boxes (`Box<L>`) of locators are
+/// locators themselves, so borrowed locators such as [`HeatmapTraversal`] compose cleanly.
+///
 /// # Example
 ///
 /// ```rust
@@ -10,8 +20,8 @@
 /// #[derive(Clone)]
 /// struct EveryOther;
 ///
-/// impl<'a> EmbeddingLocator<'a> for EveryOther {
-///     fn iter_indices(&'a self, host_len: usize) -> Box<dyn Iterator<Item = usize> + 'a> {
+/// impl EmbeddingLocator for EveryOther {
+///     fn iter_indices(&self, host_len: usize) -> Box<dyn Iterator<Item = usize> + '_> {
 ///         Box::new((0..host_len).step_by(2))
 ///     }
 /// }
@@ -19,8 +29,13 @@
 /// let locator = EveryOther;
 /// let indices: Vec<usize> = locator.iter_indices(10).collect();
 /// assert_eq!(indices, vec![0, 2, 4, 6, 8]);
+///
+/// // Borrowed and boxed locators can be used wherever a locator is expected
+/// let boxed: Box<dyn EmbeddingLocator> = Box::new(EveryOther);
+/// let borrowed = &boxed;
+/// assert_eq!(borrowed.iter_indices(5).collect::<Vec<_>>(), vec![0, 2, 4]);
 /// ```  
-pub trait EmbeddingLocator<'a> {
+pub trait EmbeddingLocator {
     /// Returns an iterator over the valid indices in the `host`.
     ///
     /// # Arguments
@@ -32,7 +47,19 @@ pub trait EmbeddingLocator<'a> {
     /// An iterator over valid indices (`usize`) within the host.
     ///
     /// These indices correspond to positions where embedding operations can be performed.
-    fn iter_indices(&'a self, host_len: usize) -> Box<dyn Iterator<Item = usize> + 'a>;
+    fn iter_indices(&self, host_len: usize) -> Box<dyn Iterator<Item = usize> + '_>;
+}
+
+impl<L: EmbeddingLocator + ?Sized> EmbeddingLocator for &L {
+    fn iter_indices(&self, host_len: usize) -> Box<dyn Iterator<Item = usize> + '_> {
+        (**self).iter_indices(host_len)
+    }
+}
+
+impl<L: EmbeddingLocator + ?Sized> EmbeddingLocator for Box<L> {
+    fn iter_indices(&self, host_len: usize) -> Box<dyn Iterator<Item = usize> + '_> {
+        (**self).iter_indices(host_len)
+    }
 }
 
 /// Implementation of `EmbeddingLocator` that performs a linear traversal
@@ -53,8 +80,8 @@ pub trait EmbeddingLocator<'a> {
 #[derive(Clone)]
 pub struct LinearTraversal ;
 
-impl<'a>  EmbeddingLocator<'a>  for LinearTraversal  {
-    fn iter_indices(&'a self, host_len: usize) -> Box<dyn Iterator<Item = usize> + 'a> {
+impl EmbeddingLocator for LinearTraversal {
+    fn iter_indices(&self, host_len: usize) -> Box<dyn Iterator<Item = usize> + '_> {
         Box::new(0..host_len)
     }
 }
@@ -85,7 +112,7 @@ pub struct HeatmapTraversal<'a> {
     pub threshold: u8,
 }
 
-impl<'a> EmbeddingLocator<'a> for HeatmapTraversal<'a> {
+impl EmbeddingLocator for HeatmapTraversal<'_> {
     /// Returns an iterator over valid indices in the host buffer where
     /// the corresponding heatmap value is greater or equal to the threshold.
     ///
@@ -131,7 +158,7 @@ pub struct PositionListTraversal<'a> {
     pub positions: &'a [usize],
 }
 
-impl<'a> EmbeddingLocator<'a> for PositionListTraversal<'a> {
+impl EmbeddingLocator for PositionListTraversal<'_> {
     /// Returns an iterator over the predefined positions that are valid within the host buffer.
     ///
     /// # Arguments
@@ -145,3 +172,50 @@ impl<'a> EmbeddingLocator<'a> for PositionListTraversal<'a> {
         Box::new(self.positions.iter().copied().filter(move |&pos| pos < host_len))
     }
 }
+
+/// Traversal strategy that visits selected channels of an interleaved image, row-major.
+///
+/// Pixels are visited left to right, top to bottom, and for each pixel the channels are
+/// visited in the order given by `channels` (`channels: &[0, 1, 2]` skips the alpha
+/// channel of an RGBA image).
+///
+/// # Example
+///
+/// ```rust
+/// use stegano_rs::embedding_locator::{ChannelTraversal, EmbeddingLocator};
+/// use stegano_rs::geometry::ImageGeometry;
+///
+/// // 2x1 RGBA image, alpha skipped
+/// let traversal = ChannelTraversal {
+///     geometry: ImageGeometry::new(2, 1, 4),
+///     channels: &[0, 1, 2],
+/// };
+/// let indices: Vec<usize> = traversal.iter_indices(8).collect();
+/// assert_eq!(indices, vec![0, 1, 2, 4, 5, 6]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChannelTraversal<'a> {
+    /// Layout of the host buffer.
+    pub geometry: ImageGeometry,
+    /// Channels visited for each pixel, in order.
+    pub channels: &'a [usize],
+}
+
+impl EmbeddingLocator for ChannelTraversal<'_> {
+    /// Returns an iterator over the selected channels of each pixel, row-major.
+    ///
+    /// Channels outside the geometry and indices >= `host_len` are ignored.
+    fn iter_indices(&self, host_len: usize) -> Box<dyn Iterator<Item = usize> + '_> {
+        let geometry = self.geometry;
+        Box::new(
+            (0..geometry.pixels())
+                .flat_map(move |pixel| {
+                    self.channels
+                        .iter()
+                        .filter(move |&&channel| channel < geometry.channels)
+                        .map(move |&channel| pixel * geometry.channels + channel)
+                })
+                .filter(move |&idx| idx < host_len),
+        )
+    }
+}
@@ -0,0 +1,214 @@
+use alloc::{format, string::String, vec::Vec};
+
+/// Byte order of a length prefix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// Most significant byte first.
+    #[default]
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+
+/// Unit in which a length prefix counts the payload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// The prefix holds the payload length in bytes.
+    #[default]
+    Bytes,
+    /// The prefix holds the payload length in bits.
+    Bits,
+}
+
+/// Fixed-size length prefix written in front of a payload.
+///
+/// Embedding functions hide raw bytes, so the extractor does not know where the message
+/// ends. Framing the payload with a length prefix lets it read back exactly the payload.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::framing::{Endianness, LengthPrefix, LengthUnit};
+///
+/// let prefix = LengthPrefix::default(); // 32-bit big-endian length in bytes
+/// let framed = prefix.frame(b"Hi").unwrap();
+/// assert_eq!(framed, vec![0, 0, 0, 2, b'H', b'i']);
+///
+/// // Trailing bytes after the payload are ignored
+/// let mut extracted = framed.clone();
+/// extracted.extend_from_slice(&[0xAA; 4]);
+/// assert_eq!(prefix.unframe(&extracted).unwrap(), b"Hi");
+///
+/// let bits = LengthPrefix { size: 2, endianness: Endianness::Little, unit: LengthUnit::Bits };
+/// assert_eq!(bits.frame(b"Hi").unwrap(), vec![16, 0, b'H', b'i']);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthPrefix {
+    /// Size of the prefix in bytes (between 1 and 8).
+    pub size: usize,
+    /// Byte order of the prefix.
+    pub endianness: Endianness,
+    /// Unit of the stored length.
+    pub unit: LengthUnit,
+}
+
+impl Default for LengthPrefix {
+    /// Returns a 32-bit big-endian length in bytes.
+    fn default() -> Self {
+        Self {
+            size: 4,
+            endianness: Endianness::Big,
+            unit: LengthUnit::Bytes,
+        }
+    }
+}
+
+impl LengthPrefix {
+    /// Returns the payload prefixed with its length.
+    ///
+    /// # Errors
+    /// Returns an error if `size` is not between 1 and 8, or if the length does not fit
+    /// in the prefix.
+    pub fn frame(&self, payload: &[u8]) -> Result<Vec<u8>, String> {
+        self.check_size()?;
+        let length = match self.unit {
+            LengthUnit::Bytes => payload.len() as u64,
+            LengthUnit::Bits => payload.len() as u64 * 8,
+        };
+        if self.size < 8 && length >> (self.size * 8) != 0 {
+            return Err(format!(
+                "Payload length {} does not fit in a {}-byte length prefix",
+                length, self.size
+            ));
+        }
+
+        let be = length.to_be_bytes();
+        let mut framed = Vec::with_capacity(self.size + payload.len());
+        match self.endianness {
+            Endianness::Big => framed.extend_from_slice(&be[8 - self.size..]),
+            Endianness::Little => framed.extend(be[8 - self.size..].iter().rev()),
+        }
+        framed.extend_from_slice(payload);
+        Ok(framed)
+    }
+
+    /// Reads the payload length, in bits, from the first `size` bytes of `data`.
+    ///
+    /// # Errors
+    /// Returns an error if `size` is invalid or if `data` is shorter than the prefix.
+    pub fn payload_bits(&self, data: &[u8]) -> Result<usize, String> {
+        self.check_size()?;
+        if data.len() < self.size {
+            return Err(format!(
+                "Data too short for the length prefix: {} < {} bytes",
+                data.len(),
+                self.size
+            ));
+        }
+
+        let header = &data[..self.size];
+        let length = match self.endianness {
+            Endianness::Big => header.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64),
+            Endianness::Little => header
+                .iter()
+                .rev()
+                .fold(0u64, |acc, &b| (acc << 8) | b as u64),
+        };
+        let bits = match self.unit {
+            LengthUnit::Bytes => length.checked_mul(8),
+            LengthUnit::Bits => Some(length),
+        };
+        bits.and_then(|bits| usize::try_from(bits).ok())
+            .ok_or_else(|| format!("Length prefix {} is too large", length))
+    }
+
+    /// Returns the payload of a framed buffer, ignoring any trailing data.
+    ///
+    /// When the length is given in bits and is not a multiple of 8, the last byte is
+    /// returned with its unused low bits cleared.
+    ///
+    /// # Errors
+    /// Returns an error if the prefix is invalid or announces more data than available.
+    pub fn unframe(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let bits = self.payload_bits(data)?;
+        let available = (data.len() - self.size) * 8;
+        if bits > available {
+            return Err(format!(
+                "Length prefix announces {} bits but only {} are available",
+                bits, available
+            ));
+        }
+
+        let mut payload = data[self.size..self.size + bits.div_ceil(8)].to_vec();
+        if bits % 8 != 0 {
+            let last = payload.len() - 1;
+            payload[last] &= 0xFF << (8 - bits % 8);
+        }
+        Ok(payload)
+    }
+
+    fn check_size(&self) -> Result<(), String> {
+        if !(1..=8).contains(&self.size) {
+            return Err("length prefix size must be between 1 and 8 bytes".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let payload = vec![7u8; 300];
+        for endianness in [Endianness::Big, Endianness::Little] {
+            for unit in [LengthUnit::Bytes, LengthUnit::Bits] {
+                let prefix = LengthPrefix {
+                    size: 3,
+                    endianness,
+                    unit,
+                };
+                let framed = prefix.frame(&payload).unwrap();
+                assert_eq!(framed.len(), 303);
+                assert_eq!(prefix.unframe(&framed).unwrap(), payload);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_errors() {
+        let prefix = LengthPrefix {
+            size: 1,
+            ..LengthPrefix::default()
+        };
+        assert_eq!(
+            prefix.frame(&[0; 256]).unwrap_err(),
+            "Payload length 256 does not fit in a 1-byte length prefix"
+        );
+
+        let invalid = LengthPrefix {
+            size: 0,
+            ..LengthPrefix::default()
+        };
+        assert!(invalid.frame(b"x").is_err());
+
+        let framed = LengthPrefix::default().frame(b"abc").unwrap();
+        assert_eq!(
+            LengthPrefix::default().unframe(&framed[..5]).unwrap_err(),
+            "Length prefix announces 24 bits but only 8 are available"
+        );
+    }
+
+    #[test]
+    fn test_unframe_partial_byte() {
+        let prefix = LengthPrefix {
+            size: 1,
+            unit: LengthUnit::Bits,
+            ..LengthPrefix::default()
+        };
+        assert_eq!(
+            prefix.unframe(&[12, 0xAB, 0xFF, 0x00]).unwrap(),
+            vec![0xAB, 0xF0]
+        );
+    }
+}
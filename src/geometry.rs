@@ -0,0 +1,76 @@
+use alloc::{format, string::String};
+
+/// Layout of an image stored as an interleaved sample buffer.
+///
+/// Samples are stored row-major, with the `channels` samples of a pixel next to each other:
+/// `[r, g, b, r, g, b, ...]` for an RGB image. The sample of channel `c` of pixel `(x, y)`
+/// is at index `(y * width + x) * channels + c`.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+///
+/// let geometry = ImageGeometry::new(4, 3, 3); // 4x3 RGB image
+/// assert_eq!(geometry.len(), 36);
+/// assert_eq!(geometry.index(1, 2, 2), 29);
+/// assert!(geometry.check(36).is_ok());
+/// assert!(geometry.check(35).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageGeometry {
+    /// Width of the image in pixels.
+    pub width: usize,
+    /// Height of the image in pixels.
+    pub height: usize,
+    /// Number of interleaved samples per pixel (1 for grayscale, 3 for RGB, ...).
+    pub channels: usize,
+}
+
+impl ImageGeometry {
+    /// Creates a new geometry.
+    pub fn new(width: usize, height: usize, channels: usize) -> Self {
+        Self {
+            width,
+            height,
+            channels,
+        }
+    }
+
+    /// Returns the number of samples of a buffer with this geometry.
+    pub fn len(&self) -> usize {
+        self.width * self.height * self.channels
+    }
+
+    /// Returns `true` if the geometry contains no sample.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of pixels.
+    pub fn pixels(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Returns the index of the sample of channel `channel` of pixel `(x, y)`.
+    pub fn index(&self, x: usize, y: usize, channel: usize) -> usize {
+        (y * self.width + x) * self.channels + channel
+    }
+
+    /// Checks that the geometry is not empty and matches a buffer of `buffer_len` samples.
+    pub fn check(&self, buffer_len: usize) -> Result<(), String> {
+        if self.is_empty() {
+            return Err("geometry must have a non-zero width, height and channel count".into());
+        }
+        if buffer_len != self.len() {
+            return Err(format!(
+                "Buffer length {} does not match geometry {}x{}x{} ({} samples)",
+                buffer_len,
+                self.width,
+                self.height,
+                self.channels,
+                self.len()
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,922 @@
+//! Baseline JPEG as a carrier of quantized DCT coefficients.
+//!
+//! Pixel-domain embedding does not survive JPEG re-encoding. Embedding in the quantized DCT
+//! coefficients instead only touches the entropy-coded data: [`JpegImage::parse`] decodes the
+//! Huffman-coded coefficients of a baseline (sequential, Huffman) JPEG, and
+//! [`JpegImage::to_bytes`] re-emits the file with the same markers, quantization tables and,
+//! whenever possible, the same Huffman tables. Nothing is decompressed to pixels.
+//!
+//! Available methods:
+//! - [`jsteg`]: LSB replacement on the AC coefficients, skipping 0 and 1 (JSteg).
+//! - [`f5`]: matrix encoding by decrementing absolute values, in a keyed order (F5).
+
+pub mod f5;
+mod huffman;
+pub mod jsteg;
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use huffman::{HuffmanDecoder, HuffmanEncoder, HuffmanTable};
+
+/// Maps the zigzag order of the coefficients in a block to their natural (row-major) order.
+pub const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// A color component of a JPEG image.
+///
+/// The coefficient `k` (in zigzag order, `k = 0` being the DC coefficient) of the block
+/// `(bx, by)` is stored at `offset + (by * blocks_wide + bx) * 64 + k` in
+/// [`JpegImage::coefficients`]. The blocks cover whole MCUs, so the last row and column of
+/// blocks can lie outside the image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    /// Component identifier from the frame header.
+    pub id: u8,
+    /// Horizontal sampling factor.
+    pub h_sampling: usize,
+    /// Vertical sampling factor.
+    pub v_sampling: usize,
+    /// Index of the quantization table.
+    pub quant_table: u8,
+    /// Number of stored blocks per row.
+    pub blocks_wide: usize,
+    /// Number of stored rows of blocks.
+    pub blocks_high: usize,
+    /// Index of the first coefficient of the component.
+    pub offset: usize,
+}
+
+impl Component {
+    /// Returns the index of coefficient `k` (zigzag order) of block `(bx, by)`.
+    pub fn index(&self, bx: usize, by: usize, k: usize) -> usize {
+        self.offset + (by * self.blocks_wide + bx) * 64 + k
+    }
+}
+
+/// Huffman tables of one class, by table identifier.
+type Tables = [Option<HuffmanTable>; 4];
+
+/// Component of a scan and the Huffman tables it uses.
+#[derive(Debug, Clone)]
+struct ScanComponent {
+    component: usize,
+    dc_table: usize,
+    ac_table: usize,
+}
+
+/// A scan: its SOS segment, and the Huffman tables and restart interval in effect.
+#[derive(Debug, Clone)]
+struct Scan {
+    header: Vec<u8>,
+    components: Vec<ScanComponent>,
+    dc_tables: Tables,
+    ac_tables: Tables,
+    restart_interval: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    /// A marker segment copied as is.
+    Raw(Vec<u8>),
+    /// A DHT segment, dropped when the Huffman tables have to be rebuilt.
+    Huffman(Vec<u8>),
+    /// A scan, whose entropy-coded data is regenerated from the coefficients.
+    Scan(Box<Scan>),
+}
+
+/// A baseline JPEG file decoded to quantized DCT coefficients.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::jpeg::JpegImage;
+///
+/// let data = std::fs::read("tests/fixtures/jpeg/gray.jpg").unwrap();
+/// let jpeg = JpegImage::parse(&data).unwrap();
+/// assert_eq!((jpeg.width(), jpeg.height()), (64, 48));
+/// assert_eq!(jpeg.components().len(), 1);
+///
+/// // Without changes, the file is re-emitted byte for byte
+/// assert_eq!(jpeg.to_bytes().unwrap(), data);
+/// ```
+#[derive(Debug, Clone)]
+pub struct JpegImage {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    coefficients: Vec<i16>,
+    segments: Vec<Segment>,
+}
+
+impl JpegImage {
+    /// Parses a baseline or extended sequential JPEG file with Huffman coding.
+    ///
+    /// # Errors
+    /// Returns an error if the file is not a valid JPEG, or uses progressive, lossless,
+    /// hierarchical or arithmetic coding, or a precision other than 8 bits.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+            return Err("Invalid JPEG: missing SOI marker".into());
+        }
+
+        let mut image = JpegImage {
+            width: 0,
+            height: 0,
+            components: Vec::new(),
+            coefficients: Vec::new(),
+            segments: vec![Segment::Raw(vec![0xFF, 0xD8])],
+        };
+        let mut dc_tables: Tables = Default::default();
+        let mut ac_tables: Tables = Default::default();
+        let mut restart_interval = 0;
+        let mut pos = 2;
+
+        loop {
+            // Markers may be preceded by fill bytes
+            while pos < data.len() && data[pos] == 0xFF && data.get(pos + 1) == Some(&0xFF) {
+                pos += 1;
+            }
+            if pos + 1 >= data.len() || data[pos] != 0xFF {
+                return Err("Invalid JPEG: expected a marker".into());
+            }
+            let marker = data[pos + 1];
+            if marker == 0xD9 {
+                image.segments.push(Segment::Raw(vec![0xFF, 0xD9]));
+                break;
+            }
+            if pos + 4 > data.len() {
+                return Err("Invalid JPEG: truncated segment".into());
+            }
+            let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            if length < 2 || pos + 2 + length > data.len() {
+                return Err("Invalid JPEG: truncated segment".into());
+            }
+            let segment = &data[pos..pos + 2 + length];
+            let payload = &segment[4..];
+            pos += 2 + length;
+
+            match marker {
+                0xC0 | 0xC1 => image.parse_frame(payload)?,
+                0xC2 | 0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => {
+                    return Err(
+                        "Unsupported JPEG: only baseline and extended sequential Huffman coding are supported"
+                            .into(),
+                    );
+                }
+                0xC4 => {
+                    for (class, id, table) in HuffmanTable::parse_segment(payload)? {
+                        let tables = if class == 0 {
+                            &mut dc_tables
+                        } else {
+                            &mut ac_tables
+                        };
+                        tables[id as usize] = Some(table);
+                    }
+                    image.segments.push(Segment::Huffman(segment.to_vec()));
+                    continue;
+                }
+                0xDD => {
+                    if payload.len() < 2 {
+                        return Err("Invalid JPEG: truncated DRI segment".into());
+                    }
+                    restart_interval = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+                }
+                0xDA => {
+                    let scan = image.parse_scan_header(
+                        segment,
+                        &dc_tables,
+                        &ac_tables,
+                        restart_interval,
+                    )?;
+                    pos = image.decode_scan(&scan, data, pos)?;
+                    image.segments.push(Segment::Scan(Box::new(scan)));
+                    continue;
+                }
+                _ => {}
+            }
+            image.segments.push(Segment::Raw(segment.to_vec()));
+        }
+
+        if image.components.is_empty() {
+            return Err("Invalid JPEG: missing frame header".into());
+        }
+        Ok(image)
+    }
+
+    /// Width of the image in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height of the image in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Color components of the image.
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    /// Quantized DCT coefficients of all components, 64 per block in zigzag order.
+    /// See [`Component`] for the layout.
+    pub fn coefficients(&self) -> &[i16] {
+        &self.coefficients
+    }
+
+    /// Mutable access to the quantized DCT coefficients.
+    pub fn coefficients_mut(&mut self) -> &mut [i16] {
+        &mut self.coefficients
+    }
+
+    /// Re-emits the JPEG file from the coefficients.
+    ///
+    /// The original Huffman tables are kept if they can code every symbol. Otherwise, for
+    /// example when a modified coefficient needs a size category the optimized tables of the
+    /// original file never used, optimal tables are rebuilt and written before each scan.
+    ///
+    /// # Errors
+    /// Returns an error if a coefficient is outside the range of baseline JPEG (±1023 for AC
+    /// coefficients, ±2047 for DC differences).
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        match self.write(false) {
+            Ok(bytes) => Ok(bytes),
+            Err(WriteError::MissingCode) => self.write(true).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn parse_frame(&mut self, payload: &[u8]) -> Result<(), String> {
+        if !self.components.is_empty() {
+            return Err("Invalid JPEG: more than one frame header".into());
+        }
+        if payload.len() < 6 {
+            return Err("Invalid JPEG: truncated frame header".into());
+        }
+        if payload[0] != 8 {
+            return Err(format!("Unsupported JPEG: {}-bit precision", payload[0]));
+        }
+        self.height = u16::from_be_bytes([payload[1], payload[2]]) as usize;
+        self.width = u16::from_be_bytes([payload[3], payload[4]]) as usize;
+        let count = payload[5] as usize;
+        if self.width == 0 || self.height == 0 {
+            return Err("Unsupported JPEG: image size defined by a DNL marker".into());
+        }
+        if count == 0 || payload.len() < 6 + 3 * count {
+            return Err("Invalid JPEG: truncated frame header".into());
+        }
+
+        for i in 0..count {
+            let c = &payload[6 + 3 * i..9 + 3 * i];
+            let (h, v) = ((c[1] >> 4) as usize, (c[1] & 0x0F) as usize);
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) {
+                return Err("Invalid JPEG: bad sampling factors".into());
+            }
+            self.components.push(Component {
+                id: c[0],
+                h_sampling: h,
+                v_sampling: v,
+                quant_table: c[2],
+                blocks_wide: 0,
+                blocks_high: 0,
+                offset: 0,
+            });
+        }
+
+        let (mcus_x, mcus_y) = self.mcus();
+        let mut offset = 0;
+        for component in &mut self.components {
+            component.blocks_wide = mcus_x * component.h_sampling;
+            component.blocks_high = mcus_y * component.v_sampling;
+            component.offset = offset;
+            offset += component.blocks_wide * component.blocks_high * 64;
+        }
+        self.coefficients = vec![0; offset];
+        Ok(())
+    }
+
+    fn max_sampling(&self) -> (usize, usize) {
+        let h = self
+            .components
+            .iter()
+            .map(|c| c.h_sampling)
+            .max()
+            .unwrap_or(1);
+        let v = self
+            .components
+            .iter()
+            .map(|c| c.v_sampling)
+            .max()
+            .unwrap_or(1);
+        (h, v)
+    }
+
+    /// Number of MCUs of an interleaved scan, horizontally and vertically.
+    fn mcus(&self) -> (usize, usize) {
+        let (h, v) = self.max_sampling();
+        (self.width.div_ceil(8 * h), self.height.div_ceil(8 * v))
+    }
+
+    /// Number of blocks of a component in a non-interleaved scan.
+    fn component_blocks(&self, component: &Component) -> (usize, usize) {
+        let (h, v) = self.max_sampling();
+        (
+            (self.width * component.h_sampling).div_ceil(h).div_ceil(8),
+            (self.height * component.v_sampling).div_ceil(v).div_ceil(8),
+        )
+    }
+
+    fn parse_scan_header(
+        &self,
+        segment: &[u8],
+        dc_tables: &Tables,
+        ac_tables: &Tables,
+        restart_interval: usize,
+    ) -> Result<Scan, String> {
+        if self.components.is_empty() {
+            return Err("Invalid JPEG: scan before the frame header".into());
+        }
+        let payload = &segment[4..];
+        let count = *payload.first().unwrap_or(&0) as usize;
+        if !(1..=4).contains(&count) || payload.len() < 4 + 2 * count {
+            return Err("Invalid JPEG: bad scan header".into());
+        }
+        let spectral = &payload[1 + 2 * count..];
+        if spectral[0] != 0 || spectral[1] != 63 || spectral[2] != 0 {
+            return Err("Unsupported JPEG: progressive scan".into());
+        }
+
+        let mut components = Vec::with_capacity(count);
+        for i in 0..count {
+            let (id, tables) = (payload[1 + 2 * i], payload[2 + 2 * i]);
+            let component = self
+                .components
+                .iter()
+                .position(|c| c.id == id)
+                .ok_or_else(|| format!("Invalid JPEG: scan references unknown component {}", id))?;
+            let (dc_table, ac_table) = ((tables >> 4) as usize, (tables & 0x0F) as usize);
+            if dc_table > 3
+                || ac_table > 3
+                || dc_tables[dc_table].is_none()
+                || ac_tables[ac_table].is_none()
+            {
+                return Err("Invalid JPEG: scan references an undefined Huffman table".into());
+            }
+            components.push(ScanComponent {
+                component,
+                dc_table,
+                ac_table,
+            });
+        }
+
+        Ok(Scan {
+            header: segment.to_vec(),
+            components,
+            dc_tables: dc_tables.clone(),
+            ac_tables: ac_tables.clone(),
+            restart_interval,
+        })
+    }
+
+    /// Blocks of a scan in coding order, MCU by MCU.
+    fn scan_blocks(&self, scan: &Scan) -> Vec<ScanBlock> {
+        let mut blocks = Vec::new();
+        let restart = |mcu: usize| {
+            scan.restart_interval > 0 && mcu > 0 && mcu.is_multiple_of(scan.restart_interval)
+        };
+        if let [single] = scan.components.as_slice() {
+            let component = &self.components[single.component];
+            let (wide, high) = self.component_blocks(component);
+            for by in 0..high {
+                for bx in 0..wide {
+                    blocks.push(ScanBlock {
+                        restart: restart(by * wide + bx),
+                        component: 0,
+                        start: component.index(bx, by, 0),
+                    });
+                }
+            }
+            return blocks;
+        }
+
+        let (mcus_x, mcus_y) = self.mcus();
+        for mcu in 0..mcus_x * mcus_y {
+            let (mx, my) = (mcu % mcus_x, mcu / mcus_x);
+            let mut first = true;
+            for (i, sc) in scan.components.iter().enumerate() {
+                let component = &self.components[sc.component];
+                for v in 0..component.v_sampling {
+                    for h in 0..component.h_sampling {
+                        let bx = mx * component.h_sampling + h;
+                        let by = my * component.v_sampling + v;
+                        blocks.push(ScanBlock {
+                            restart: first && restart(mcu),
+                            component: i,
+                            start: component.index(bx, by, 0),
+                        });
+                        first = false;
+                    }
+                }
+            }
+        }
+        blocks
+    }
+
+    /// Decodes the entropy-coded data of a scan starting at `pos`, returns the position of
+    /// the marker that follows it.
+    fn decode_scan(&mut self, scan: &Scan, data: &[u8], pos: usize) -> Result<usize, String> {
+        let decoders: Vec<(HuffmanDecoder, HuffmanDecoder)> = scan
+            .components
+            .iter()
+            .map(|sc| {
+                (
+                    scan.dc_tables[sc.dc_table].as_ref().unwrap().decoder(),
+                    scan.ac_tables[sc.ac_table].as_ref().unwrap().decoder(),
+                )
+            })
+            .collect();
+
+        let mut reader = EntropyReader::new(data, pos);
+        let mut predictions = vec![0i32; scan.components.len()];
+
+        for block in self.scan_blocks(scan) {
+            if block.restart {
+                reader.restart()?;
+                predictions.fill(0);
+            }
+            let i = block.component;
+            let (dc, ac) = &decoders[i];
+            let coefficients = &mut self.coefficients[block.start..block.start + 64];
+
+            let size = dc.decode(|| reader.bit())?;
+            let diff = reader.value(size)?;
+            predictions[i] += diff;
+            coefficients[0] = predictions[i] as i16;
+
+            let mut k = 1;
+            while k < 64 {
+                let symbol = ac.decode(|| reader.bit())?;
+                let (run, size) = ((symbol >> 4) as usize, symbol & 0x0F);
+                if size == 0 {
+                    if run != 15 {
+                        break; // End of block
+                    }
+                    k += 16;
+                    continue;
+                }
+                k += run;
+                if k > 63 {
+                    return Err("Invalid JPEG: coefficient index out of range".into());
+                }
+                coefficients[k] = reader.value(size)? as i16;
+                k += 1;
+            }
+        }
+        Ok(reader.end())
+    }
+
+    fn write(&self, rebuild_tables: bool) -> Result<Vec<u8>, WriteError> {
+        let mut out = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Raw(bytes) => out.extend_from_slice(bytes),
+                Segment::Huffman(bytes) => {
+                    if !rebuild_tables {
+                        out.extend_from_slice(bytes);
+                    }
+                }
+                Segment::Scan(scan) => {
+                    let (dc_tables, ac_tables) = if rebuild_tables {
+                        let tables = self.optimal_tables(scan)?;
+                        write_tables(&tables.0, &tables.1, &mut out);
+                        tables
+                    } else {
+                        (scan.dc_tables.clone(), scan.ac_tables.clone())
+                    };
+                    out.extend_from_slice(&scan.header);
+                    self.encode_scan(scan, &dc_tables, &ac_tables, &mut out)?;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Huffman-coded symbols of a scan and restart markers, in coding order.
+    fn scan_symbols(&self, scan: &Scan) -> Result<Vec<Symbol>, WriteError> {
+        let mut symbols = Vec::new();
+        let mut predictions = vec![0i32; scan.components.len()];
+        for block in self.scan_blocks(scan) {
+            if block.restart {
+                predictions.fill(0);
+                symbols.push(Symbol::Restart);
+            }
+            let i = block.component;
+            let coefficients = &self.coefficients[block.start..block.start + 64];
+            let diff = coefficients[0] as i32 - predictions[i];
+            predictions[i] = coefficients[0] as i32;
+            symbols.push(Symbol::dc(i, magnitude(diff, 11)?));
+
+            let mut zeros = 0;
+            for &coefficient in &coefficients[1..] {
+                if coefficient == 0 {
+                    zeros += 1;
+                    continue;
+                }
+                while zeros > 15 {
+                    symbols.push(Symbol::ac(i, 15, (0, 0)));
+                    zeros -= 16;
+                }
+                symbols.push(Symbol::ac(i, zeros, magnitude(coefficient as i32, 10)?));
+                zeros = 0;
+            }
+            if zeros > 0 {
+                symbols.push(Symbol::ac(i, 0, (0, 0))); // End of block
+            }
+        }
+        Ok(symbols)
+    }
+
+    fn encode_scan(
+        &self,
+        scan: &Scan,
+        dc_tables: &Tables,
+        ac_tables: &Tables,
+        out: &mut Vec<u8>,
+    ) -> Result<(), WriteError> {
+        let encoders: Vec<(HuffmanEncoder, HuffmanEncoder)> = scan
+            .components
+            .iter()
+            .map(|sc| {
+                (
+                    dc_tables[sc.dc_table].as_ref().unwrap().encoder(),
+                    ac_tables[sc.ac_table].as_ref().unwrap().encoder(),
+                )
+            })
+            .collect();
+
+        let symbols = self.scan_symbols(scan)?;
+        let mut writer = EntropyWriter::new(out);
+        let mut marker = 0u8;
+        for symbol in symbols {
+            match symbol {
+                Symbol::Restart => {
+                    writer.restart(marker);
+                    marker = (marker + 1) % 8;
+                }
+                Symbol::Code {
+                    component,
+                    is_dc,
+                    symbol,
+                    bits,
+                    count,
+                } => {
+                    let (dc, ac) = &encoders[component];
+                    let encoder = if is_dc { dc } else { ac };
+                    let (code, len) = encoder.code(symbol).ok_or(WriteError::MissingCode)?;
+                    writer.put(code as u32, len);
+                    writer.put(bits as u32, count);
+                }
+            }
+        }
+        writer.flush();
+        Ok(())
+    }
+
+    /// Optimal Huffman tables for the symbols of a scan.
+    fn optimal_tables(&self, scan: &Scan) -> Result<(Tables, Tables), WriteError> {
+        let mut dc_freq = [[0u64; 256]; 4];
+        let mut ac_freq = [[0u64; 256]; 4];
+        for symbol in self.scan_symbols(scan)? {
+            if let Symbol::Code {
+                component,
+                is_dc,
+                symbol,
+                ..
+            } = symbol
+            {
+                let sc = &scan.components[component];
+                if is_dc {
+                    dc_freq[sc.dc_table][symbol as usize] += 1;
+                } else {
+                    ac_freq[sc.ac_table][symbol as usize] += 1;
+                }
+            }
+        }
+
+        let mut dc_tables: Tables = Default::default();
+        let mut ac_tables: Tables = Default::default();
+        for sc in &scan.components {
+            dc_tables[sc.dc_table] = Some(HuffmanTable::optimal(&dc_freq[sc.dc_table]));
+            ac_tables[sc.ac_table] = Some(HuffmanTable::optimal(&ac_freq[sc.ac_table]));
+        }
+        Ok((dc_tables, ac_tables))
+    }
+}
+
+/// A block of a scan.
+struct ScanBlock {
+    /// A restart marker precedes the block.
+    restart: bool,
+    /// Index of the component in the scan.
+    component: usize,
+    /// Index of the first coefficient of the block.
+    start: usize,
+}
+
+/// An element of the entropy-coded data of a scan.
+enum Symbol {
+    Restart,
+    /// A Huffman-coded symbol followed by `count` extra bits.
+    Code {
+        component: usize,
+        is_dc: bool,
+        symbol: u8,
+        bits: u16,
+        count: u8,
+    },
+}
+
+impl Symbol {
+    /// DC difference of size category `size`.
+    fn dc(component: usize, (size, bits): (u8, u16)) -> Self {
+        Symbol::Code {
+            component,
+            is_dc: true,
+            symbol: size,
+            bits,
+            count: size,
+        }
+    }
+
+    /// AC coefficient after a run of `zeros` zero coefficients. A size of 0 codes an end of
+    /// block (`zeros = 0`) or a run of 16 zeros (`zeros = 15`).
+    fn ac(component: usize, zeros: u8, (size, bits): (u8, u16)) -> Self {
+        Symbol::Code {
+            component,
+            is_dc: false,
+            symbol: (zeros << 4) | size,
+            bits,
+            count: size,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum WriteError {
+    /// A symbol has no code in the Huffman table.
+    MissingCode,
+    Invalid(String),
+}
+
+impl core::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WriteError::MissingCode => write!(f, "A symbol has no Huffman code"),
+            WriteError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Writes a DHT segment with the given tables.
+fn write_tables(dc_tables: &Tables, ac_tables: &Tables, out: &mut Vec<u8>) {
+    let mut payload = Vec::new();
+    for (class, tables) in [(0, dc_tables), (1, ac_tables)] {
+        for (id, table) in tables.iter().enumerate() {
+            if let Some(table) = table {
+                table.write(class, id as u8, &mut payload);
+            }
+        }
+    }
+    out.extend_from_slice(&[0xFF, 0xC4]);
+    out.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+    out.extend_from_slice(&payload);
+}
+
+/// Size category and extra bits of a value (section F.1.2.1).
+fn magnitude(value: i32, max_size: u8) -> Result<(u8, u16), WriteError> {
+    let size = (32 - value.unsigned_abs().leading_zeros()) as u8;
+    if size > max_size {
+        return Err(WriteError::Invalid(format!(
+            "Coefficient {} is out of range for baseline JPEG",
+            value
+        )));
+    }
+    let bits = if value < 0 { value - 1 } else { value };
+    Ok((size, (bits as u32 & ((1u32 << size) - 1)) as u16))
+}
+
+/// Reads the entropy-coded data of a scan, removing the stuffed zero bytes.
+struct EntropyReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    byte: u8,
+    bits_left: u8,
+}
+
+impl<'a> EntropyReader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self {
+            data,
+            pos,
+            byte: 0,
+            bits_left: 0,
+        }
+    }
+
+    fn bit(&mut self) -> Result<u32, String> {
+        if self.bits_left == 0 {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or("Invalid JPEG: truncated scan data")?;
+            if byte == 0xFF {
+                if self.data.get(self.pos + 1) != Some(&0x00) {
+                    return Err("Invalid JPEG: unexpected marker in scan data".into());
+                }
+                self.pos += 1;
+            }
+            self.pos += 1;
+            self.byte = byte;
+            self.bits_left = 8;
+        }
+        self.bits_left -= 1;
+        Ok(((self.byte >> self.bits_left) & 1) as u32)
+    }
+
+    /// Reads a value of `size` bits and extends its sign (section F.2.2.1).
+    fn value(&mut self, size: u8) -> Result<i32, String> {
+        if size > 16 {
+            return Err("Invalid JPEG: bad coefficient size".into());
+        }
+        let mut bits = 0i32;
+        for _ in 0..size {
+            bits = (bits << 1) | self.bit()? as i32;
+        }
+        if size > 0 && bits < 1 << (size - 1) {
+            bits -= (1 << size) - 1;
+        }
+        Ok(bits)
+    }
+
+    /// Skips the padding bits and the next RST marker.
+    fn restart(&mut self) -> Result<(), String> {
+        self.bits_left = 0;
+        match self.data.get(self.pos..self.pos + 2) {
+            Some([0xFF, marker]) if (0xD0..=0xD7).contains(marker) => {
+                self.pos += 2;
+                Ok(())
+            }
+            _ => Err("Invalid JPEG: missing restart marker".into()),
+        }
+    }
+
+    /// Position of the first marker after the scan data.
+    fn end(&self) -> usize {
+        let mut pos = self.pos;
+        while pos + 1 < self.data.len() {
+            if self.data[pos] == 0xFF && self.data[pos + 1] != 0x00 {
+                break;
+            }
+            pos += 1;
+        }
+        pos
+    }
+}
+
+/// Writes entropy-coded data, stuffing a zero byte after each 0xFF byte.
+struct EntropyWriter<'a> {
+    out: &'a mut Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl<'a> EntropyWriter<'a> {
+    fn new(out: &'a mut Vec<u8>) -> Self {
+        Self {
+            out,
+            buffer: 0,
+            bits: 0,
+        }
+    }
+
+    fn put(&mut self, value: u32, count: u8) {
+        for i in (0..count).rev() {
+            self.buffer = (self.buffer << 1) | ((value >> i) & 1);
+            self.bits += 1;
+            if self.bits == 8 {
+                let byte = self.buffer as u8;
+                self.out.push(byte);
+                if byte == 0xFF {
+                    self.out.push(0x00);
+                }
+                self.buffer = 0;
+                self.bits = 0;
+            }
+        }
+    }
+
+    /// Pads the last byte with one bits.
+    fn flush(&mut self) {
+        if self.bits > 0 {
+            self.put(0x7F, 8 - self.bits);
+        }
+    }
+
+    fn restart(&mut self, marker: u8) {
+        self.flush();
+        self.out.extend_from_slice(&[0xFF, 0xD0 + marker]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: [(&str, &[u8], &[u8]); 3] = [
+        (
+            "gray",
+            include_bytes!("../tests/fixtures/jpeg/gray.jpg"),
+            include_bytes!("../tests/fixtures/jpeg/gray.coef"),
+        ),
+        (
+            "color_420",
+            include_bytes!("../tests/fixtures/jpeg/color_420.jpg"),
+            include_bytes!("../tests/fixtures/jpeg/color_420.coef"),
+        ),
+        (
+            "color_444",
+            include_bytes!("../tests/fixtures/jpeg/color_444.jpg"),
+            include_bytes!("../tests/fixtures/jpeg/color_444.coef"),
+        ),
+    ];
+
+    #[test]
+    fn test_coefficients_match_libjpeg() {
+        for (name, jpeg, dump) in FIXTURES {
+            let image = JpegImage::parse(jpeg).unwrap();
+            let mut expected = dump
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]));
+            for component in image.components() {
+                let (wide, high) = image.component_blocks(component);
+                for by in 0..high {
+                    for bx in 0..wide {
+                        let block = &image.coefficients()[component.index(bx, by, 0)..][..64];
+                        let mut natural = [0i16; 64];
+                        for (k, &value) in block.iter().enumerate() {
+                            natural[ZIGZAG[k]] = value;
+                        }
+                        for value in natural {
+                            assert_eq!(Some(value), expected.next(), "{}", name);
+                        }
+                    }
+                }
+            }
+            assert_eq!(expected.next(), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_unchanged_file_is_reemitted_identically() {
+        for (name, jpeg, _) in FIXTURES {
+            let image = JpegImage::parse(jpeg).unwrap();
+            assert_eq!(image.to_bytes().unwrap(), jpeg, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_missing_codes_rebuild_tables() {
+        // The optimized tables of this file have no code for a large AC coefficient
+        let (_, jpeg, _) = FIXTURES[1];
+        let mut image = JpegImage::parse(jpeg).unwrap();
+        let index = image.components()[1].index(0, 0, 5);
+        image.coefficients_mut()[index] = -1000;
+
+        let bytes = image.to_bytes().unwrap();
+        let reparsed = JpegImage::parse(&bytes).unwrap();
+        assert_eq!(reparsed.coefficients(), image.coefficients());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(JpegImage::parse(b"not a jpeg").is_err());
+
+        let (_, jpeg, _) = FIXTURES[0];
+        assert!(JpegImage::parse(&jpeg[..jpeg.len() / 2]).is_err());
+
+        let mut image = JpegImage::parse(jpeg).unwrap();
+        image.coefficients_mut()[1] = 2000;
+        assert_eq!(
+            image.to_bytes().unwrap_err(),
+            "Coefficient 2000 is out of range for baseline JPEG"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! F5 (Westfeld, 2001): matrix encoding by decrementing the absolute value of the AC
+//! coefficients, in a keyed order.
+//!
+//! - The bit carried by a non-zero coefficient `c` is `c & 1` for `c > 0` and `1 - (|c| & 1)`
+//!   for `c < 0`. It is changed by decrementing `|c|`, which keeps the histogram of the
+//!   coefficients smooth (no pairs of values as with JSteg).
+//! - Coefficients are visited in an order derived from the key ([`Keystream::permutation`]).
+//!   DC and zero coefficients are skipped.
+//! - Matrix encoding `(1, n, k)`, with `n = 2^k - 1`, hides `k` bits in a group of `n`
+//!   non-zero coefficients by changing at most one of them: the bits are the XOR of the
+//!   (1-based) positions of the coefficients carrying a 1.
+//! - Shrinkage: a coefficient that becomes zero is skipped by the decoder, so it is dropped
+//!   from the group, the next non-zero coefficient joins, and the same bits are embedded
+//!   again.
+//!
+//! A 32-bit header is embedded first with `k = 1`: `k` in the high 8 bits, the secret length
+//! in bytes in the low 24 bits. The largest `k` that fits the secret is selected, which
+//! minimizes the number of changes.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use super::JpegImage;
+use crate::bit_order::{BitOrder, BitWriter, read_bit};
+use crate::keystream::Keystream;
+
+/// Size of the header in bits.
+const HEADER_BITS: usize = 32;
+
+/// Largest secret length, stored on 24 bits.
+const MAX_SECRET_LEN: usize = (1 << 24) - 1;
+
+/// Largest matrix encoding parameter.
+const MAX_K: usize = 7;
+
+/// Bit carried by a non-zero coefficient.
+fn coefficient_bit(value: i16) -> usize {
+    if value > 0 {
+        (value & 1) as usize
+    } else {
+        1 - (value.unsigned_abs() & 1) as usize
+    }
+}
+
+/// Keyed walk over the non-zero AC coefficients.
+struct Walk {
+    order: Vec<usize>,
+    next: usize,
+}
+
+impl Walk {
+    fn new(coefficients: &[i16], key: &[u8]) -> Self {
+        Self {
+            order: Keystream::from_password(key).permutation(coefficients.len()),
+            next: 0,
+        }
+    }
+
+    /// Returns the index of the next non-zero AC coefficient.
+    fn next_carrier(&mut self, coefficients: &[i16]) -> Option<usize> {
+        while let Some(&idx) = self.order.get(self.next) {
+            self.next += 1;
+            if idx % 64 != 0 && coefficients[idx] != 0 {
+                return Some(idx);
+            }
+        }
+        None
+    }
+
+    /// Returns the indices of the next `n` non-zero AC coefficients.
+    fn next_group(&mut self, coefficients: &[i16], n: usize) -> Option<Vec<usize>> {
+        (0..n).map(|_| self.next_carrier(coefficients)).collect()
+    }
+}
+
+/// XOR of the 1-based positions of the coefficients of a group carrying a 1.
+fn group_hash(coefficients: &[i16], group: &[usize]) -> usize {
+    group
+        .iter()
+        .enumerate()
+        .filter(|&(_, &idx)| coefficient_bit(coefficients[idx]) == 1)
+        .fold(0, |hash, (i, _)| hash ^ (i + 1))
+}
+
+/// Embeds the `k` bits of `code` in the next group, handling shrinkage. Returns `false` if
+/// the coefficients run out.
+fn embed_group(coefficients: &mut [i16], walk: &mut Walk, code: usize, k: usize) -> bool {
+    let Some(mut group) = walk.next_group(coefficients, (1 << k) - 1) else {
+        return false;
+    };
+    loop {
+        let change = group_hash(coefficients, &group) ^ code;
+        if change == 0 {
+            return true;
+        }
+        let idx = group[change - 1];
+        coefficients[idx] -= coefficients[idx].signum();
+        if coefficients[idx] != 0 {
+            return true;
+        }
+        // Shrinkage: the next carrier replaces the zeroed coefficient
+        group.remove(change - 1);
+        match walk.next_carrier(coefficients) {
+            Some(next) => group.push(next),
+            None => return false,
+        }
+    }
+}
+
+/// Reads `k` bits from the next group.
+fn extract_group(coefficients: &[i16], walk: &mut Walk, k: usize) -> Option<usize> {
+    let group = walk.next_group(coefficients, (1 << k) - 1)?;
+    Some(group_hash(coefficients, &group))
+}
+
+/// Expected number of bits the image can hold with matrix encoding `k`, header excluded.
+fn expected_capacity(coefficients: &[i16], k: usize) -> usize {
+    let ac = coefficients
+        .iter()
+        .enumerate()
+        .filter(|&(i, &value)| i % 64 != 0 && value != 0);
+    let (mut ones, mut large) = (0usize, 0usize);
+    for (_, value) in ac {
+        if value.unsigned_abs() == 1 {
+            ones += 1;
+        } else {
+            large += 1;
+        }
+    }
+    // About half of the coefficients are changed with k = 1, and a changed ±1 shrinks to 0
+    let usable = (large + ones * 49 / 100).saturating_sub(HEADER_BITS);
+    usable / ((1 << k) - 1) * k
+}
+
+/// Returns the expected number of secret bits an image can hold (without matrix encoding),
+/// accounting for the coefficients lost to shrinkage. The actual capacity depends on the key.
+pub fn f5_capacity(image: &JpegImage) -> usize {
+    expected_capacity(image.coefficients(), 1)
+}
+
+/// Embeds a secret in the AC coefficients of a JPEG image with F5.
+///
+/// The image is modified in place; use [`JpegImage::to_bytes`] to get the stego file.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::jpeg::JpegImage;
+/// use stegano_rs::jpeg::f5::{f5_embed, f5_extract};
+///
+/// let data = std::fs::read("tests/fixtures/jpeg/color_444.jpg").unwrap();
+/// let mut image = JpegImage::parse(&data).unwrap();
+/// f5_embed(&mut image, b"F5", b"password").unwrap();
+///
+/// let stego = JpegImage::parse(&image.to_bytes().unwrap()).unwrap();
+/// assert_eq!(f5_extract(&stego, b"password").unwrap(), b"F5");
+/// ```
+///
+/// # Errors
+/// Returns an error if the secret is longer than 2^24 - 1 bytes, or if the coefficients run
+/// out before the whole secret is embedded. The image is left untouched when an error is
+/// returned.
+pub fn f5_embed(image: &mut JpegImage, secret: &[u8], key: &[u8]) -> Result<(), String> {
+    if secret.len() > MAX_SECRET_LEN {
+        return Err(format!(
+            "Secret too large: F5 stores its length on 24 bits (at most {} bytes)",
+            MAX_SECRET_LEN
+        ));
+    }
+    let mut coefficients = image.coefficients().to_vec();
+    let total_bits = secret.len() * 8;
+    let k = (1..=MAX_K)
+        .rev()
+        .find(|&k| expected_capacity(&coefficients, k) >= total_bits)
+        .unwrap_or(1);
+
+    let not_enough = || {
+        format!(
+            "Not enough capacity to embed the secret: expected capacity={} bits, message={} bits",
+            expected_capacity(image.coefficients(), 1),
+            total_bits
+        )
+    };
+
+    let mut walk = Walk::new(&coefficients, key);
+    let header = (k << 24) | secret.len();
+    for i in (0..HEADER_BITS).rev() {
+        if !embed_group(&mut coefficients, &mut walk, (header >> i) & 1, 1) {
+            return Err(not_enough());
+        }
+    }
+    for start in (0..total_bits).step_by(k) {
+        // The last group is padded with zeros
+        let code = (0..k).fold(0, |code, i| {
+            let bit = if start + i < total_bits {
+                read_bit(secret, start + i, BitOrder::MsbFirst) as usize
+            } else {
+                0
+            };
+            (code << 1) | bit
+        });
+        if !embed_group(&mut coefficients, &mut walk, code, k) {
+            return Err(not_enough());
+        }
+    }
+
+    image.coefficients_mut().copy_from_slice(&coefficients);
+    Ok(())
+}
+
+/// Extracts a secret embedded with [`f5_embed`].
+///
+/// # Errors
+/// Returns an error if the header is invalid (for example with a wrong key), or if the
+/// coefficients run out before the announced length.
+pub fn f5_extract(image: &JpegImage, key: &[u8]) -> Result<Vec<u8>, String> {
+    let coefficients = image.coefficients();
+    let mut walk = Walk::new(coefficients, key);
+    let truncated = || "Not enough coefficients for the announced secret length".to_string();
+
+    let mut header = 0;
+    for _ in 0..HEADER_BITS {
+        header = (header << 1) | extract_group(coefficients, &mut walk, 1).ok_or_else(truncated)?;
+    }
+    let (k, len) = (header >> 24, header & MAX_SECRET_LEN);
+    if !(1..=MAX_K).contains(&k) {
+        return Err(format!("Invalid F5 header: matrix encoding k={}", k));
+    }
+
+    let total_bits = len * 8;
+    let mut secret = BitWriter::new(BitOrder::MsbFirst);
+    for start in (0..total_bits).step_by(k) {
+        let code = extract_group(coefficients, &mut walk, k).ok_or_else(truncated)?;
+        for i in (0..k).rev().take(total_bits - start) {
+            secret.push(((code >> i) & 1) as u8);
+        }
+    }
+    Ok(secret.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestRng;
+
+    const FIXTURES: [&[u8]; 3] = [
+        include_bytes!("../../tests/fixtures/jpeg/gray.jpg"),
+        include_bytes!("../../tests/fixtures/jpeg/color_420.jpg"),
+        include_bytes!("../../tests/fixtures/jpeg/color_444.jpg"),
+    ];
+
+    #[test]
+    fn test_round_trip_through_file() {
+        for (seed, jpeg) in FIXTURES.iter().enumerate() {
+            let mut image = JpegImage::parse(jpeg).unwrap();
+            let secret = TestRng::new(seed as u64 + 1).bytes(f5_capacity(&image) / 8 / 2);
+
+            f5_embed(&mut image, &secret, b"key").unwrap();
+            let stego = JpegImage::parse(&image.to_bytes().unwrap()).unwrap();
+            assert_eq!(f5_extract(&stego, b"key").unwrap(), secret);
+        }
+    }
+
+    #[test]
+    fn test_absolute_values_only_decrease() {
+        let cover = JpegImage::parse(FIXTURES[1]).unwrap();
+        let mut stego = cover.clone();
+        let secret = TestRng::new(5).bytes(f5_capacity(&cover) / 8 / 2);
+        f5_embed(&mut stego, &secret, b"key").unwrap();
+
+        let mut shrunk = 0;
+        for (i, (&before, &after)) in cover
+            .coefficients()
+            .iter()
+            .zip(stego.coefficients())
+            .enumerate()
+        {
+            if i % 64 == 0 || before == 0 {
+                assert_eq!(before, after);
+            } else if before != after {
+                assert_eq!(after, before - before.signum());
+                shrunk += (after == 0) as usize;
+            }
+        }
+        // Shrinkage happened and was handled
+        assert!(shrunk > 0);
+        assert_eq!(f5_extract(&stego, b"key").unwrap(), secret);
+    }
+
+    #[test]
+    fn test_matrix_encoding_changes_one_coefficient_per_group() {
+        let mut rng = TestRng::new(9);
+        let cover: Vec<i16> = (0..64 * 40).map(|_| [-5, -2, 3, 4][rng.below(4)]).collect();
+        for k in 1..=MAX_K {
+            let mut coefficients = cover.clone();
+            let mut walk = Walk::new(&coefficients, b"key");
+            let codes: Vec<usize> = (0..10).map(|_| rng.below(1 << k)).collect();
+            for &code in &codes {
+                let before = coefficients.clone();
+                assert!(embed_group(&mut coefficients, &mut walk, code, k));
+                let changed = before.iter().zip(&coefficients).filter(|(a, b)| a != b);
+                assert!(changed.count() <= 1);
+            }
+
+            let mut walk = Walk::new(&coefficients, b"key");
+            for &code in &codes {
+                assert_eq!(extract_group(&coefficients, &mut walk, k), Some(code));
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrong_key_and_errors() {
+        let mut image = JpegImage::parse(FIXTURES[0]).unwrap();
+        let coefficients = image.coefficients().to_vec();
+
+        let too_long = vec![0u8; f5_capacity(&image) / 8 * 2];
+        assert!(
+            f5_embed(&mut image, &too_long, b"key")
+                .unwrap_err()
+                .starts_with("Not enough capacity")
+        );
+        assert_eq!(image.coefficients(), coefficients);
+
+        f5_embed(&mut image, b"secret", b"key").unwrap();
+        assert_ne!(
+            f5_extract(&image, b"other key").ok(),
+            Some(b"secret".to_vec())
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Huffman tables of baseline JPEG (ITU T.81, annexes C, F.2.2.3 and K.2).
+
+use alloc::{format, string::String, vec::Vec};
+
+/// A Huffman table as stored in a DHT segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HuffmanTable {
+    /// Number of codes of each length, from 1 to 16 bits.
+    pub(crate) counts: [u8; 16],
+    /// Symbols, by increasing code length.
+    pub(crate) symbols: Vec<u8>,
+}
+
+/// Decoding tables of a [`HuffmanTable`].
+#[derive(Debug, Clone)]
+pub(crate) struct HuffmanDecoder {
+    /// Largest code of each length, or -1 if there is none.
+    max_code: [i32; 17],
+    /// Offset to add to a code of each length to get the index of its symbol.
+    offset: [i32; 17],
+    symbols: Vec<u8>,
+}
+
+/// Encoding tables of a [`HuffmanTable`]: code and length of each symbol.
+#[derive(Debug, Clone)]
+pub(crate) struct HuffmanEncoder {
+    codes: [Option<(u16, u8)>; 256],
+}
+
+impl HuffmanTable {
+    /// Parses the tables of a DHT segment payload. Returns `(class, id, table)` triples.
+    pub(crate) fn parse_segment(mut data: &[u8]) -> Result<Vec<(u8, u8, HuffmanTable)>, String> {
+        let mut tables = Vec::new();
+        while !data.is_empty() {
+            if data.len() < 17 {
+                return Err("Invalid JPEG: truncated DHT segment".into());
+            }
+            let (class, id) = (data[0] >> 4, data[0] & 0x0F);
+            if class > 1 || id > 3 {
+                return Err(format!("Invalid JPEG: bad Huffman table {}/{}", class, id));
+            }
+            let mut counts = [0u8; 16];
+            counts.copy_from_slice(&data[1..17]);
+            let total: usize = counts.iter().map(|&c| c as usize).sum();
+            if data.len() < 17 + total || total > 256 {
+                return Err("Invalid JPEG: truncated DHT segment".into());
+            }
+            let symbols = data[17..17 + total].to_vec();
+            tables.push((class, id, HuffmanTable { counts, symbols }));
+            data = &data[17 + total..];
+        }
+        Ok(tables)
+    }
+
+    /// Appends the table to a DHT segment payload.
+    pub(crate) fn write(&self, class: u8, id: u8, out: &mut Vec<u8>) {
+        out.push((class << 4) | id);
+        out.extend_from_slice(&self.counts);
+        out.extend_from_slice(&self.symbols);
+    }
+
+    /// Canonical codes of the symbols, in the order of `symbols` (annex C).
+    fn codes(&self) -> impl Iterator<Item = (u16, u8)> + '_ {
+        let mut code = 0u32;
+        (0..16).flat_map(move |len| {
+            let count = self.counts[len] as u32;
+            let first = code;
+            code = (code + count) << 1;
+            (0..count).map(move |i| ((first + i) as u16, len as u8 + 1))
+        })
+    }
+
+    pub(crate) fn decoder(&self) -> HuffmanDecoder {
+        let mut max_code = [-1i32; 17];
+        let mut offset = [0i32; 17];
+        let mut code = 0i32;
+        let mut index = 0i32;
+        for len in 1..=16 {
+            let count = self.counts[len - 1] as i32;
+            if count > 0 {
+                offset[len] = index - code;
+                code += count;
+                index += count;
+                max_code[len] = code - 1;
+            }
+            code <<= 1;
+        }
+        HuffmanDecoder {
+            max_code,
+            offset,
+            symbols: self.symbols.clone(),
+        }
+    }
+
+    pub(crate) fn encoder(&self) -> HuffmanEncoder {
+        let mut codes = [None; 256];
+        for (&symbol, code) in self.symbols.iter().zip(self.codes()) {
+            codes[symbol as usize] = Some(code);
+        }
+        HuffmanEncoder { codes }
+    }
+
+    /// Builds an optimal table for the given symbol frequencies, with codes of at most
+    /// 16 bits and no all-ones code (annex K.2).
+    pub(crate) fn optimal(frequencies: &[u64; 256]) -> Self {
+        // A reserved symbol with frequency 1 keeps the all-ones code unused
+        let mut freq = [0u64; 257];
+        freq[..256].copy_from_slice(frequencies);
+        if freq.iter().all(|&f| f == 0) {
+            freq[0] = 1; // a table needs at least one symbol
+        }
+        freq[256] = 1;
+        let mut code_size = [0usize; 257];
+        let mut others = [usize::MAX; 257];
+
+        loop {
+            // The two least frequent entries, v1 having the largest symbol on ties
+            let mut v1 = None;
+            let mut v2 = None;
+            for i in 0..257 {
+                if freq[i] == 0 {
+                    continue;
+                }
+                if v1.is_none_or(|v: usize| freq[i] <= freq[v]) {
+                    v1 = Some(i);
+                }
+            }
+            for i in 0..257 {
+                if freq[i] == 0 || Some(i) == v1 {
+                    continue;
+                }
+                if v2.is_none_or(|v: usize| freq[i] <= freq[v]) {
+                    v2 = Some(i);
+                }
+            }
+            let (Some(mut c1), Some(mut c2)) = (v1, v2) else {
+                break;
+            };
+
+            freq[c1] += freq[c2];
+            freq[c2] = 0;
+            code_size[c1] += 1;
+            while others[c1] != usize::MAX {
+                c1 = others[c1];
+                code_size[c1] += 1;
+            }
+            others[c1] = c2;
+            code_size[c2] += 1;
+            while others[c2] != usize::MAX {
+                c2 = others[c2];
+                code_size[c2] += 1;
+            }
+        }
+
+        let mut bits = [0usize; 33];
+        for &size in code_size.iter().filter(|&&size| size > 0) {
+            bits[size.min(32)] += 1;
+        }
+
+        // Limit the code lengths to 16 bits
+        for i in (17..=32).rev() {
+            while bits[i] > 0 {
+                let mut j = i - 2;
+                while bits[j] == 0 {
+                    j -= 1;
+                }
+                bits[i] -= 2;
+                bits[i - 1] += 1;
+                bits[j + 1] += 2;
+                bits[j] -= 1;
+            }
+        }
+        // Remove the reserved code from the longest length
+        let mut i = 16;
+        while bits[i] == 0 {
+            i -= 1;
+        }
+        bits[i] -= 1;
+
+        let mut counts = [0u8; 16];
+        for (len, count) in counts.iter_mut().enumerate() {
+            *count = bits[len + 1] as u8;
+        }
+        let mut symbols = Vec::new();
+        for size in 1..=32 {
+            symbols.extend((0..256).filter(|&s| code_size[s] == size).map(|s| s as u8));
+        }
+        HuffmanTable { counts, symbols }
+    }
+}
+
+impl HuffmanDecoder {
+    /// Decodes one symbol, reading the bits one by one.
+    pub(crate) fn decode(
+        &self,
+        mut next_bit: impl FnMut() -> Result<u32, String>,
+    ) -> Result<u8, String> {
+        let mut code = 0i32;
+        for len in 1..=16 {
+            code = (code << 1) | next_bit()? as i32;
+            if code <= self.max_code[len] {
+                return Ok(self.symbols[(code + self.offset[len]) as usize]);
+            }
+        }
+        Err("Invalid JPEG: bad Huffman code".into())
+    }
+}
+
+impl HuffmanEncoder {
+    /// Returns the code and the code length of a symbol.
+    pub(crate) fn code(&self, symbol: u8) -> Option<(u16, u8)> {
+        self.codes[symbol as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimal_table_round_trip() {
+        let mut frequencies = [0u64; 256];
+        for (i, f) in [1000u64, 500, 200, 100, 50, 20, 10, 5, 2, 1, 1]
+            .iter()
+            .enumerate()
+        {
+            frequencies[i * 3] = *f;
+        }
+        let table = HuffmanTable::optimal(&frequencies);
+        assert_eq!(table.symbols.len(), 11);
+        assert_eq!(table.symbols[0], 0); // the most frequent symbol has the shortest code
+
+        let encoder = table.encoder();
+        let decoder = table.decoder();
+        for s in (0..11).map(|i| i as u8 * 3) {
+            let (code, len) = encoder.code(s).unwrap();
+            assert_ne!(code, (1 << len) - 1); // no all-ones code
+            let mut bits = (0..len).rev().map(|i| ((code >> i) & 1) as u32);
+            assert_eq!(decoder.decode(|| Ok(bits.next().unwrap())).unwrap(), s);
+        }
+        assert!(encoder.code(1).is_none());
+    }
+
+    #[test]
+    fn test_optimal_table_limits_code_length() {
+        // Fibonacci frequencies produce codes longer than 16 bits without the limit
+        let mut frequencies = [0u64; 256];
+        let (mut a, mut b) = (1u64, 1u64);
+        for f in frequencies.iter_mut().take(30) {
+            *f = a;
+            (a, b) = (b, a + b);
+        }
+        let table = HuffmanTable::optimal(&frequencies);
+        assert_eq!(table.symbols.len(), 30);
+        let encoder = table.encoder();
+        assert!((0..30).all(|s| encoder.code(s).unwrap().1 <= 16));
+    }
+}
@@ -0,0 +1,192 @@
+//! JSteg: LSB replacement in the quantized AC coefficients of a JPEG image.
+//!
+//! Coefficients equal to 0 or 1 are skipped: zeros make up most of the AC coefficients and
+//! changing them would be easy to detect (and would grow the file), and a 1 could become a
+//! 0. LSB replacement on the two's complement value maps every other coefficient to a value
+//! that is not 0 or 1 either (`2 ↔ 3`, `-1 ↔ -2`, ...), so the decoder finds the same
+//! carriers. The DC coefficients are never modified.
+//!
+//! The secret is framed with a 4-byte big-endian length prefix ([`LengthPrefix::default`]),
+//! so [`jsteg_extract`] only needs the image.
+
+use alloc::{format, string::String, vec::Vec};
+
+use super::JpegImage;
+use crate::bitplane::{BitplaneOptions, bitplane_embed_with_locator, bitplane_extract_iter};
+use crate::embedding_locator::PositionListTraversal;
+use crate::framing::LengthPrefix;
+
+/// Returns `true` if a coefficient carries a bit.
+///
+/// `-1024` and `-1023` are skipped as well: `-1023` would become `-1024`, which baseline JPEG
+/// cannot code as an AC coefficient.
+fn is_carrier(value: i16) -> bool {
+    !matches!(value, 0 | 1 | -1024 | -1023)
+}
+
+/// Returns the indices, in [`JpegImage::coefficients`], of the AC coefficients that carry
+/// the secret, in storage order (component by component, block by block, zigzag order).
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::jpeg::jsteg::jsteg_positions;
+///
+/// let mut coefficients = vec![0i16; 128];
+/// coefficients[0] = 40; // DC coefficients are never used
+/// coefficients[3] = -1;
+/// coefficients[5] = 1;
+/// coefficients[70] = 2;
+/// assert_eq!(jsteg_positions(&coefficients), vec![3, 70]);
+/// ```
+pub fn jsteg_positions(coefficients: &[i16]) -> Vec<usize> {
+    coefficients
+        .iter()
+        .enumerate()
+        .filter(|&(i, &value)| i % 64 != 0 && is_carrier(value))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the number of secret bits the image can hold, excluding the length prefix.
+pub fn jsteg_capacity(image: &JpegImage) -> usize {
+    let prefix_bits = LengthPrefix::default().size * 8;
+    jsteg_positions(image.coefficients())
+        .len()
+        .saturating_sub(prefix_bits)
+}
+
+/// Embeds a secret in the AC coefficients of a JPEG image.
+///
+/// The image is modified in place; use [`JpegImage::to_bytes`] to get the stego file.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::jpeg::JpegImage;
+/// use stegano_rs::jpeg::jsteg::{jsteg_embed, jsteg_extract};
+///
+/// let data = std::fs::read("tests/fixtures/jpeg/gray.jpg").unwrap();
+/// let mut image = JpegImage::parse(&data).unwrap();
+/// jsteg_embed(&mut image, b"DCT").unwrap();
+///
+/// let stego = JpegImage::parse(&image.to_bytes().unwrap()).unwrap();
+/// assert_eq!(jsteg_extract(&stego).unwrap(), b"DCT");
+/// ```
+///
+/// # Errors
+/// Returns an error if the image does not have enough usable coefficients. The image is
+/// left untouched when an error is returned.
+pub fn jsteg_embed(image: &mut JpegImage, secret: &[u8]) -> Result<(), String> {
+    let framed = LengthPrefix::default().frame(secret)?;
+    let positions = jsteg_positions(image.coefficients());
+    let locator = PositionListTraversal {
+        positions: &positions,
+    };
+    bitplane_embed_with_locator(
+        image.coefficients_mut(),
+        &framed,
+        &BitplaneOptions::default(),
+        &locator,
+    )
+}
+
+/// Extracts a secret embedded with [`jsteg_embed`].
+///
+/// # Errors
+/// Returns an error if the length prefix announces more data than the image holds.
+pub fn jsteg_extract(image: &JpegImage) -> Result<Vec<u8>, String> {
+    let prefix = LengthPrefix::default();
+    let options = BitplaneOptions::default();
+    let coefficients = image.coefficients();
+    let positions = jsteg_positions(coefficients);
+
+    let prefix_bits = prefix.size * 8;
+    if positions.len() < prefix_bits {
+        return Err("Not enough coefficients to hold a length prefix".into());
+    }
+    let header = bitplane_extract_iter(
+        coefficients,
+        &options,
+        positions[..prefix_bits].iter().copied(),
+    )?;
+    let total_bits = prefix_bits + prefix.payload_bits(&header)?;
+    if total_bits > positions.len() {
+        return Err(format!(
+            "Length prefix announces {} bits but the image only holds {} bits",
+            total_bits,
+            positions.len()
+        ));
+    }
+
+    let framed = bitplane_extract_iter(
+        coefficients,
+        &options,
+        positions[..total_bits].iter().copied(),
+    )?;
+    prefix.unframe(&framed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestRng;
+
+    const FIXTURES: [&[u8]; 3] = [
+        include_bytes!("../../tests/fixtures/jpeg/gray.jpg"),
+        include_bytes!("../../tests/fixtures/jpeg/color_420.jpg"),
+        include_bytes!("../../tests/fixtures/jpeg/color_444.jpg"),
+    ];
+
+    #[test]
+    fn test_round_trip_through_file() {
+        for (seed, jpeg) in FIXTURES.iter().enumerate() {
+            let mut image = JpegImage::parse(jpeg).unwrap();
+            let capacity = jsteg_capacity(&image);
+            let secret = TestRng::new(seed as u64).bytes(capacity / 8);
+
+            jsteg_embed(&mut image, &secret).unwrap();
+            let bytes = image.to_bytes().unwrap();
+            let stego = JpegImage::parse(&bytes).unwrap();
+            assert_eq!(stego.coefficients(), image.coefficients());
+            assert_eq!(jsteg_extract(&stego).unwrap(), secret);
+        }
+    }
+
+    #[test]
+    fn test_zeros_ones_and_dc_are_untouched() {
+        let cover = JpegImage::parse(FIXTURES[1]).unwrap();
+        let mut stego = cover.clone();
+        let secret = vec![0xA5; jsteg_capacity(&cover) / 8];
+        jsteg_embed(&mut stego, &secret).unwrap();
+
+        let mut changed = 0;
+        for (i, (&before, &after)) in cover
+            .coefficients()
+            .iter()
+            .zip(stego.coefficients())
+            .enumerate()
+        {
+            if i % 64 == 0 || !is_carrier(before) {
+                assert_eq!(before, after);
+            } else {
+                assert!(is_carrier(after));
+                assert!(before.abs_diff(after) <= 1);
+                changed += (before != after) as usize;
+            }
+        }
+        assert!(changed > 0);
+    }
+
+    #[test]
+    fn test_not_enough_capacity() {
+        let mut image = JpegImage::parse(FIXTURES[0]).unwrap();
+        let coefficients = image.coefficients().to_vec();
+        let too_long = vec![0u8; jsteg_capacity(&image) / 8 + 1];
+
+        assert!(
+            jsteg_embed(&mut image, &too_long)
+                .unwrap_err()
+                .starts_with("Not enough space")
+        );
+        assert_eq!(image.coefficients(), coefficients);
+    }
+}
@@ -0,0 +1,327 @@
+//! Keyed pseudo-random streams, used to derive embedding orders from a password.
+//!
+//! [`Keystream`] is the ChaCha20 stream cipher (RFC 8439) used as a generator, keyed by a
+//! 256-bit key. [`Keystream::from_password`] derives the key with SHA-256; this is not a
+//! password hashing function, so weak passwords remain open to dictionary attacks.
+//! [`Keystream::from_entropy`] is keyed from the operating system CSPRNG instead, for data
+//! that must not be reproducible, such as the filling of unused carrier bits.
+
+use alloc::vec::Vec;
+
+/// SHA-256 digest of `data` (FIPS 180-4).
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::keystream::sha256;
+///
+/// let digest = sha256(b"abc");
+/// assert_eq!(&digest[..4], &[0xba, 0x78, 0x16, 0xbf]);
+/// ```
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
+        0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
+        0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
+        0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc,
+        0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+        0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116,
+        0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+        0xc67178f2,
+    ];
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for (&k, &w) in K.iter().zip(&w) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(k)
+                .wrapping_add(w);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            (hh, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (out, word) in digest.chunks_exact_mut(4).zip(h) {
+        out.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// ChaCha20 keystream generator.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::keystream::Keystream;
+///
+/// let mut a = Keystream::from_password(b"correct horse");
+/// let mut b = Keystream::from_password(b"correct horse");
+/// assert_eq!(a.next_u32(), b.next_u32());
+///
+/// // A keyed permutation of 0..10
+/// let order = a.permutation(10);
+/// assert_eq!(order, b.permutation(10));
+/// let mut sorted = order.clone();
+/// sorted.sort();
+/// assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keystream {
+    key: [u32; 8],
+    nonce: [u32; 3],
+    counter: u32,
+    block: [u8; 64],
+    /// Number of bytes of `block` already used.
+    used: usize,
+}
+
+impl Keystream {
+    /// Creates a keystream from a 256-bit key and a 96-bit nonce, starting at block 0.
+    pub fn new(key: &[u8; 32], nonce: &[u8; 12]) -> Self {
+        let word = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let mut key_words = [0u32; 8];
+        for (w, bytes) in key_words.iter_mut().zip(key.chunks_exact(4)) {
+            *w = word(bytes);
+        }
+        let mut nonce_words = [0u32; 3];
+        for (w, bytes) in nonce_words.iter_mut().zip(nonce.chunks_exact(4)) {
+            *w = word(bytes);
+        }
+        Self {
+            key: key_words,
+            nonce: nonce_words,
+            counter: 0,
+            block: [0; 64],
+            used: 64,
+        }
+    }
+
+    /// Creates a keystream keyed by the SHA-256 digest of a password, with a zero nonce.
+    pub fn from_password(password: &[u8]) -> Self {
+        Self::new(&sha256(password), &[0; 12])
+    }
+
+    /// Creates a keystream with a random key, different on every call.
+    ///
+    /// The key is read from the operating system CSPRNG through
+    /// [getrandom](https://crates.io/crates/getrandom). Only available with the `std`
+    /// feature.
+    ///
+    /// # Panics
+    /// Panics if the operating system random source fails.
+    #[cfg(feature = "std")]
+    pub fn from_entropy() -> Self {
+        let mut key = [0u8; 32];
+        getrandom::fill(&mut key).expect("operating system random source failed");
+        Self::new(&key, &[0; 12])
+    }
+
+    /// Computes the next 64-byte block (RFC 8439, section 2.3).
+    fn refill(&mut self) {
+        let mut state = [0u32; 16];
+        state[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+        state[4..12].copy_from_slice(&self.key);
+        state[12] = self.counter;
+        state[13..].copy_from_slice(&self.nonce);
+
+        let mut working = state;
+        for _ in 0..10 {
+            for [a, b, c, d] in [
+                [0, 4, 8, 12],
+                [1, 5, 9, 13],
+                [2, 6, 10, 14],
+                [3, 7, 11, 15],
+                [0, 5, 10, 15],
+                [1, 6, 11, 12],
+                [2, 7, 8, 13],
+                [3, 4, 9, 14],
+            ] {
+                working[a] = working[a].wrapping_add(working[b]);
+                working[d] = (working[d] ^ working[a]).rotate_left(16);
+                working[c] = working[c].wrapping_add(working[d]);
+                working[b] = (working[b] ^ working[c]).rotate_left(12);
+                working[a] = working[a].wrapping_add(working[b]);
+                working[d] = (working[d] ^ working[a]).rotate_left(8);
+                working[c] = working[c].wrapping_add(working[d]);
+                working[b] = (working[b] ^ working[c]).rotate_left(7);
+            }
+        }
+        for (out, (w, s)) in self
+            .block
+            .chunks_exact_mut(4)
+            .zip(working.iter().zip(&state))
+        {
+            out.copy_from_slice(&w.wrapping_add(*s).to_le_bytes());
+        }
+        self.counter = self.counter.wrapping_add(1);
+        self.used = 0;
+    }
+
+    /// Moves to byte `offset` of the stream, counted from block 0.
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    pub(crate) fn seek(&mut self, offset: u64) {
+        self.counter = (offset / 64) as u32;
+        self.refill();
+        self.used = (offset % 64) as usize;
+    }
+
+    /// Fills `out` with the next bytes of the keystream.
+    pub fn fill(&mut self, out: &mut [u8]) {
+        for byte in out {
+            if self.used == 64 {
+                self.refill();
+            }
+            *byte = self.block[self.used];
+            self.used += 1;
+        }
+    }
+
+    /// Returns the next 32 bits of the keystream.
+    pub fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    /// Returns the next 64 bits of the keystream.
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Returns a value uniformly distributed in `0..n` (`n` must not be zero).
+    pub fn below(&mut self, n: usize) -> usize {
+        let n = n as u64;
+        // Reject the values of the last incomplete range to avoid a modulo bias
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % n) as usize;
+            }
+        }
+    }
+
+    /// Shuffles `items` (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+
+    /// Returns `0..n` in a keyed random order.
+    pub fn permutation(&mut self, n: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..n).collect();
+        self.shuffle(&mut order);
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_vectors() {
+        let hex =
+            |digest: [u8; 32]| -> String { digest.iter().map(|b| format!("{:02x}", b)).collect() };
+        assert_eq!(
+            hex(sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Two blocks of padding
+        assert_eq!(
+            hex(sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn test_chacha20_block_vector() {
+        // RFC 8439, section 2.3.2 (block counter 1)
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let nonce = [0, 0, 0, 9, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let mut stream = Keystream::new(&key, &nonce);
+        stream.fill(&mut [0u8; 64]);
+
+        let mut block = [0u8; 16];
+        stream.fill(&mut block);
+        assert_eq!(
+            block,
+            [
+                0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15, 0x50, 0x0f, 0xdd, 0x1f, 0xa3, 0x20,
+                0x71, 0xc4
+            ]
+        );
+    }
+
+    #[test]
+    fn test_different_passwords_give_different_orders() {
+        let a = Keystream::from_password(b"alice").permutation(1000);
+        let b = Keystream::from_password(b"bob").permutation(1000);
+        assert_ne!(a, b);
+        assert!(Keystream::from_password(b"x").below(7) < 7);
+    }
+
+    #[test]
+    fn test_seek() {
+        let mut stream = Keystream::from_password(b"seek");
+        let mut bytes = [0u8; 200];
+        stream.fill(&mut bytes);
+        for offset in [0, 5, 64, 130] {
+            let mut sought = Keystream::from_password(b"seek");
+            sought.seek(offset as u64);
+            let mut tail = [0u8; 16];
+            sought.fill(&mut tail);
+            assert_eq!(tail, bytes[offset..offset + 16]);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_entropy_streams_differ() {
+        let (mut a, mut b) = (Keystream::from_entropy(), Keystream::from_entropy());
+        assert_ne!((a.next_u64(), a.next_u64()), (b.next_u64(), b.next_u64()));
+    }
+}
@@ -1,3 +1,32 @@
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+extern crate alloc;
+
 pub mod embedding_locator;
+pub mod bit_order;
 pub mod bitplane;
-pub mod pvd;
\ No newline at end of file
+pub mod pvd;
+pub mod sample;
+pub mod stego_method;
+#[cfg(feature = "std")]
+pub mod steganalysis;
+pub mod geometry;
+#[cfg(feature = "std")]
+pub mod metrics;
+pub mod visualization;
+pub mod report;
+pub mod framing;
+pub mod lsb_layout;
+pub mod reversible;
+pub mod jpeg;
+pub mod keystream;
+pub(crate) mod deflate;
+pub mod palette;
+pub mod text;
+#[cfg(feature = "std")]
+pub mod multi_slot;
+#[cfg(feature = "std")]
+pub mod deniable;
+
+#[cfg(test)]
+mod test_utils;
@@ -0,0 +1,274 @@
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::bit_order::BitOrder;
+use crate::bitplane::{BitplaneOptions, bitplane_embed_framed, bitplane_extract_framed};
+use crate::embedding_locator::{ChannelTraversal, EmbeddingLocator};
+use crate::framing::{Endianness, LengthPrefix, LengthUnit};
+use crate::geometry::ImageGeometry;
+
+/// Fixed LSB layout of a length-prefixed payload in an interleaved image.
+///
+/// A layout combines a locator (which channels of each pixel carry data, visited
+/// row-major), the bitplane settings, and the framing of the payload. [`layout_embed`]
+/// and [`layout_extract`] write and read a payload in that layout.
+///
+/// The presets describe a bit stream, not a particular program: they are tested against
+/// the golden files of `tests/fixtures/lsb_layout`, written by an independent reference
+/// encoder (`generate.py`). Whether a given stego tool uses the same layout has to be
+/// checked with that tool. The bit stream of each preset is named with the notation of
+/// [zsteg](https://github.com/zed-0xff/zsteg), which can dump it from a PNG
+/// (`zsteg -E <stream> image.png`).
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::lsb_layout::{layout_embed, layout_extract, LsbLayout};
+/// use stegano_rs::geometry::ImageGeometry;
+///
+/// let geometry = ImageGeometry::new(8, 8, 4); // RGBA, alpha is left untouched
+/// let mut image = vec![200u8; geometry.len()];
+/// let layout = LsbLayout::rgb_msb_first_byte_length();
+///
+/// layout_embed(&mut image, &geometry, b"Hi", &layout).unwrap();
+/// assert_eq!(layout_extract(&image, &geometry, &layout).unwrap(), b"Hi");
+/// assert!(image.iter().skip(3).step_by(4).all(|&alpha| alpha == 200));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LsbLayout {
+    /// Channels used in each pixel, in order (`[0, 1, 2]` for RGB, skipping alpha).
+    pub channels: Vec<usize>,
+    /// Number of low bits used in each sample.
+    pub bits_per_sample: u8,
+    /// Order of the bits of each payload byte.
+    pub secret_bit_order: BitOrder,
+    /// Length prefix written in front of the payload.
+    pub length_prefix: LengthPrefix,
+}
+
+impl Default for LsbLayout {
+    /// Returns [`LsbLayout::rgb_msb_first_byte_length`].
+    fn default() -> Self {
+        Self::rgb_msb_first_byte_length()
+    }
+}
+
+impl LsbLayout {
+    /// R, G, B LSBs row-major (alpha skipped), bits MSB first, payload prefixed with its
+    /// length in bytes as a 32-bit big-endian integer.
+    ///
+    /// The bit stream is zsteg's `b1,rgb,msb,xy`; the golden files `stego_rgb.ppm` and
+    /// `stego_rgba.pam` come from the reference encoder.
+    pub fn rgb_msb_first_byte_length() -> Self {
+        Self {
+            channels: vec![0, 1, 2],
+            bits_per_sample: 1,
+            secret_bit_order: BitOrder::MsbFirst,
+            length_prefix: LengthPrefix::default(),
+        }
+    }
+
+    /// R, G, B LSBs row-major (alpha skipped), bits LSB first, payload prefixed with its
+    /// length in bits as a 32-bit little-endian integer.
+    ///
+    /// The bit stream is zsteg's `b1,rgb,lsb,xy`; the golden file `stego_rgb_lsb_first.ppm`
+    /// comes from the reference encoder.
+    pub fn rgb_lsb_first_bit_length() -> Self {
+        Self {
+            channels: vec![0, 1, 2],
+            bits_per_sample: 1,
+            secret_bit_order: BitOrder::LsbFirst,
+            length_prefix: LengthPrefix {
+                size: 4,
+                endianness: Endianness::Little,
+                unit: LengthUnit::Bits,
+            },
+        }
+    }
+
+    /// Returns the locator visiting the carrier samples of an image.
+    pub fn locator(&self, geometry: &ImageGeometry) -> ChannelTraversal<'_> {
+        ChannelTraversal {
+            geometry: *geometry,
+            channels: &self.channels,
+        }
+    }
+
+    /// Returns the bitplane options of the layout.
+    pub fn options(&self) -> BitplaneOptions {
+        BitplaneOptions {
+            bits_to_operate: self.bits_per_sample,
+            secret_bit_order: self.secret_bit_order,
+            ..BitplaneOptions::default()
+        }
+    }
+
+    /// Returns the largest payload, in bytes, that fits in an image (0 if even the
+    /// length prefix does not fit).
+    pub fn capacity(&self, geometry: &ImageGeometry) -> usize {
+        let samples = self.locator(geometry).iter_indices(geometry.len()).count();
+        (samples * self.bits_per_sample as usize / 8).saturating_sub(self.length_prefix.size)
+    }
+
+    fn check(&self, geometry: &ImageGeometry, host_len: usize) -> Result<(), String> {
+        geometry.check(host_len)?;
+        if let Some(&channel) = self.channels.iter().find(|&&c| c >= geometry.channels) {
+            return Err(format!(
+                "channel {} is out of range for {} channels",
+                channel, geometry.channels
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Frames `payload` with the layout's length prefix and embeds it into `host`.
+///
+/// # Errors
+/// Returns an error if the geometry does not match the host, if the layout uses a channel
+/// the image does not have, or if the framed payload does not fit.
+pub fn layout_embed(
+    host: &mut [u8],
+    geometry: &ImageGeometry,
+    payload: &[u8],
+    layout: &LsbLayout,
+) -> Result<(), String> {
+    layout.check(geometry, host.len())?;
+    bitplane_embed_framed(
+        host,
+        payload,
+        &layout.options(),
+        &layout.locator(geometry),
+        &layout.length_prefix,
+    )
+}
+
+/// Reads the length prefix, then extracts exactly the payload it announces.
+///
+/// # Errors
+/// Returns an error if the geometry does not match the host, if the layout uses a channel
+/// the image does not have, or if the length prefix announces more data than the image holds.
+pub fn layout_extract(
+    host: &[u8],
+    geometry: &ImageGeometry,
+    layout: &LsbLayout,
+) -> Result<Vec<u8>, String> {
+    layout.check(geometry, host.len())?;
+    bitplane_extract_framed(
+        host,
+        &layout.options(),
+        &layout.locator(geometry),
+        &layout.length_prefix,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a binary PPM (P6) or PAM (P7) image with a maxval of 255.
+    fn parse_pnm(data: &[u8]) -> (ImageGeometry, Vec<u8>) {
+        let mut fields = Vec::new();
+        let mut pos = 0;
+        let mut token = String::new();
+        let end_of_header = |fields: &Vec<String>| match fields.first().map(String::as_str) {
+            Some("P6") => fields.len() == 4,
+            Some("P7") => fields.last().map(String::as_str) == Some("ENDHDR"),
+            _ => false,
+        };
+        while !end_of_header(&fields) {
+            let c = data[pos] as char;
+            pos += 1;
+            if c.is_ascii_whitespace() {
+                if !token.is_empty() {
+                    fields.push(core::mem::take(&mut token));
+                }
+            } else {
+                token.push(c);
+            }
+        }
+
+        let geometry = if fields[0] == "P6" {
+            ImageGeometry::new(fields[1].parse().unwrap(), fields[2].parse().unwrap(), 3)
+        } else {
+            let value = |key: &str| -> usize {
+                let i = fields.iter().position(|f| f == key).unwrap();
+                fields[i + 1].parse().unwrap()
+            };
+            ImageGeometry::new(value("WIDTH"), value("HEIGHT"), value("DEPTH"))
+        };
+        let pixels = data[pos..].to_vec();
+        assert_eq!(pixels.len(), geometry.len());
+        (geometry, pixels)
+    }
+
+    const MESSAGE: &[u8] = b"Hello, partner! Row-major RGB LSB.";
+
+    const COVER: &[u8] = include_bytes!("../tests/fixtures/lsb_layout/cover.ppm");
+    const STEGO: &[u8] = include_bytes!("../tests/fixtures/lsb_layout/stego_rgb.ppm");
+    const STEGO_LSB_FIRST: &[u8] =
+        include_bytes!("../tests/fixtures/lsb_layout/stego_rgb_lsb_first.ppm");
+    const COVER_RGBA: &[u8] = include_bytes!("../tests/fixtures/lsb_layout/cover_rgba.pam");
+    const STEGO_RGBA: &[u8] = include_bytes!("../tests/fixtures/lsb_layout/stego_rgba.pam");
+
+    #[test]
+    fn test_golden_files_decode() {
+        let cases = [
+            (STEGO, LsbLayout::rgb_msb_first_byte_length()),
+            (STEGO_LSB_FIRST, LsbLayout::rgb_lsb_first_bit_length()),
+            (STEGO_RGBA, LsbLayout::rgb_msb_first_byte_length()),
+        ];
+        for (file, layout) in cases {
+            let (geometry, pixels) = parse_pnm(file);
+            assert_eq!(
+                layout_extract(&pixels, &geometry, &layout).unwrap(),
+                MESSAGE
+            );
+        }
+    }
+
+    #[test]
+    fn test_embed_matches_golden_files() {
+        let cases = [
+            (COVER, STEGO, LsbLayout::rgb_msb_first_byte_length()),
+            (
+                COVER,
+                STEGO_LSB_FIRST,
+                LsbLayout::rgb_lsb_first_bit_length(),
+            ),
+            (
+                COVER_RGBA,
+                STEGO_RGBA,
+                LsbLayout::rgb_msb_first_byte_length(),
+            ),
+        ];
+        for (cover, stego, layout) in cases {
+            let (geometry, mut pixels) = parse_pnm(cover);
+            layout_embed(&mut pixels, &geometry, MESSAGE, &layout).unwrap();
+            assert_eq!(pixels, parse_pnm(stego).1);
+        }
+    }
+
+    #[test]
+    fn test_capacity_and_errors() {
+        let geometry = ImageGeometry::new(4, 4, 3);
+        let layout = LsbLayout::rgb_msb_first_byte_length();
+        assert_eq!(layout.capacity(&geometry), 2); // 48 bits = 6 bytes, minus the prefix
+
+        let mut host = vec![0u8; geometry.len()];
+        assert!(layout_embed(&mut host, &geometry, b"abc", &layout).is_err());
+        layout_embed(&mut host, &geometry, b"ab", &layout).unwrap();
+
+        // A corrupted prefix announcing too much data
+        host[..32].fill(1);
+        assert!(
+            layout_extract(&host, &geometry, &layout)
+                .unwrap_err()
+                .starts_with("Length prefix announces")
+        );
+
+        let gray = ImageGeometry::new(4, 4, 1);
+        assert_eq!(
+            layout_extract(&[0u8; 16], &gray, &layout).unwrap_err(),
+            "channel 1 is out of range for 1 channels"
+        );
+    }
+}
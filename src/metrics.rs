@@ -0,0 +1,538 @@
+//! Image quality metrics comparing a cover buffer with its stego version.
+//!
+//! These metrics measure how visible the embedding is, as a replacement for checking the
+//! output by eye. All functions work on 8-bit buffers; the ones that need the neighbourhood
+//! of a sample take the buffer layout as an [`ImageGeometry`].
+
+use crate::geometry::ImageGeometry;
+
+/// Peak value of an 8-bit sample.
+const PEAK: f64 = 255.0;
+
+/// Summary of the differences between a cover and a stego buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityReport {
+    /// Mean squared error.
+    pub mse: f64,
+    /// Peak signal-to-noise ratio in dB (`f64::INFINITY` for identical buffers).
+    pub psnr: f64,
+    /// Weighted PSNR in dB, see [`weighted_psnr`].
+    pub weighted_psnr: f64,
+    /// Mean structural similarity, see [`ssim`].
+    pub ssim: f64,
+    /// Number of samples that differ.
+    pub changed_samples: usize,
+    /// Largest absolute difference between two samples.
+    pub max_abs_change: u8,
+}
+
+/// Computes all the metrics of this module between `cover` and `stego`.
+///
+/// SSIM uses the default window ([`SsimOptions::default`]).
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+/// use stegano_rs::metrics::compare;
+///
+/// let geometry = ImageGeometry::new(16, 16, 1);
+/// let cover: Vec<u8> = (0..256).map(|i| i as u8).collect();
+/// let mut stego = cover.clone();
+/// stego[10] ^= 1;
+///
+/// let report = compare(&cover, &stego, &geometry).unwrap();
+/// assert_eq!(report.changed_samples, 1);
+/// assert_eq!(report.max_abs_change, 1);
+/// assert!(report.psnr > 70.0);
+/// ```
+pub fn compare(
+    cover: &[u8],
+    stego: &[u8],
+    geometry: &ImageGeometry,
+) -> Result<QualityReport, String> {
+    check_buffers(cover, stego, geometry)?;
+
+    Ok(QualityReport {
+        mse: mse(cover, stego)?,
+        psnr: psnr(cover, stego)?,
+        weighted_psnr: weighted_psnr(cover, stego, geometry)?,
+        ssim: ssim(cover, stego, geometry, &SsimOptions::default())?,
+        changed_samples: changed_samples(cover, stego)?,
+        max_abs_change: max_abs_change(cover, stego)?,
+    })
+}
+
+/// Computes the mean squared error between two buffers of the same length.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::metrics::mse;
+/// assert_eq!(mse(&[10, 20, 30, 40], &[11, 20, 28, 40]).unwrap(), 1.25);
+/// ```
+pub fn mse(cover: &[u8], stego: &[u8]) -> Result<f64, String> {
+    check_lengths(cover, stego)?;
+    if cover.is_empty() {
+        return Err("Buffers cannot be empty".into());
+    }
+
+    let sum: f64 = cover
+        .iter()
+        .zip(stego)
+        .map(|(&c, &s)| (c as f64 - s as f64).powi(2))
+        .sum();
+    Ok(sum / cover.len() as f64)
+}
+
+/// Computes the peak signal-to-noise ratio in dB between two buffers of the same length.
+///
+/// Returns `f64::INFINITY` when both buffers are identical.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::metrics::psnr;
+/// // MSE = 1 -> PSNR = 20 * log10(255) = 48.13 dB
+/// assert!((psnr(&[0, 0], &[1, 1]).unwrap() - 48.1308).abs() < 1e-4);
+/// assert_eq!(psnr(&[5, 5], &[5, 5]).unwrap(), f64::INFINITY);
+/// ```
+pub fn psnr(cover: &[u8], stego: &[u8]) -> Result<f64, String> {
+    Ok(psnr_from_mse(mse(cover, stego)?))
+}
+
+/// Computes the weighted PSNR in dB (Voloshynovskiy et al.).
+///
+/// Each squared error is weighted by the noise visibility function
+/// `NVF = 1 / (1 + theta * local_variance)` of the cover, computed on a 3x3 neighbourhood
+/// of each sample in its channel, with `theta = 100 / max_local_variance`. Changes in
+/// textured regions are less visible than in flat ones, so they weigh less.
+///
+/// Returns `f64::INFINITY` when both buffers are identical.
+pub fn weighted_psnr(cover: &[u8], stego: &[u8], geometry: &ImageGeometry) -> Result<f64, String> {
+    check_buffers(cover, stego, geometry)?;
+
+    let variances = local_variances(cover, geometry);
+    let max_variance = variances.iter().cloned().fold(0.0, f64::max);
+    let theta = if max_variance > 0.0 {
+        100.0 / max_variance
+    } else {
+        0.0
+    };
+
+    let weighted_sum: f64 = cover
+        .iter()
+        .zip(stego)
+        .zip(&variances)
+        .map(|((&c, &s), &variance)| {
+            let nvf = 1.0 / (1.0 + theta * variance);
+            (nvf * (c as f64 - s as f64)).powi(2)
+        })
+        .sum();
+
+    Ok(psnr_from_mse(weighted_sum / cover.len() as f64))
+}
+
+/// Window used to compute local statistics in [`ssim`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SsimWindow {
+    /// Square window of `size` x `size` samples with equal weights.
+    Uniform {
+        /// Side of the window in samples.
+        size: usize,
+    },
+    /// Square window of `size` x `size` samples with Gaussian weights.
+    Gaussian {
+        /// Side of the window in samples.
+        size: usize,
+        /// Standard deviation of the Gaussian, in samples.
+        sigma: f64,
+    },
+}
+
+/// Options for [`ssim`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsimOptions {
+    /// The window used for the local means, variances and covariance.
+    pub window: SsimWindow,
+    /// Stabilization constant `K1` of the luminance term.
+    pub k1: f64,
+    /// Stabilization constant `K2` of the contrast-structure term.
+    pub k2: f64,
+}
+
+impl Default for SsimOptions {
+    /// Returns the parameters of Wang et al.: an 11x11 Gaussian window with a standard
+    /// deviation of 1.5, `K1 = 0.01` and `K2 = 0.03`.
+    fn default() -> Self {
+        Self {
+            window: SsimWindow::Gaussian {
+                size: 11,
+                sigma: 1.5,
+            },
+            k1: 0.01,
+            k2: 0.03,
+        }
+    }
+}
+
+/// Computes the mean structural similarity (SSIM) between a cover and a stego buffer.
+///
+/// The window slides over every position where it fits entirely inside the image, on each
+/// channel separately. The result is the mean SSIM over all windows and channels, between
+/// -1 and 1 (1 for identical buffers).
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+/// use stegano_rs::metrics::{ssim, SsimOptions, SsimWindow};
+///
+/// let geometry = ImageGeometry::new(16, 16, 1);
+/// let cover: Vec<u8> = (0..256).map(|i| (i * 7 % 256) as u8).collect();
+/// let options = SsimOptions { window: SsimWindow::Uniform { size: 8 }, ..SsimOptions::default() };
+/// assert!((ssim(&cover, &cover, &geometry, &options).unwrap() - 1.0).abs() < 1e-12);
+/// ```
+pub fn ssim(
+    cover: &[u8],
+    stego: &[u8],
+    geometry: &ImageGeometry,
+    options: &SsimOptions,
+) -> Result<f64, String> {
+    check_buffers(cover, stego, geometry)?;
+
+    let (size, weights) = window_weights(&options.window)?;
+    if size > geometry.width || size > geometry.height {
+        return Err(format!(
+            "SSIM window of size {} does not fit in a {}x{} image",
+            size, geometry.width, geometry.height
+        ));
+    }
+
+    let c1 = (options.k1 * PEAK).powi(2);
+    let c2 = (options.k2 * PEAK).powi(2);
+
+    let mut total = 0.0;
+    let mut windows = 0usize;
+    for channel in 0..geometry.channels {
+        for top in 0..=geometry.height - size {
+            for left in 0..=geometry.width - size {
+                let (mut mean_x, mut mean_y) = (0.0, 0.0);
+                let (mut xx, mut yy, mut xy) = (0.0, 0.0, 0.0);
+                for wy in 0..size {
+                    for wx in 0..size {
+                        let weight = weights[wy * size + wx];
+                        let idx = geometry.index(left + wx, top + wy, channel);
+                        let (x, y) = (cover[idx] as f64, stego[idx] as f64);
+                        mean_x += weight * x;
+                        mean_y += weight * y;
+                        xx += weight * x * x;
+                        yy += weight * y * y;
+                        xy += weight * x * y;
+                    }
+                }
+                let variance_x = xx - mean_x * mean_x;
+                let variance_y = yy - mean_y * mean_y;
+                let covariance = xy - mean_x * mean_y;
+
+                total += ((2.0 * mean_x * mean_y + c1) * (2.0 * covariance + c2))
+                    / ((mean_x * mean_x + mean_y * mean_y + c1) * (variance_x + variance_y + c2));
+                windows += 1;
+            }
+        }
+    }
+
+    Ok(total / windows as f64)
+}
+
+/// Counts the samples that differ between two buffers of the same length.
+pub fn changed_samples(cover: &[u8], stego: &[u8]) -> Result<usize, String> {
+    check_lengths(cover, stego)?;
+    Ok(cover.iter().zip(stego).filter(|(c, s)| c != s).count())
+}
+
+/// Returns the largest absolute difference between two buffers of the same length.
+pub fn max_abs_change(cover: &[u8], stego: &[u8]) -> Result<u8, String> {
+    check_lengths(cover, stego)?;
+    Ok(cover
+        .iter()
+        .zip(stego)
+        .map(|(&c, &s)| c.abs_diff(s))
+        .max()
+        .unwrap_or(0))
+}
+
+/// Per-sample map of the changes between a cover and a stego buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeMap {
+    /// Layout of the map, identical to the layout of the compared buffers.
+    pub geometry: ImageGeometry,
+    /// Absolute difference of each sample, scaled so that the largest change is 255.
+    pub data: Vec<u8>,
+    /// Largest absolute difference before scaling.
+    pub max_abs_change: u8,
+}
+
+impl ChangeMap {
+    /// Encodes the map as a binary PNM image.
+    ///
+    /// One-channel maps are written as PGM (`P5`), three-channel maps as PPM (`P6`). For
+    /// other channel counts, each pixel of the PGM holds the largest change of its channels.
+    pub fn to_pnm(&self) -> Vec<u8> {
+        let (magic, pixels) = match self.geometry.channels {
+            1 => ("P5", self.data.clone()),
+            3 => ("P6", self.data.clone()),
+            channels => (
+                "P5",
+                self.data
+                    .chunks_exact(channels)
+                    .map(|pixel| pixel.iter().copied().max().unwrap_or(0))
+                    .collect(),
+            ),
+        };
+
+        let mut pnm = format!(
+            "{}\n{} {}\n255\n",
+            magic, self.geometry.width, self.geometry.height
+        )
+        .into_bytes();
+        pnm.extend_from_slice(&pixels);
+        pnm
+    }
+}
+
+/// Builds the per-sample change map between a cover and a stego buffer.
+///
+/// The map can be written out as an image with [`ChangeMap::to_pnm`] to see where a method
+/// (bitplane, PVD, ...) modified the host.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+/// use stegano_rs::metrics::change_map;
+///
+/// let geometry = ImageGeometry::new(2, 2, 1);
+/// let map = change_map(&[10, 10, 10, 10], &[10, 12, 10, 11], &geometry).unwrap();
+/// assert_eq!(map.data, vec![0, 255, 0, 127]);
+/// assert!(map.to_pnm().starts_with(b"P5\n2 2\n255\n"));
+/// ```
+pub fn change_map(
+    cover: &[u8],
+    stego: &[u8],
+    geometry: &ImageGeometry,
+) -> Result<ChangeMap, String> {
+    check_buffers(cover, stego, geometry)?;
+
+    let max_abs_change = max_abs_change(cover, stego)?;
+    let data = cover
+        .iter()
+        .zip(stego)
+        .map(|(&c, &s)| {
+            if max_abs_change == 0 {
+                0
+            } else {
+                (c.abs_diff(s) as u32 * 255 / max_abs_change as u32) as u8
+            }
+        })
+        .collect();
+
+    Ok(ChangeMap {
+        geometry: *geometry,
+        data,
+        max_abs_change,
+    })
+}
+
+fn psnr_from_mse(mse: f64) -> f64 {
+    if mse == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (PEAK * PEAK / mse).log10()
+    }
+}
+
+/// Variance of the 3x3 neighbourhood (clipped at the borders) of each sample, per channel.
+fn local_variances(buffer: &[u8], geometry: &ImageGeometry) -> Vec<f64> {
+    let mut variances = vec![0.0; buffer.len()];
+    for y in 0..geometry.height {
+        for x in 0..geometry.width {
+            for channel in 0..geometry.channels {
+                let (mut sum, mut sum_sq, mut count) = (0.0, 0.0, 0.0);
+                for ny in y.saturating_sub(1)..=(y + 1).min(geometry.height - 1) {
+                    for nx in x.saturating_sub(1)..=(x + 1).min(geometry.width - 1) {
+                        let value = buffer[geometry.index(nx, ny, channel)] as f64;
+                        sum += value;
+                        sum_sq += value * value;
+                        count += 1.0;
+                    }
+                }
+                let mean = sum / count;
+                variances[geometry.index(x, y, channel)] = sum_sq / count - mean * mean;
+            }
+        }
+    }
+    variances
+}
+
+/// Returns the side and the normalized weights of an SSIM window.
+fn window_weights(window: &SsimWindow) -> Result<(usize, Vec<f64>), String> {
+    let (size, weights) = match *window {
+        SsimWindow::Uniform { size } => (size, vec![1.0; size * size]),
+        SsimWindow::Gaussian { size, sigma } => {
+            if sigma <= 0.0 {
+                return Err("Gaussian window sigma must be greater than 0".into());
+            }
+            let center = (size as f64 - 1.0) / 2.0;
+            let weights = (0..size * size)
+                .map(|i| {
+                    let dx = (i % size) as f64 - center;
+                    let dy = (i / size) as f64 - center;
+                    (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp()
+                })
+                .collect();
+            (size, weights)
+        }
+    };
+
+    if size == 0 {
+        return Err("SSIM window size must be greater than 0".into());
+    }
+
+    let sum: f64 = weights.iter().sum();
+    Ok((size, weights.into_iter().map(|w| w / sum).collect()))
+}
+
+fn check_lengths(cover: &[u8], stego: &[u8]) -> Result<(), String> {
+    if cover.len() != stego.len() {
+        return Err(format!(
+            "Cover and stego lengths differ: {} != {}",
+            cover.len(),
+            stego.len()
+        ));
+    }
+    Ok(())
+}
+
+fn check_buffers(cover: &[u8], stego: &[u8], geometry: &ImageGeometry) -> Result<(), String> {
+    check_lengths(cover, stego)?;
+    geometry.check(cover.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitplane::{BitplaneOptions, bitplane_embed_with_locator};
+    use crate::embedding_locator::LinearTraversal;
+    use crate::pvd::{PvdOptions, pvd_embed_with_locator};
+    use crate::test_utils::{TestRng, textured_cover};
+
+    const WIDTH: usize = 64;
+    const HEIGHT: usize = 64;
+
+    #[test]
+    fn test_identical_buffers() {
+        let geometry = ImageGeometry::new(WIDTH, HEIGHT, 1);
+        let cover = textured_cover(WIDTH, HEIGHT, 1);
+
+        let report = compare(&cover, &cover, &geometry).unwrap();
+        assert_eq!(report.mse, 0.0);
+        assert_eq!(report.psnr, f64::INFINITY);
+        assert_eq!(report.weighted_psnr, f64::INFINITY);
+        assert!((report.ssim - 1.0).abs() < 1e-12);
+        assert_eq!(report.changed_samples, 0);
+        assert_eq!(report.max_abs_change, 0);
+    }
+
+    #[test]
+    fn test_bitplane_lsb_output() {
+        let geometry = ImageGeometry::new(WIDTH, HEIGHT, 1);
+        let cover = textured_cover(WIDTH, HEIGHT, 1);
+        let mut stego = cover.clone();
+        let secret = TestRng::new(2).bytes(cover.len() / 8);
+        bitplane_embed_with_locator(
+            &mut stego,
+            &secret,
+            &BitplaneOptions::default(),
+            &LinearTraversal,
+        )
+        .unwrap();
+
+        let report = compare(&cover, &stego, &geometry).unwrap();
+        // Random bits change about half of the LSBs, by exactly 1
+        assert_eq!(report.max_abs_change, 1);
+        assert!((report.mse - 0.5).abs() < 0.05);
+        assert!((report.psnr - 51.1).abs() < 0.5);
+        assert!(report.weighted_psnr > report.psnr);
+        assert!(report.ssim > 0.99 && report.ssim < 1.0);
+        assert_eq!(
+            report.changed_samples,
+            change_map(&cover, &stego, &geometry)
+                .unwrap()
+                .data
+                .iter()
+                .filter(|&&v| v == 255)
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_pvd_output_is_more_visible_than_lsb() {
+        let geometry = ImageGeometry::new(WIDTH, HEIGHT, 1);
+        let cover = textured_cover(WIDTH, HEIGHT, 1);
+        let secret = TestRng::new(2).bytes(256);
+
+        let mut lsb = cover.clone();
+        bitplane_embed_with_locator(
+            &mut lsb,
+            &secret,
+            &BitplaneOptions::default(),
+            &LinearTraversal,
+        )
+        .unwrap();
+        let mut pvd = cover.clone();
+        pvd_embed_with_locator(&mut pvd, &secret, &PvdOptions::default(), &LinearTraversal)
+            .unwrap();
+
+        let lsb_report = compare(&cover, &lsb, &geometry).unwrap();
+        let pvd_report = compare(&cover, &pvd, &geometry).unwrap();
+        assert!(pvd_report.max_abs_change > lsb_report.max_abs_change);
+        assert!(pvd_report.psnr < lsb_report.psnr);
+        assert!(pvd_report.ssim < lsb_report.ssim);
+    }
+
+    #[test]
+    fn test_ssim_drops_with_noise() {
+        let geometry = ImageGeometry::new(WIDTH, HEIGHT, 1);
+        let cover = textured_cover(WIDTH, HEIGHT, 1);
+        let mut rng = TestRng::new(3);
+        let noisy: Vec<u8> = cover
+            .iter()
+            .map(|&v| v.saturating_add(rng.below(40) as u8))
+            .collect();
+
+        let uniform = SsimOptions {
+            window: SsimWindow::Uniform { size: 8 },
+            ..SsimOptions::default()
+        };
+        assert!(ssim(&cover, &noisy, &geometry, &uniform).unwrap() < 0.9);
+        assert!(ssim(&cover, &noisy, &geometry, &SsimOptions::default()).unwrap() < 0.9);
+    }
+
+    #[test]
+    fn test_change_map_to_ppm() {
+        let geometry = ImageGeometry::new(2, 1, 3);
+        let map = change_map(&[0, 0, 0, 9, 9, 9], &[0, 4, 0, 9, 9, 1], &geometry).unwrap();
+        assert_eq!(map.max_abs_change, 8);
+        assert_eq!(map.data, vec![0, 127, 0, 0, 0, 255]);
+
+        let mut expected = b"P6\n2 1\n255\n".to_vec();
+        expected.extend_from_slice(&[0, 127, 0, 0, 0, 255]);
+        assert_eq!(map.to_pnm(), expected);
+    }
+
+    #[test]
+    fn test_length_mismatch_error() {
+        assert_eq!(
+            mse(&[1, 2, 3], &[1, 2]).unwrap_err(),
+            "Cover and stego lengths differ: 3 != 2"
+        );
+        let geometry = ImageGeometry::new(2, 2, 1);
+        assert!(compare(&[0; 3], &[0; 3], &geometry).is_err());
+    }
+}
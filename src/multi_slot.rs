@@ -0,0 +1,549 @@
+//! Several independent payloads in one carrier, one key per payload.
+//!
+//! The positions yielded by a locator are split into disjoint slots, with relative sizes
+//! given by a [`SlotLayout`]. Payload `i` goes to slot `i`. The first positions of the slot
+//! hold a random 16-byte salt, and the slot key is derived from the payload key and the
+//! salt with Argon2id ([`KdfParams`]). The slot key permutes the other positions of the
+//! slot (ChaCha20 [`Keystream`]) and encrypts the framed payload, and the rest of the slot
+//! is filled with keystream as well. Slots without a payload are filled with a random
+//! keystream ([`Keystream::from_entropy`]), which even the holders of the other keys cannot
+//! reproduce. Every slot therefore looks like random bits, whether it holds a payload or
+//! not.
+//!
+//! The extractor only needs its own key and the layout: it tries each slot and returns the
+//! payload whose check value matches. A wrong key fails in the same way whether or not the
+//! other slots are used, so a recipient learns nothing about the other payloads.
+//!
+//! A slot holds the salt in clear, then a 4-byte big-endian length prefix, the secret and a
+//! 16-byte check value (the first bytes of `SHA-256(slot key || secret)`), all encrypted.
+//!
+//! # Threat model
+//!
+//! The keys are meant to be passwords, and an attacker holding the carrier can try
+//! passwords offline. The salt makes every slot of every carrier a separate target, so
+//! precomputed tables and batch guessing across carriers do not help, and the memory-hard
+//! KDF makes each guess cost [`KdfParams::memory_kib`] of memory and a few passes over it.
+//! This only slows guessing down: a weak password is still found. The check value
+//! identifies the slot of a key (a wrong key matches with probability 2^-128); it is not
+//! meant to protect the payload against a forger who knows the key. Extraction runs the
+//! KDF once per slot, so its cost grows with the number of slots.
+
+use std::ops::Range;
+
+use crate::bitplane::{BitplaneOptions, bitplane_embed_with_locator, bitplane_extract_iter};
+use crate::embedding_locator::{EmbeddingLocator, PositionListTraversal};
+use crate::framing::LengthPrefix;
+use crate::keystream::{Keystream, sha256};
+use crate::sample::Sample;
+
+/// Bytes of the salt at the start of each slot.
+const SALT_LEN: usize = 16;
+/// Bytes of the check value.
+const CHECK_LEN: usize = 16;
+/// Encrypted bytes added to each secret: the length prefix and the check value.
+const OVERHEAD: usize = 4 + CHECK_LEN;
+
+/// Argon2id parameters used to derive the slot keys from the payload keys.
+///
+/// The parameters must be the same for embedding and extraction. The default is the
+/// Argon2id setting recommended by OWASP: 19 MiB of memory, 2 passes, 1 lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory cost, in KiB.
+    pub memory_kib: u32,
+    /// Number of passes over the memory.
+    pub iterations: u32,
+    /// Number of lanes.
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Derives a 32-byte key from `key` and `salt`.
+    fn derive(&self, key: &[u8], salt: &[u8]) -> Result<[u8; 32], String> {
+        let params =
+            argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+                .map_err(|e| format!("Invalid KDF parameters: {}", e))?;
+        let argon2 =
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+        let mut derived = [0u8; 32];
+        argon2
+            .hash_password_into(key, salt, &mut derived)
+            .map_err(|e| format!("Key derivation failed: {}", e))?;
+        Ok(derived)
+    }
+}
+
+/// Relative sizes of the slots and key derivation settings.
+///
+/// Slot `i` gets about `shares[i] / sum(shares)` of the carrier positions, as a contiguous
+/// range of the locator order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotLayout {
+    /// Relative size of each slot.
+    pub shares: Vec<usize>,
+    /// Parameters of the key derivation.
+    pub kdf: KdfParams,
+}
+
+impl Default for SlotLayout {
+    /// Returns two slots of equal size.
+    fn default() -> Self {
+        Self::equal(2)
+    }
+}
+
+impl SlotLayout {
+    /// Returns `slots` slots of equal size.
+    pub fn equal(slots: usize) -> Self {
+        Self {
+            shares: vec![1; slots],
+            kdf: KdfParams::default(),
+        }
+    }
+
+    /// Splits `positions` carrier positions into the slot ranges.
+    fn ranges(&self, positions: usize) -> Result<Vec<Range<usize>>, String> {
+        let total: usize = self.shares.iter().sum();
+        if total == 0 {
+            return Err("Slot layout must have at least one slot with a non-zero share".into());
+        }
+        let mut ranges = Vec::with_capacity(self.shares.len());
+        let mut cumulative = 0;
+        for &share in &self.shares {
+            let start = positions * cumulative / total;
+            cumulative += share;
+            ranges.push(start..positions * cumulative / total);
+        }
+        Ok(ranges)
+    }
+}
+
+/// A secret and the key that protects it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotPayload<'a> {
+    /// Key of the recipient.
+    pub key: &'a [u8],
+    /// Secret of the recipient.
+    pub secret: &'a [u8],
+}
+
+/// Keystream of slot `index`, keyed by the slot key.
+fn slot_stream(key: &[u8; 32], index: usize) -> Keystream {
+    let mut nonce = [0u8; 12];
+    nonce[..4].copy_from_slice(b"slot");
+    nonce[4..].copy_from_slice(&(index as u64).to_le_bytes());
+    Keystream::new(key, &nonce)
+}
+
+fn check_value(key: &[u8; 32], secret: &[u8]) -> [u8; CHECK_LEN] {
+    let digest = sha256(&[&key[..], secret].concat());
+    let mut check = [0u8; CHECK_LEN];
+    check.copy_from_slice(&digest[..CHECK_LEN]);
+    check
+}
+
+/// Number of samples holding the salt.
+fn salt_samples(bits: usize) -> usize {
+    (SALT_LEN * 8).div_ceil(bits)
+}
+
+/// Number of encrypted bytes a slot of `samples` samples can hold after the salt.
+fn slot_bytes(samples: usize, bits: usize) -> usize {
+    samples.saturating_sub(salt_samples(bits)) * bits / 8
+}
+
+fn xor_keystream(data: &mut [u8], stream: &mut Keystream) {
+    let mut mask = vec![0u8; data.len()];
+    stream.fill(&mut mask);
+    for (byte, m) in data.iter_mut().zip(mask) {
+        *byte ^= m;
+    }
+}
+
+fn carrier_positions<L: EmbeddingLocator + ?Sized>(host_len: usize, locator: &L) -> Vec<usize> {
+    locator
+        .iter_indices(host_len)
+        .filter(|&idx| idx < host_len)
+        .collect()
+}
+
+/// Returns the number of secret bits each slot can hold, excluding the overhead.
+///
+/// # Errors
+/// Returns an error if the layout has no slot with a non-zero share.
+pub fn multi_slot_capacity<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &[S],
+    layout: &SlotLayout,
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<Vec<usize>, String> {
+    let positions = carrier_positions(host.len(), locator).len();
+    let bits = options.bits_to_operate as usize;
+    Ok(layout
+        .ranges(positions)?
+        .into_iter()
+        .map(|range| slot_bytes(range.len(), bits).saturating_sub(OVERHEAD) * 8)
+        .collect())
+}
+
+/// Embeds one payload per slot: `payloads[i]` goes to slot `i`, and every slot of the
+/// layout is overwritten.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::bitplane::BitplaneOptions;
+/// use stegano_rs::embedding_locator::LinearTraversal;
+/// use stegano_rs::multi_slot::{SlotLayout, SlotPayload, multi_slot_embed, multi_slot_extract};
+///
+/// let mut host = vec![0x55u8; 2048];
+/// let layout = SlotLayout::equal(3);
+/// let options = BitplaneOptions::default();
+/// let payloads = [
+///     SlotPayload { key: b"alice", secret: b"for Alice" },
+///     SlotPayload { key: b"bob", secret: b"for Bob" },
+/// ];
+/// multi_slot_embed(&mut host, &payloads, &layout, &options, &LinearTraversal).unwrap();
+///
+/// let extract = |key: &[u8]| multi_slot_extract(&host, key, &layout, &options, &LinearTraversal);
+/// assert_eq!(extract(b"bob").unwrap(), b"for Bob");
+/// assert_eq!(extract(b"alice").unwrap(), b"for Alice");
+/// assert!(extract(b"carol").is_err());
+/// ```
+///
+/// # Errors
+/// Returns an error if there are more payloads than slots, if two payloads use the same
+/// key, if a secret does not fit in its slot, or if the options or the KDF parameters are
+/// invalid. The host is left untouched when an error is returned.
+pub fn multi_slot_embed<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &mut [S],
+    payloads: &[SlotPayload],
+    layout: &SlotLayout,
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<(), String> {
+    embed_slots(
+        host,
+        payloads,
+        layout,
+        options,
+        locator,
+        Keystream::from_entropy(),
+    )
+}
+
+/// [`multi_slot_embed`] with the stream that draws the salts and fills the unused slots.
+fn embed_slots<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &mut [S],
+    payloads: &[SlotPayload],
+    layout: &SlotLayout,
+    options: &BitplaneOptions<S>,
+    locator: &L,
+    mut filler: Keystream,
+) -> Result<(), String> {
+    let positions = carrier_positions(host.len(), locator);
+    let ranges = layout.ranges(positions.len())?;
+    if payloads.len() > ranges.len() {
+        return Err(format!(
+            "{} payloads for a layout of {} slots",
+            payloads.len(),
+            ranges.len()
+        ));
+    }
+    for (i, payload) in payloads.iter().enumerate() {
+        if payloads[..i].iter().any(|other| other.key == payload.key) {
+            return Err("Two payloads use the same key".into());
+        }
+    }
+    let bits = options.bits_to_operate as usize;
+    for (i, (payload, range)) in payloads.iter().zip(&ranges).enumerate() {
+        let available = slot_bytes(range.len(), bits);
+        if payload.secret.len() + OVERHEAD > available {
+            return Err(format!(
+                "Not enough space in slot {} to hide the secret message: capacity={}, needed={}",
+                i,
+                available.saturating_sub(OVERHEAD) * 8,
+                payload.secret.len() * 8
+            ));
+        }
+    }
+
+    // Derive every key before touching the host
+    let mut salts = Vec::with_capacity(payloads.len());
+    let mut keys = Vec::with_capacity(payloads.len());
+    for payload in payloads {
+        let mut salt = [0u8; SALT_LEN];
+        filler.fill(&mut salt);
+        keys.push(layout.kdf.derive(payload.key, &salt)?);
+        salts.push(salt);
+    }
+
+    let salt_samples = salt_samples(bits);
+    for (i, range) in ranges.into_iter().enumerate() {
+        let slot = &positions[range];
+        match payloads.get(i) {
+            Some(payload) => {
+                let locator = PositionListTraversal {
+                    positions: &slot[..salt_samples],
+                };
+                bitplane_embed_with_locator(host, &salts[i], options, &locator)?;
+
+                let mut rest = slot[salt_samples..].to_vec();
+                let mut data = vec![0u8; rest.len() * bits / 8];
+                let mut stream = slot_stream(&keys[i], i);
+                stream.shuffle(&mut rest);
+                let framed = LengthPrefix::default().frame(payload.secret)?;
+                data[..framed.len()].copy_from_slice(&framed);
+                data[framed.len()..framed.len() + CHECK_LEN]
+                    .copy_from_slice(&check_value(&keys[i], payload.secret));
+                xor_keystream(&mut data, &mut stream);
+                let locator = PositionListTraversal { positions: &rest };
+                bitplane_embed_with_locator(host, &data, options, &locator)?;
+            }
+            None => {
+                let mut data = vec![0u8; slot.len() * bits / 8];
+                filler.fill(&mut data);
+                let locator = PositionListTraversal { positions: slot };
+                bitplane_embed_with_locator(host, &data, options, &locator)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the payload embedded with [`multi_slot_embed`] under `key`.
+///
+/// # Errors
+/// Returns an error if no slot holds a payload for this key, or if the options or the KDF
+/// parameters are invalid.
+pub fn multi_slot_extract<S: Sample, L: EmbeddingLocator + ?Sized>(
+    host: &[S],
+    key: &[u8],
+    layout: &SlotLayout,
+    options: &BitplaneOptions<S>,
+    locator: &L,
+) -> Result<Vec<u8>, String> {
+    let positions = carrier_positions(host.len(), locator);
+    let bits = options.bits_to_operate as usize;
+    let prefix = LengthPrefix::default();
+    let salt_samples = salt_samples(bits);
+
+    for (i, range) in layout.ranges(positions.len())?.into_iter().enumerate() {
+        let available = slot_bytes(range.len(), bits);
+        if available < OVERHEAD {
+            continue;
+        }
+        let slot = &positions[range];
+        let mut salt = bitplane_extract_iter(host, options, slot[..salt_samples].iter().copied())?;
+        salt.truncate(SALT_LEN);
+        let slot_key = layout.kdf.derive(key, &salt)?;
+
+        let mut rest = slot[salt_samples..].to_vec();
+        let mut stream = slot_stream(&slot_key, i);
+        stream.shuffle(&mut rest);
+
+        let read = |bytes: usize| -> Result<Vec<u8>, String> {
+            let samples = (bytes * 8).div_ceil(bits);
+            let mut data = bitplane_extract_iter(host, options, rest[..samples].iter().copied())?;
+            data.truncate(bytes);
+            xor_keystream(&mut data, &mut stream.clone());
+            Ok(data)
+        };
+        let header = read(prefix.size)?;
+        let len = prefix.payload_bits(&header)? / 8;
+        if len > available - OVERHEAD {
+            continue;
+        }
+        let data = read(len + OVERHEAD)?;
+        let secret = &data[prefix.size..prefix.size + len];
+        if data[prefix.size + len..] == check_value(&slot_key, secret) {
+            return Ok(secret.to_vec());
+        }
+    }
+    Err("No payload found for this key".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding_locator::LinearTraversal;
+    use crate::test_utils::{TestRng, natural_cover};
+
+    /// Cheap parameters, to keep the tests fast.
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn test_each_key_reveals_its_payload() {
+        let cover = natural_cover(64, 64, 3);
+        let layout = SlotLayout {
+            shares: vec![1, 2, 1],
+            kdf: TEST_KDF,
+        };
+        let options = BitplaneOptions {
+            bits_to_operate: 2,
+            ..BitplaneOptions::default()
+        };
+        let capacity = multi_slot_capacity(&cover, &layout, &options, &LinearTraversal).unwrap();
+        // 64 samples of salt, then 20 bytes of overhead
+        assert_eq!(
+            capacity,
+            vec![(240 - 20) * 8, (496 - 20) * 8, (240 - 20) * 8]
+        );
+
+        let secrets: Vec<Vec<u8>> = capacity
+            .iter()
+            .enumerate()
+            .map(|(i, &bits)| TestRng::new(i as u64 + 1).bytes(bits / 8 - i))
+            .collect();
+        let keys: [&[u8]; 3] = [b"alice", b"bob", b"carol"];
+        let payloads: Vec<SlotPayload> = keys
+            .iter()
+            .zip(&secrets)
+            .map(|(key, secret)| SlotPayload { key, secret })
+            .collect();
+
+        let mut host = cover.clone();
+        multi_slot_embed(&mut host, &payloads, &layout, &options, &LinearTraversal).unwrap();
+        for (key, secret) in keys.iter().zip(&secrets) {
+            let extracted =
+                multi_slot_extract(&host, key, &layout, &options, &LinearTraversal).unwrap();
+            assert_eq!(&extracted, secret);
+        }
+        assert_eq!(
+            multi_slot_extract(&host, b"mallory", &layout, &options, &LinearTraversal).unwrap_err(),
+            "No payload found for this key"
+        );
+    }
+
+    #[test]
+    fn test_slots_are_disjoint() {
+        let cover = natural_cover(48, 48, 5);
+        let layout = SlotLayout {
+            kdf: TEST_KDF,
+            ..SlotLayout::equal(3)
+        };
+        let options = BitplaneOptions::default();
+        let alice = SlotPayload {
+            key: b"alice",
+            secret: b"only Alice",
+        };
+        let bob = SlotPayload {
+            key: b"bob",
+            secret: b"only Bob",
+        };
+
+        let filler = || Keystream::new(&[7; 32], &[0; 12]);
+        let mut alone = cover.clone();
+        embed_slots(
+            &mut alone,
+            &[alice],
+            &layout,
+            &options,
+            &LinearTraversal,
+            filler(),
+        )
+        .unwrap();
+        let mut both = cover.clone();
+        embed_slots(
+            &mut both,
+            &[alice, bob],
+            &layout,
+            &options,
+            &LinearTraversal,
+            filler(),
+        )
+        .unwrap();
+
+        // With the same salt, slot 0 is the same whether or not Bob's payload exists;
+        // unused slots are filled
+        let third = cover.len() / 3;
+        assert_eq!(alone[..third], both[..third]);
+        assert_ne!(alone[third..], both[third..]);
+        let changed = |range: Range<usize>| range.filter(|&i| alone[i] != cover[i]).count();
+        assert!(changed(third..2 * third) > third / 4);
+        assert!(changed(2 * third..cover.len()) > third / 4);
+
+        let extracted = multi_slot_extract(&alone, b"alice", &layout, &options, &LinearTraversal);
+        assert_eq!(extracted.unwrap(), b"only Alice");
+        assert!(multi_slot_extract(&alone, b"bob", &layout, &options, &LinearTraversal).is_err());
+
+        // Without a fixed filler, every embedding draws a new salt
+        let mut again = cover.clone();
+        multi_slot_embed(&mut again, &[alice], &layout, &options, &LinearTraversal).unwrap();
+        assert_ne!(alone[..third], again[..third]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut host = natural_cover(32, 32, 1);
+        let cover = host.clone();
+        let options = BitplaneOptions::default();
+        let layout = SlotLayout {
+            kdf: TEST_KDF,
+            ..SlotLayout::equal(2)
+        };
+        let embed = |host: &mut [u8], payloads: &[SlotPayload]| {
+            multi_slot_embed(host, payloads, &layout, &options, &LinearTraversal)
+        };
+        let payload = |key: &'static [u8], secret: &'static [u8]| SlotPayload { key, secret };
+
+        assert!(embed(&mut host, &[payload(b"a", b"1"), payload(b"a", b"2")]).is_err());
+        let three = [
+            payload(b"a", b"1"),
+            payload(b"b", b"2"),
+            payload(b"c", b"3"),
+        ];
+        assert!(embed(&mut host, &three).is_err());
+        // 512 samples per slot: 16 bytes of salt, then 48 bytes for the overhead and secret
+        let too_long = [0u8; 48 - OVERHEAD + 1];
+        let result = embed(
+            &mut host,
+            &[SlotPayload {
+                key: b"a",
+                secret: &too_long,
+            }],
+        );
+        assert!(
+            result
+                .unwrap_err()
+                .starts_with("Not enough space in slot 0")
+        );
+        assert_eq!(host, cover);
+
+        let weak = SlotLayout {
+            kdf: KdfParams {
+                memory_kib: 1,
+                ..TEST_KDF
+            },
+            ..SlotLayout::equal(2)
+        };
+        let result = multi_slot_embed(
+            &mut host,
+            &[payload(b"a", b"1")],
+            &weak,
+            &options,
+            &LinearTraversal,
+        );
+        assert!(result.unwrap_err().starts_with("Invalid KDF parameters"));
+        assert_eq!(host, cover);
+
+        let empty = SlotLayout {
+            shares: vec![0],
+            kdf: TEST_KDF,
+        };
+        assert!(multi_slot_capacity(&host, &empty, &options, &LinearTraversal).is_err());
+    }
+}
@@ -0,0 +1,311 @@
+//! Palette (indexed color) images as carriers: GIF and palette PNG.
+//!
+//! In an indexed image each pixel is an index into a palette, and palettes are rarely
+//! sorted: indices `2k` and `2k + 1` can be unrelated colors, so LSB embedding on the
+//! indices shows as noise of random colors. [`palette_order`] sorts the palette by
+//! luminance or into a chain of nearest colors, so that neighbours in the sorted order look
+//! alike. Embedding methods then work on the rank of each index in that order; the palette
+//! itself is left unchanged in the file, so the extractor derives the same order from the
+//! stego file alone.
+//!
+//! [`IndexedImage::parse`] reads the first image of a GIF file or a non-interlaced palette
+//! PNG, and [`IndexedImage::to_bytes`] writes the file back with the new indices, copying
+//! every other block or chunk as is.
+//!
+//! Available methods:
+//! - [`ezstego`]: LSB embedding on the sorted palette ranks (EzStego).
+
+pub mod ezstego;
+mod gif;
+mod png;
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use gif::GifImage;
+use png::PngImage;
+
+/// File format of an [`IndexedImage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteFormat {
+    Gif,
+    Png,
+}
+
+/// Order in which the palette is sorted before embedding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PaletteOrder {
+    /// Chain of nearest colors: starting from the darkest color, each next color is the
+    /// closest remaining one (Euclidean distance in RGB), as in EzStego.
+    #[default]
+    NearestNeighbor,
+    /// Increasing luminance (ITU-R BT.601 weights).
+    Luminance,
+}
+
+#[derive(Debug, Clone)]
+enum Container {
+    Gif(GifImage),
+    Png(PngImage),
+}
+
+/// An indexed color image read from a GIF or PNG file.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::palette::{IndexedImage, PaletteFormat};
+///
+/// let data = std::fs::read("tests/fixtures/palette/palette8.png").unwrap();
+/// let mut image = IndexedImage::parse(&data).unwrap();
+/// assert_eq!(image.format(), PaletteFormat::Png);
+/// assert_eq!((image.width(), image.height()), (53, 29));
+///
+/// image.indices_mut()[0] = 3;
+/// let reparsed = IndexedImage::parse(&image.to_bytes().unwrap()).unwrap();
+/// assert_eq!(reparsed.indices()[0], 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexedImage {
+    source: Vec<u8>,
+    container: Container,
+    indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// Parses a GIF file (first image) or a non-interlaced palette PNG file.
+    ///
+    /// # Errors
+    /// Returns an error if the data is not a valid GIF or PNG file, or is a PNG file that is
+    /// not palette-based or is interlaced.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let container = if data.starts_with(b"GIF") {
+            Container::Gif(GifImage::parse(data)?)
+        } else if data.starts_with(b"\x89PNG") {
+            Container::Png(PngImage::parse(data)?)
+        } else {
+            return Err("Unsupported file: expected a GIF or PNG file".into());
+        };
+        let indices = match &container {
+            Container::Gif(gif) => gif.indices.clone(),
+            Container::Png(png) => png.indices.clone(),
+        };
+        Ok(Self {
+            source: data.to_vec(),
+            container,
+            indices,
+        })
+    }
+
+    /// File format of the image.
+    pub fn format(&self) -> PaletteFormat {
+        match self.container {
+            Container::Gif(_) => PaletteFormat::Gif,
+            Container::Png(_) => PaletteFormat::Png,
+        }
+    }
+
+    /// Width of the image in pixels.
+    pub fn width(&self) -> usize {
+        match &self.container {
+            Container::Gif(gif) => gif.width,
+            Container::Png(png) => png.width,
+        }
+    }
+
+    /// Height of the image in pixels.
+    pub fn height(&self) -> usize {
+        match &self.container {
+            Container::Gif(gif) => gif.height,
+            Container::Png(png) => png.height,
+        }
+    }
+
+    /// Palette of the image, as RGB triples.
+    pub fn palette(&self) -> &[[u8; 3]] {
+        match &self.container {
+            Container::Gif(gif) => &gif.palette,
+            Container::Png(png) => &png.palette,
+        }
+    }
+
+    /// Palette indices of the pixels, row-major.
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+
+    /// Mutable access to the palette indices.
+    pub fn indices_mut(&mut self) -> &mut [u8] {
+        &mut self.indices
+    }
+
+    /// Writes the file back with the current indices.
+    ///
+    /// The original file is returned as is if the indices are unchanged. Otherwise only
+    /// the image data is re-encoded (LZW for GIF, zlib for PNG).
+    ///
+    /// # Errors
+    /// Returns an error if an index is outside the palette.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let palette_len = self.palette().len();
+        if let Some(&index) = self.indices.iter().find(|&&i| i as usize >= palette_len) {
+            return Err(format!(
+                "Index {} is out of range for a palette of {} colors",
+                index, palette_len
+            ));
+        }
+        Ok(match &self.container {
+            Container::Gif(gif) if gif.indices != self.indices => {
+                gif.write(&self.source, &self.indices)
+            }
+            Container::Png(png) if png.indices != self.indices => {
+                png.write(&self.source, &self.indices)
+            }
+            _ => self.source.clone(),
+        })
+    }
+}
+
+/// Luminance of a color, scaled by 1000.
+fn luminance([r, g, b]: [u8; 3]) -> u32 {
+    299 * r as u32 + 587 * g as u32 + 114 * b as u32
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(&b)
+        .map(|(&x, &y)| (x as i32 - y as i32).pow(2) as u32)
+        .sum()
+}
+
+/// Returns the palette indices sorted in the given order. Ties are broken by index.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::palette::{PaletteOrder, palette_order};
+///
+/// let palette = [[255, 255, 255], [0, 0, 0], [250, 250, 250], [10, 0, 0]];
+/// assert_eq!(palette_order(&palette, PaletteOrder::Luminance), vec![1, 3, 2, 0]);
+/// assert_eq!(palette_order(&palette, PaletteOrder::NearestNeighbor), vec![1, 3, 2, 0]);
+/// ```
+pub fn palette_order(palette: &[[u8; 3]], order: PaletteOrder) -> Vec<usize> {
+    let mut sorted: Vec<usize> = (0..palette.len()).collect();
+    sorted.sort_by_key(|&i| (luminance(palette[i]), i));
+    if order == PaletteOrder::Luminance || sorted.is_empty() {
+        return sorted;
+    }
+
+    let mut chain = vec![sorted[0]];
+    let mut remaining: Vec<usize> = (0..palette.len()).filter(|&i| i != sorted[0]).collect();
+    while !remaining.is_empty() {
+        let last = palette[*chain.last().unwrap()];
+        let (pos, _) = remaining
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &i)| (distance(last, palette[i]), i))
+            .unwrap();
+        chain.push(remaining.remove(pos));
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// File name, file, reference palette (RGB triples) and reference indices.
+    type Fixture = (&'static str, &'static [u8], &'static [u8], &'static [u8]);
+
+    pub(super) const FIXTURES: [Fixture; 4] = [
+        (
+            "palette.gif",
+            include_bytes!("../tests/fixtures/palette/palette.gif"),
+            include_bytes!("../tests/fixtures/palette/palette.pal"),
+            include_bytes!("../tests/fixtures/palette/palette.idx"),
+        ),
+        (
+            "interlaced.gif",
+            include_bytes!("../tests/fixtures/palette/interlaced.gif"),
+            include_bytes!("../tests/fixtures/palette/interlaced.pal"),
+            include_bytes!("../tests/fixtures/palette/interlaced.idx"),
+        ),
+        (
+            "palette8.png",
+            include_bytes!("../tests/fixtures/palette/palette8.png"),
+            include_bytes!("../tests/fixtures/palette/palette8.pal"),
+            include_bytes!("../tests/fixtures/palette/palette8.idx"),
+        ),
+        (
+            "palette4.png",
+            include_bytes!("../tests/fixtures/palette/palette4.png"),
+            include_bytes!("../tests/fixtures/palette/palette4.pal"),
+            include_bytes!("../tests/fixtures/palette/palette4.idx"),
+        ),
+    ];
+
+    #[test]
+    fn test_parse_matches_reference() {
+        for (name, file, palette, indices) in FIXTURES {
+            let image = IndexedImage::parse(file).unwrap();
+            assert_eq!(image.palette().concat(), palette, "{}", name);
+            assert_eq!(image.indices(), indices, "{}", name);
+            assert_eq!(image.width() * image.height(), indices.len(), "{}", name);
+            assert_eq!(image.to_bytes().unwrap(), file, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_modified_indices_round_trip() {
+        for (name, file, palette, _) in FIXTURES {
+            let mut image = IndexedImage::parse(file).unwrap();
+            let colors = palette.len() / 3;
+            for (i, index) in image.indices_mut().iter_mut().enumerate() {
+                *index = ((*index as usize + i) % colors) as u8;
+            }
+            let bytes = image.to_bytes().unwrap();
+            assert_ne!(bytes, file, "{}", name);
+
+            let reparsed = IndexedImage::parse(&bytes).unwrap();
+            assert_eq!(reparsed.indices(), image.indices(), "{}", name);
+            assert_eq!(reparsed.palette(), image.palette(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(IndexedImage::parse(b"BM not an image").is_err());
+
+        let (_, file, _, _) = FIXTURES[2];
+        assert!(IndexedImage::parse(&file[..file.len() / 2]).is_err());
+        let mut bad_crc = file.to_vec();
+        bad_crc[20] ^= 1;
+        assert!(
+            IndexedImage::parse(&bad_crc)
+                .unwrap_err()
+                .contains("bad CRC")
+        );
+
+        let mut image = IndexedImage::parse(file).unwrap();
+        image.indices_mut()[0] = 250;
+        assert_eq!(
+            image.to_bytes().unwrap_err(),
+            "Index 250 is out of range for a palette of 200 colors"
+        );
+    }
+
+    #[test]
+    fn test_nearest_neighbor_chain_is_smoother() {
+        let (_, file, _, _) = FIXTURES[0];
+        let palette = IndexedImage::parse(file).unwrap().palette().to_vec();
+        let cost = |order: PaletteOrder| -> u32 {
+            let sorted = palette_order(&palette, order);
+            sorted
+                .windows(2)
+                .map(|w| distance(palette[w[0]], palette[w[1]]))
+                .sum()
+        };
+        let identity: u32 = (0..palette.len() - 1)
+            .map(|i| distance(palette[i], palette[i + 1]))
+            .sum();
+        assert!(cost(PaletteOrder::NearestNeighbor) < identity);
+        assert!(cost(PaletteOrder::Luminance) < identity);
+    }
+}
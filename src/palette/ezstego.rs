@@ -0,0 +1,213 @@
+//! EzStego: LSB embedding on the ranks of the indices in a sorted palette.
+//!
+//! The palette is sorted with [`palette_order`]; each pixel is replaced by its rank `r` in
+//! the sorted order, the secret is embedded in the LSB of `r`, and the pixel gets the index
+//! of the new rank. Ranks `2k` and `2k + 1` are neighbours in the sorted order, so a change
+//! replaces a color by a similar one. With an odd number of colors, the last rank has no
+//! partner and its pixels are skipped.
+//!
+//! The secret is framed with a 4-byte big-endian length prefix ([`LengthPrefix::default`]).
+//! The palette is not modified, so the extractor only needs the stego file and the order.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use super::{IndexedImage, PaletteOrder, palette_order};
+use crate::bitplane::{BitplaneOptions, bitplane_embed_with_locator, bitplane_extract_iter};
+use crate::embedding_locator::PositionListTraversal;
+use crate::framing::LengthPrefix;
+
+/// Ranks of the pixels in the sorted palette, and the inverse mapping.
+struct Ranks {
+    sorted: Vec<usize>,
+    ranks: Vec<u8>,
+    /// Pixels whose rank has a partner.
+    carriers: Vec<usize>,
+}
+
+impl Ranks {
+    fn new(image: &IndexedImage, order: PaletteOrder) -> Result<Self, String> {
+        let palette = image.palette();
+        let sorted = palette_order(palette, order);
+        let mut rank_of = vec![0u8; palette.len()];
+        for (rank, &index) in sorted.iter().enumerate() {
+            rank_of[index] = rank as u8;
+        }
+
+        let mut ranks = Vec::with_capacity(image.indices().len());
+        for &index in image.indices() {
+            let rank = rank_of.get(index as usize).ok_or_else(|| {
+                format!(
+                    "Index {} is out of range for a palette of {} colors",
+                    index,
+                    palette.len()
+                )
+            })?;
+            ranks.push(*rank);
+        }
+        let carriers = ranks
+            .iter()
+            .enumerate()
+            .filter(|&(_, &rank)| (rank as usize ^ 1) < sorted.len())
+            .map(|(i, _)| i)
+            .collect();
+        Ok(Self {
+            sorted,
+            ranks,
+            carriers,
+        })
+    }
+}
+
+/// Returns the number of secret bits the image can hold, excluding the length prefix.
+pub fn ezstego_capacity(image: &IndexedImage, order: PaletteOrder) -> usize {
+    let prefix_bits = LengthPrefix::default().size * 8;
+    Ranks::new(image, order)
+        .map(|ranks| ranks.carriers.len().saturating_sub(prefix_bits))
+        .unwrap_or(0)
+}
+
+/// Embeds a secret in the palette indices of an image.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::palette::{IndexedImage, PaletteOrder};
+/// use stegano_rs::palette::ezstego::{ezstego_embed, ezstego_extract};
+///
+/// let data = std::fs::read("tests/fixtures/palette/palette.gif").unwrap();
+/// let mut image = IndexedImage::parse(&data).unwrap();
+/// ezstego_embed(&mut image, b"indexed", PaletteOrder::default()).unwrap();
+///
+/// let stego = IndexedImage::parse(&image.to_bytes().unwrap()).unwrap();
+/// assert_eq!(ezstego_extract(&stego, PaletteOrder::default()).unwrap(), b"indexed");
+/// ```
+///
+/// # Errors
+/// Returns an error if an index is outside the palette, or if the image does not have
+/// enough pixels. The image is left untouched when an error is returned.
+pub fn ezstego_embed(
+    image: &mut IndexedImage,
+    secret: &[u8],
+    order: PaletteOrder,
+) -> Result<(), String> {
+    let Ranks {
+        sorted,
+        mut ranks,
+        carriers,
+    } = Ranks::new(image, order)?;
+    let framed = LengthPrefix::default().frame(secret)?;
+    let locator = PositionListTraversal {
+        positions: &carriers,
+    };
+    bitplane_embed_with_locator(&mut ranks, &framed, &BitplaneOptions::default(), &locator)?;
+
+    for (index, &rank) in image.indices_mut().iter_mut().zip(&ranks) {
+        *index = sorted[rank as usize] as u8;
+    }
+    Ok(())
+}
+
+/// Extracts a secret embedded with [`ezstego_embed`], with the same palette order.
+///
+/// # Errors
+/// Returns an error if an index is outside the palette, or if the length prefix announces
+/// more data than the image holds.
+pub fn ezstego_extract(image: &IndexedImage, order: PaletteOrder) -> Result<Vec<u8>, String> {
+    let Ranks {
+        ranks, carriers, ..
+    } = Ranks::new(image, order)?;
+    let prefix = LengthPrefix::default();
+    let options = BitplaneOptions::default();
+
+    let prefix_bits = prefix.size * 8;
+    if carriers.len() < prefix_bits {
+        return Err("Not enough pixels to hold a length prefix".into());
+    }
+    let header = bitplane_extract_iter(&ranks, &options, carriers[..prefix_bits].iter().copied())?;
+    let total_bits = prefix_bits + prefix.payload_bits(&header)?;
+    if total_bits > carriers.len() {
+        return Err(format!(
+            "Length prefix announces {} bits but the image only holds {} bits",
+            total_bits,
+            carriers.len()
+        ));
+    }
+    let framed = bitplane_extract_iter(&ranks, &options, carriers[..total_bits].iter().copied())?;
+    prefix.unframe(&framed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::Container;
+    use crate::palette::tests::FIXTURES;
+    use crate::test_utils::TestRng;
+
+    #[test]
+    fn test_round_trip_through_file() {
+        for (seed, (name, file, _, _)) in FIXTURES.iter().enumerate() {
+            for order in [PaletteOrder::NearestNeighbor, PaletteOrder::Luminance] {
+                let mut image = IndexedImage::parse(file).unwrap();
+                let capacity = ezstego_capacity(&image, order);
+                let secret = TestRng::new(seed as u64 + 1).bytes(capacity / 8);
+
+                ezstego_embed(&mut image, &secret, order).unwrap();
+                let stego = IndexedImage::parse(&image.to_bytes().unwrap()).unwrap();
+                assert_eq!(stego.indices(), image.indices(), "{}", name);
+                assert_eq!(ezstego_extract(&stego, order).unwrap(), secret, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_changes_move_to_neighbor_colors() {
+        let (_, file, _, _) = FIXTURES[0];
+        let cover = IndexedImage::parse(file).unwrap();
+        let sorted = palette_order(cover.palette(), PaletteOrder::NearestNeighbor);
+        let rank = |index: u8| sorted.iter().position(|&i| i == index as usize).unwrap();
+
+        let mut stego = cover.clone();
+        let secret = TestRng::new(2).bytes(ezstego_capacity(&cover, PaletteOrder::default()) / 8);
+        ezstego_embed(&mut stego, &secret, PaletteOrder::default()).unwrap();
+
+        let mut changed = 0;
+        for (&before, &after) in cover.indices().iter().zip(stego.indices()) {
+            if before != after {
+                assert_eq!(rank(before) ^ 1, rank(after));
+                changed += 1;
+            }
+        }
+        assert!(changed > 0);
+        assert_eq!(stego.palette(), cover.palette());
+    }
+
+    #[test]
+    fn test_odd_palette_skips_last_rank() {
+        // Keep 199 of the 200 colors of palette8.png: the last rank has no partner
+        let (_, file, _, _) = FIXTURES[2];
+        let mut image = IndexedImage::parse(file).unwrap();
+        if let Container::Png(png) = &mut image.container {
+            png.palette.truncate(199);
+        }
+        let last = palette_order(image.palette(), PaletteOrder::Luminance)[198] as u8;
+        for (i, index) in image.indices_mut().iter_mut().enumerate() {
+            *index = if i < 50 { last } else { (*index).min(198) };
+        }
+
+        ezstego_embed(&mut image, b"odd", PaletteOrder::Luminance).unwrap();
+        assert!(image.indices()[..50].iter().all(|&i| i == last));
+        assert_eq!(
+            ezstego_extract(&image, PaletteOrder::Luminance).unwrap(),
+            b"odd"
+        );
+    }
+
+    #[test]
+    fn test_not_enough_capacity() {
+        let (_, file, _, _) = FIXTURES[3];
+        let mut image = IndexedImage::parse(file).unwrap();
+        let indices = image.indices().to_vec();
+        let too_long = vec![0u8; ezstego_capacity(&image, PaletteOrder::default()) / 8 + 1];
+        assert!(ezstego_embed(&mut image, &too_long, PaletteOrder::default()).is_err());
+        assert_eq!(image.indices(), indices);
+    }
+}
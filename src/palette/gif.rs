@@ -0,0 +1,318 @@
+//! GIF (87a and 89a) container: the first image of the file, with its LZW-coded indices.
+
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
+
+/// Largest LZW code: codes are at most 12 bits wide.
+const MAX_CODES: usize = 4096;
+
+/// Location of the first image of a GIF file.
+#[derive(Debug, Clone)]
+pub(crate) struct GifImage {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    /// Local color table, or the global one.
+    pub(crate) palette: Vec<[u8; 3]>,
+    /// Row-major indices (deinterlaced).
+    pub(crate) indices: Vec<u8>,
+    pub(crate) interlaced: bool,
+    pub(crate) min_code_size: u8,
+    /// Range of the LZW data sub-blocks in the file, terminator included.
+    pub(crate) data: core::ops::Range<usize>,
+}
+
+fn read_color_table(data: &[u8], pos: usize, packed: u8) -> Result<(Vec<[u8; 3]>, usize), String> {
+    let len = 3 << ((packed & 0x07) + 1);
+    let table = data
+        .get(pos..pos + len)
+        .ok_or("Invalid GIF: truncated color table")?;
+    let colors = table.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+    Ok((colors, pos + len))
+}
+
+/// Skips a sequence of data sub-blocks, returns the position after the terminator.
+fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Result<usize, String> {
+    loop {
+        let len = *data
+            .get(pos)
+            .ok_or("Invalid GIF: truncated data sub-blocks")? as usize;
+        pos += 1 + len;
+        if len == 0 {
+            return Ok(pos);
+        }
+    }
+}
+
+/// Rows of an image in the order they are stored.
+fn row_order(height: usize, interlaced: bool) -> Vec<usize> {
+    if !interlaced {
+        return (0..height).collect();
+    }
+    [(0, 8), (4, 8), (2, 4), (1, 2)]
+        .into_iter()
+        .flat_map(|(start, step)| (start..height).step_by(step))
+        .collect()
+}
+
+impl GifImage {
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 13 || (&data[..6] != b"GIF87a" && &data[..6] != b"GIF89a") {
+            return Err("Invalid GIF: missing signature".into());
+        }
+        let mut pos = 13;
+        let mut palette = None;
+        if data[10] & 0x80 != 0 {
+            let (table, next) = read_color_table(data, pos, data[10])?;
+            palette = Some(table);
+            pos = next;
+        }
+
+        loop {
+            match data.get(pos) {
+                Some(0x21) => pos = skip_sub_blocks(data, pos + 2)?,
+                Some(0x2C) => break,
+                Some(0x3B) => return Err("Invalid GIF: the file has no image".into()),
+                _ => return Err("Invalid GIF: unexpected block".into()),
+            }
+        }
+
+        let descriptor = data
+            .get(pos + 1..pos + 10)
+            .ok_or("Invalid GIF: truncated image descriptor")?;
+        let width = u16::from_le_bytes([descriptor[4], descriptor[5]]) as usize;
+        let height = u16::from_le_bytes([descriptor[6], descriptor[7]]) as usize;
+        let packed = descriptor[8];
+        pos += 10;
+        if packed & 0x80 != 0 {
+            let (table, next) = read_color_table(data, pos, packed)?;
+            palette = Some(table);
+            pos = next;
+        }
+        let palette = palette.ok_or("Invalid GIF: the image has no color table")?;
+
+        let min_code_size = *data.get(pos).ok_or("Invalid GIF: missing LZW code size")?;
+        if !(2..=8).contains(&min_code_size) {
+            return Err(format!("Invalid GIF: bad LZW code size {}", min_code_size));
+        }
+        let start = pos + 1;
+        let end = skip_sub_blocks(data, start)?;
+        if end > data.len() {
+            return Err("Invalid GIF: truncated image data".into());
+        }
+
+        let mut compressed = Vec::new();
+        let mut block = start;
+        while data[block] != 0 {
+            let len = data[block] as usize;
+            compressed.extend_from_slice(&data[block + 1..block + 1 + len]);
+            block += 1 + len;
+        }
+        let stored = lzw_decode(&compressed, min_code_size, width * height)?;
+
+        let interlaced = packed & 0x40 != 0;
+        let mut indices = vec![0u8; width * height];
+        for (i, y) in row_order(height, interlaced).into_iter().enumerate() {
+            indices[y * width..(y + 1) * width]
+                .copy_from_slice(&stored[i * width..(i + 1) * width]);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            palette,
+            indices,
+            interlaced,
+            min_code_size,
+            data: start..end,
+        })
+    }
+
+    /// Re-emits the file with new indices, copying everything but the LZW data.
+    pub(crate) fn write(&self, source: &[u8], indices: &[u8]) -> Vec<u8> {
+        let mut stored = Vec::with_capacity(indices.len());
+        for y in row_order(self.height, self.interlaced) {
+            stored.extend_from_slice(&indices[y * self.width..(y + 1) * self.width]);
+        }
+        let compressed = lzw_encode(&stored, self.min_code_size);
+
+        let mut out = source[..self.data.start].to_vec();
+        for chunk in compressed.chunks(255) {
+            out.push(chunk.len() as u8);
+            out.extend_from_slice(chunk);
+        }
+        out.push(0);
+        out.extend_from_slice(&source[self.data.end..]);
+        out
+    }
+}
+
+/// Decodes GIF LZW data into `len` indices.
+fn lzw_decode(data: &[u8], min_code_size: u8, len: usize) -> Result<Vec<u8>, String> {
+    let clear = 1usize << min_code_size;
+    let end = clear + 1;
+    // Each code is a prefix code and a last index; strings are rebuilt backwards
+    let mut prefix = vec![0u16; MAX_CODES];
+    let mut suffix = vec![0u8; MAX_CODES];
+    let mut first = vec![0u8; MAX_CODES];
+    for code in 0..clear {
+        suffix[code] = code as u8;
+        first[code] = code as u8;
+    }
+
+    let mut out = Vec::with_capacity(len);
+    let mut size = min_code_size + 1;
+    let mut next = end + 1;
+    let mut previous: Option<usize> = None;
+    let (mut acc, mut bits, mut pos) = (0u32, 0u8, 0usize);
+    let mut string = Vec::new();
+
+    while out.len() < len {
+        while bits < size {
+            let byte = *data.get(pos).ok_or("Invalid GIF: truncated LZW data")?;
+            acc |= (byte as u32) << bits;
+            bits += 8;
+            pos += 1;
+        }
+        let code = (acc & ((1 << size) - 1)) as usize;
+        acc >>= size;
+        bits -= size;
+
+        if code == clear {
+            size = min_code_size + 1;
+            next = end + 1;
+            previous = None;
+            continue;
+        }
+        if code == end {
+            break;
+        }
+        let Some(prev) = previous else {
+            if code >= clear {
+                return Err("Invalid GIF: bad LZW code".into());
+            }
+            out.push(code as u8);
+            previous = Some(code);
+            continue;
+        };
+        if code > next || (code == next && next == MAX_CODES) {
+            return Err("Invalid GIF: bad LZW code".into());
+        }
+
+        if next < MAX_CODES {
+            prefix[next] = prev as u16;
+            first[next] = first[prev];
+            suffix[next] = if code == next {
+                first[prev]
+            } else {
+                first[code]
+            };
+            next += 1;
+            if next == 1 << size && size < 12 {
+                size += 1;
+            }
+        }
+
+        string.clear();
+        let mut c = code;
+        while c > end {
+            string.push(suffix[c]);
+            c = prefix[c] as usize;
+        }
+        string.push(suffix[c]);
+        out.extend(string.iter().rev());
+        previous = Some(code);
+    }
+
+    if out.len() < len {
+        return Err("Invalid GIF: not enough image data".into());
+    }
+    out.truncate(len);
+    if out.iter().any(|&index| index as usize >= clear) {
+        return Err("Invalid GIF: index out of range of the LZW code size".into());
+    }
+    Ok(out)
+}
+
+/// Encodes indices with GIF LZW. The dictionary is cleared when it is full.
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1usize << min_code_size;
+    let end = clear + 1;
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0u8);
+    let mut size = min_code_size + 1;
+    let mut emit = |code: usize, size: u8| {
+        acc |= (code as u32) << bits;
+        bits += size;
+        while bits >= 8 {
+            out.push(acc as u8);
+            acc >>= 8;
+            bits -= 8;
+        }
+    };
+
+    let mut dictionary = BTreeMap::new();
+    let mut next = end + 1;
+    emit(clear, size);
+    let mut rest = indices.iter();
+    if let Some(&first) = rest.next() {
+        let mut current = first as usize;
+        for &index in rest {
+            if let Some(&code) = dictionary.get(&(current, index)) {
+                current = code;
+                continue;
+            }
+            emit(current, size);
+            // The decoder widens its codes one code later than the encoder adds entries
+            if next == 1 << size && size < 12 {
+                size += 1;
+            }
+            if next < MAX_CODES {
+                dictionary.insert((current, index), next);
+                next += 1;
+            } else {
+                emit(clear, size);
+                dictionary.clear();
+                size = min_code_size + 1;
+                next = end + 1;
+            }
+            current = index as usize;
+        }
+        emit(current, size);
+        if next == 1 << size && size < 12 {
+            size += 1;
+        }
+    }
+    emit(end, size);
+    if bits > 0 {
+        out.push(acc as u8);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestRng;
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let mut rng = TestRng::new(12);
+        for (min_code_size, len) in [(2, 0), (2, 1), (2, 5000), (5, 20_000), (8, 70_000)] {
+            // Runs of random length, which fill the dictionary several times
+            let mut indices = Vec::new();
+            while indices.len() < len {
+                let index = rng.below(1 << min_code_size) as u8;
+                indices.extend(core::iter::repeat_n(index, 1 + rng.below(4)));
+            }
+            indices.truncate(len);
+
+            let encoded = lzw_encode(&indices, min_code_size);
+            assert_eq!(lzw_decode(&encoded, min_code_size, len).unwrap(), indices);
+        }
+    }
+
+    #[test]
+    fn test_row_order() {
+        assert_eq!(row_order(3, false), vec![0, 1, 2]);
+        assert_eq!(row_order(10, true), vec![0, 8, 4, 2, 6, 1, 3, 5, 7, 9]);
+    }
+}
@@ -0,0 +1,256 @@
+//! PNG container for palette images (color type 3), non-interlaced.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::deflate::{zlib_compress, zlib_decompress};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Location of the image data of a palette PNG file.
+#[derive(Debug, Clone)]
+pub(crate) struct PngImage {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) palette: Vec<[u8; 3]>,
+    pub(crate) indices: Vec<u8>,
+    bit_depth: u8,
+    /// Range of the IDAT chunks in the file.
+    data: core::ops::Range<usize>,
+}
+
+/// CRC-32 of PNG chunks (ISO 3309).
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn write_chunk(kind: &[u8; 4], payload: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(payload);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Paeth predictor of the PNG filters.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+impl PngImage {
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, String> {
+        if !data.starts_with(&SIGNATURE) {
+            return Err("Invalid PNG: missing signature".into());
+        }
+        let mut pos = SIGNATURE.len();
+        let mut header = None;
+        let mut palette = None;
+        let mut compressed = Vec::new();
+        let mut idat: Option<core::ops::Range<usize>> = None;
+
+        loop {
+            let length = data
+                .get(pos..pos + 4)
+                .ok_or("Invalid PNG: missing IEND chunk")?;
+            let len = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+            let chunk = data
+                .get(pos + 4..pos + 12 + len)
+                .ok_or("Invalid PNG: truncated chunk")?;
+            let (body, crc) = chunk.split_at(4 + len);
+            if crc32(body) != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+                return Err(format!(
+                    "Invalid PNG: bad CRC in chunk {}",
+                    String::from_utf8_lossy(&body[..4])
+                ));
+            }
+            let (kind, payload) = body.split_at(4);
+            let end = pos + 12 + len;
+
+            match kind {
+                b"IHDR" => header = Some(payload.to_vec()),
+                b"PLTE" => {
+                    if !len.is_multiple_of(3) || len == 0 || len > 768 {
+                        return Err("Invalid PNG: bad PLTE chunk".into());
+                    }
+                    palette = Some(
+                        payload
+                            .chunks_exact(3)
+                            .map(|c| [c[0], c[1], c[2]])
+                            .collect(),
+                    );
+                }
+                b"IDAT" => {
+                    match &mut idat {
+                        Some(range) if range.end == pos => range.end = end,
+                        Some(_) => {
+                            return Err("Invalid PNG: IDAT chunks are not consecutive".into());
+                        }
+                        None => idat = Some(pos..end),
+                    }
+                    compressed.extend_from_slice(payload);
+                }
+                b"IEND" => break,
+                _ => {}
+            }
+            pos = end;
+        }
+
+        let header = header.ok_or("Invalid PNG: missing IHDR chunk")?;
+        if header.len() != 13 {
+            return Err("Invalid PNG: bad IHDR chunk".into());
+        }
+        let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let (bit_depth, color_type, interlace) = (header[8], header[9], header[12]);
+        if width == 0 || height == 0 {
+            return Err("Invalid PNG: empty image".into());
+        }
+        if color_type != 3 {
+            return Err(format!(
+                "Unsupported PNG: color type {} is not indexed color",
+                color_type
+            ));
+        }
+        if !matches!(bit_depth, 1 | 2 | 4 | 8) {
+            return Err(format!("Invalid PNG: bad bit depth {}", bit_depth));
+        }
+        if interlace != 0 {
+            return Err("Unsupported PNG: interlaced images are not supported".into());
+        }
+        let palette: Vec<[u8; 3]> = palette.ok_or("Invalid PNG: missing PLTE chunk")?;
+        // Larger palettes have indices that do not fit in a pixel
+        if palette.len() > 1 << bit_depth {
+            return Err(format!(
+                "Invalid PNG: {} palette entries for bit depth {}",
+                palette.len(),
+                bit_depth
+            ));
+        }
+        let data_range = idat.ok_or("Invalid PNG: missing IDAT chunk")?;
+
+        let raw = zlib_decompress(&compressed)?;
+        let stride = (width * bit_depth as usize).div_ceil(8);
+        if raw.len() < height * (stride + 1) {
+            return Err("Invalid PNG: not enough image data".into());
+        }
+
+        let mut indices = Vec::with_capacity(width * height);
+        let mut previous = vec![0u8; stride];
+        let mut row = vec![0u8; stride];
+        for line in raw.chunks_exact(stride + 1).take(height) {
+            let (filter, filtered) = (line[0], &line[1..]);
+            for i in 0..stride {
+                let a = if i > 0 { row[i - 1] } else { 0 };
+                let (b, c) = (previous[i], if i > 0 { previous[i - 1] } else { 0 });
+                let predictor = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    4 => paeth(a, b, c),
+                    _ => return Err(format!("Invalid PNG: bad filter type {}", filter)),
+                };
+                row[i] = filtered[i].wrapping_add(predictor);
+            }
+
+            let per_byte = 8 / bit_depth as usize;
+            let mask = ((1u16 << bit_depth) - 1) as u8;
+            indices.extend((0..width).map(|x| {
+                let shift = 8 - bit_depth as usize * (x % per_byte + 1);
+                (row[x / per_byte] >> shift) & mask
+            }));
+            core::mem::swap(&mut previous, &mut row);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            palette,
+            indices,
+            bit_depth,
+            data: data_range,
+        })
+    }
+
+    /// Re-emits the file with new indices, copying every chunk but the IDAT chunks.
+    ///
+    /// The rows are not filtered (filter type 0), as recommended for palette images.
+    pub(crate) fn write(&self, source: &[u8], indices: &[u8]) -> Vec<u8> {
+        let depth = self.bit_depth as usize;
+        let stride = (self.width * depth).div_ceil(8);
+        let per_byte = 8 / depth;
+        let mut raw = Vec::with_capacity(self.height * (stride + 1));
+        for row in indices.chunks_exact(self.width).take(self.height) {
+            raw.push(0);
+            let mut packed = vec![0u8; stride];
+            for (x, &index) in row.iter().enumerate() {
+                packed[x / per_byte] |= index << (8 - depth * (x % per_byte + 1));
+            }
+            raw.extend_from_slice(&packed);
+        }
+
+        let mut out = source[..self.data.start].to_vec();
+        write_chunk(b"IDAT", &zlib_compress(&raw), &mut out);
+        out.extend_from_slice(&source[self.data.end..]);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_palette_larger_than_bit_depth_error() {
+        let file = include_bytes!("../../tests/fixtures/palette/palette4.png");
+        assert_eq!(PngImage::parse(file).unwrap().palette.len(), 16);
+
+        // Same file with 20 colors in the PLTE chunk at bit depth 4
+        let mut data = SIGNATURE.to_vec();
+        let mut pos = SIGNATURE.len();
+        while pos < file.len() {
+            let len = u32::from_be_bytes(file[pos..pos + 4].try_into().unwrap()) as usize;
+            let kind: [u8; 4] = file[pos + 4..pos + 8].try_into().unwrap();
+            let payload = &file[pos + 8..pos + 8 + len];
+            if &kind == b"PLTE" {
+                write_chunk(&kind, &[payload, &[0; 12]].concat(), &mut data);
+            } else {
+                write_chunk(&kind, payload, &mut data);
+            }
+            pos += 12 + len;
+        }
+        assert_eq!(
+            PngImage::parse(&data).unwrap_err(),
+            "Invalid PNG: 20 palette entries for bit depth 4"
+        );
+    }
+}
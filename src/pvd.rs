@@ -1,3 +1,10 @@
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::bit_order::{BitOrder, BitWriter, read_bit};
+use crate::embedding_locator::EmbeddingLocator;
+use crate::report::{BinUsage, EmbedReport, SkipReason};
+use crate::stego_method::StegoMethod;
+
 /// Configuration options for the Pixel Value Differencing (PVD) embedding method.
 ///
 /// The `bins` field is a vector of tuples representing ranges of pixel difference values.
@@ -49,6 +56,13 @@
 /// - `pvd_extract()`
 pub struct PvdOptions {
     pub bins: Vec<(i32, i32)>,
+
+    /// Order in which the bits of each secret byte are serialized (MSB first by default).
+    pub secret_bit_order: BitOrder,
+
+    /// Order in which the secret bits are placed inside the value added to the lower bound
+    /// of a bin (MSB first by default).
+    pub chunk_bit_order: BitOrder,
 }
 
 impl Default for PvdOptions {
@@ -69,14 +83,57 @@ impl Default for PvdOptions {
                 (64, 127),
                 (128, 255),
             ],
+            secret_bit_order: BitOrder::MsbFirst,
+            chunk_bit_order: BitOrder::MsbFirst,
         }
     }
 }
 
+/// Computes how many secret bits `pvd_embed` can hide in `host` with the given pairs.
+///
+/// Pairs are formed two-by-two from `embedding_indices`, like in `pvd_embed`. Pairs with an
+/// index outside the host, or whose difference does not fit any bin, are not counted.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::pvd::{pvd_capacity, PvdOptions};
+/// let host = vec![100u8, 110, 120, 130];
+/// let options = PvdOptions::default();
+/// // Both differences (10) fall in the (8, 15) bin, which holds 3 bits each
+/// assert_eq!(pvd_capacity(&host, &options, &[0, 1, 2, 3]), 6);
+/// ```
+pub fn pvd_capacity(host: &[u8], options: &PvdOptions, embedding_indices: &[usize]) -> usize {
+    pvd_capacity_iter(host, options, embedding_indices.iter().copied())
+}
+
+/// Computes how many secret bits can be hidden with the pairs formed by any index iterator.
+///
+/// See [`pvd_capacity`].
+pub fn pvd_capacity_iter(
+    host: &[u8],
+    options: &PvdOptions,
+    embedding_indices: impl IntoIterator<Item = usize>,
+) -> usize {
+    pairs(embedding_indices.into_iter())
+        .filter(|&(idx1, idx2)| idx1 < host.len() && idx2 < host.len())
+        .filter_map(|(idx1, idx2)| {
+            let (p1, p2) = (host[idx1] as i32, host[idx2] as i32);
+            let diff = (p1 - p2).abs();
+            options
+                .bins
+                .iter()
+                .find(|&&(min_bin, max_bin)| diff >= min_bin && diff <= max_bin)
+                .filter(|&&(_, max_bin)| !overflows(p1, p2, max_bin))
+        })
+        .map(|&(min_bin, max_bin)| bin_bits(min_bin, max_bin))
+        .sum()
+}
 
 /// Embeds a secret message into a host buffer using the Pixel Value Differencing (PVD) technique.
 ///
 /// This function ignores the last pixel if it does not form a complete pair with another pixel.
+/// Pairs whose bin could push a pixel outside `0..=255` are left untouched; the extraction
+/// and the capacity skip the same pairs.
 ///
 /// # Arguments
 /// - `host`: Mutable slice of host data bytes where the secret will be embedded.
@@ -101,6 +158,94 @@ pub fn pvd_embed(
     secret: &[u8],
     options: &PvdOptions,
     embedding_indices: &[usize],
+) -> Result<usize, String> {
+    pvd_embed_iter(host, secret, options, embedding_indices.iter().copied())
+}
+
+/// Embeds a secret message using PVD, reading the pixel pairs lazily from an [`EmbeddingLocator`].
+///
+/// Consecutive indices returned by the locator form the pairs, exactly like the
+/// `embedding_indices` slice of [`pvd_embed`], but no `Vec<usize>` is allocated.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::embedding_locator::LinearTraversal;
+/// use stegano_rs::pvd::{pvd_embed_with_locator, pvd_extract_with_locator, PvdOptions};
+///
+/// let mut host = vec![50u8, 80, 60, 100, 10, 50, 150, 210, 14, 58, 23, 47];
+/// let options = PvdOptions::default();
+///
+/// pvd_embed_with_locator(&mut host, b"Hi", &options, &LinearTraversal).unwrap();
+/// let extracted = pvd_extract_with_locator(&host, &options, &LinearTraversal).unwrap();
+/// assert!(extracted.starts_with(b"Hi"));
+/// ```
+pub fn pvd_embed_with_locator<L: EmbeddingLocator + ?Sized>(
+    host: &mut [u8],
+    secret: &[u8],
+    options: &PvdOptions,
+    locator: &L,
+) -> Result<usize, String> {
+    let len = host.len();
+    pvd_embed_iter(host, secret, options, locator.iter_indices(len))
+}
+
+/// Embeds a secret message using PVD with the pixel pairs formed by any index iterator.
+///
+/// The indices are consumed lazily, two by two, and only as far as needed.
+/// See [`pvd_embed`] for the details of the embedding.
+pub fn pvd_embed_iter(
+    host: &mut [u8],
+    secret: &[u8],
+    options: &PvdOptions,
+    embedding_indices: impl IntoIterator<Item = usize>,
+) -> Result<usize, String> {
+    embed_pairs(host, secret, options, embedding_indices.into_iter(), None)
+}
+
+/// Embeds a secret message like [`pvd_embed_with_locator`] and returns an [`EmbedReport`]
+/// with the pixels changed, the usage of each bin and the skipped pairs.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::embedding_locator::LinearTraversal;
+/// use stegano_rs::pvd::{pvd_embed_with_report, PvdOptions};
+///
+/// let mut host = vec![50u8, 80, 60, 100, 10, 50, 150, 210, 14, 58, 23, 47];
+/// let report = pvd_embed_with_report(&mut host, b"Hi", &PvdOptions::default(), &LinearTraversal).unwrap();
+///
+/// assert_eq!(report.bits_embedded, 16);
+/// let pairs: usize = report.bin_usage.iter().map(|usage| usage.pairs).sum();
+/// assert_eq!(report.positions_used.len(), 2 * pairs);
+/// ```
+///
+/// # Errors
+/// Same as [`pvd_embed`].
+pub fn pvd_embed_with_report<L: EmbeddingLocator + ?Sized>(
+    host: &mut [u8],
+    secret: &[u8],
+    options: &PvdOptions,
+    locator: &L,
+) -> Result<EmbedReport, String> {
+    let len = host.len();
+    let mut report = EmbedReport {
+        bin_usage: options
+            .bins
+            .iter()
+            .map(|&bin| BinUsage { bin, pairs: 0, bits: 0 })
+            .collect(),
+        ..EmbedReport::default()
+    };
+    embed_pairs(host, secret, options, locator.iter_indices(len), Some(&mut report))?;
+    Ok(report)
+}
+
+/// Shared PVD embedding loop, optionally filling an [`EmbedReport`].
+fn embed_pairs(
+    host: &mut [u8],
+    secret: &[u8],
+    options: &PvdOptions,
+    embedding_indices: impl Iterator<Item = usize>,
+    mut report: Option<&mut EmbedReport>,
 ) -> Result<usize, String> {
     // Check that the bin configuration is not empty
     if options.bins.is_empty() {
@@ -111,20 +256,21 @@ pub fn pvd_embed(
     let mut bit_index = 0; // Number of bits embedded so far
 
     // Iterate over the embedding indices two-by-two to form pixel pairs
-    for pair in embedding_indices.chunks(2) {
-        if pair.len() < 2 {
-            break; // If we have an incomplete pair, stop
-        }
-
+    // An incomplete last pair is ignored
+    for (idx1, idx2) in pairs(embedding_indices) {
         if bit_index >= total_secret_bits {
             break; // All bits have been embedded
         }
 
-        let idx1 = pair[0];
-        let idx2 = pair[1];
+        if let Some(report) = report.as_deref_mut() {
+            report.last_index = Some(idx2);
+        }
 
         // Ensure indices are within the bounds of the host buffer
         if idx1 >= host.len() || idx2 >= host.len() {
+            if let Some(report) = report.as_deref_mut() {
+                report.record_skip(vec![idx1, idx2], SkipReason::OutOfBounds);
+            }
             continue; // Skip invalid index pairs
         }
 
@@ -136,7 +282,7 @@ pub fn pvd_embed(
         let bin_option = options
             .bins
             .iter()
-            .find(|&&(min_bin, max_bin)| diff >= min_bin && diff <= max_bin);
+            .position(|&(min_bin, max_bin)| diff >= min_bin && diff <= max_bin);
 
         if bin_option.is_none() {
             return Err(format!(
@@ -145,74 +291,59 @@ pub fn pvd_embed(
             ));
         }
 
-        let (min_bin, max_bin) = *bin_option.unwrap();
-        let range_size = (max_bin - min_bin + 1) as usize;
+        let bin_position = bin_option.unwrap();
+        let (min_bin, max_bin) = options.bins[bin_position];
+
+        // Skip the pair if some difference of its bin would push a pixel out of 0..=255
+        if overflows(p1, p2, max_bin) {
+            if let Some(report) = report.as_deref_mut() {
+                report.record_skip(vec![idx1, idx2], SkipReason::Overflow);
+            }
+            continue;
+        }
 
         // Number of bits we can hide in this bin
-        let bits_to_embed = (range_size as f64).log2().floor() as usize;
+        let bits_to_embed = bin_bits(min_bin, max_bin);
 
 
         // Extract bits_to_embed bits from the secret starting at bit_index
+        // Missing bits at the end of the secret are left to 0
         let mut secret_bits = 0u32;
-        let mut actual_bits = 0;
-
-        for i in 0..bits_to_embed {
-            let global_bit_pos = bit_index + i;
-
-            if global_bit_pos >= total_secret_bits {
-                // println!(
-                //     " -> Reached end of secret bits (global_bit_pos = {})",
-                //     global_bit_pos
-                // );
-                break;
-            }
-
-            let byte_index = global_bit_pos / 8;
-            let bit_pos_in_byte = 7 - (global_bit_pos % 8); // MSB first
-            let byte = secret[byte_index];
-            let bit = (byte >> bit_pos_in_byte) & 1;
+        let actual_bits = bits_to_embed.min(total_secret_bits - bit_index);
 
-            secret_bits = (secret_bits << 1) | (bit as u32);
-            actual_bits += 1;
+        for i in 0..actual_bits {
+            let bit = read_bit(secret, bit_index + i, options.secret_bit_order) as u32;
+            secret_bits |= bit << options.chunk_bit_order.shift(i, bits_to_embed);
         }
 
         if actual_bits == 0 {
-            // println!(" -> No bits left to embed. Exiting loop.");
             break;
         }
 
-        // P remaining bits with 0s if needed to reach bits_to_embed
-        if actual_bits < bits_to_embed {
-            // println!(
-            //     " -> Padding with {} bits (shifting left)",
-            //     bits_to_embed - actual_bits
-            // );
-            secret_bits <<= bits_to_embed - actual_bits;
-        }
-
         // Calculate the new difference value using the extracted bits
-        let new_diff_sign = if p1 >= p2 { 1 } else { -1 };
         let new_diff: i32 = min_bin + secret_bits as i32;
 
-        // Recompute pixel values so their difference equals new_diff, preserving average
+        // Recompute pixel values so their difference equals new_diff, preserving the average:
+        // the larger pixel gets the rounded-up half of the difference
         let avg = (p1 + p2) / 2;
-        let new_p1 = (avg + new_diff_sign * ((new_diff + 1) / 2)).clamp(0, 255);
-        let new_p2 = (avg - new_diff_sign * (new_diff / 2)).clamp(0, 255);
-
-        // Ensure new pixel values are valid (in range 0..=255)
-        if new_p1 < 0 || new_p1 > 255 || new_p2 < 0 || new_p2 > 255 {
-            println!(
-                "new_p1={} or new_p2={} out of bounds (0-255), skipping this pair",
-                new_p1, new_p2
-            );
-            continue; // Skip if pixel overflow would occur
-        }
+        let (high, low) = (avg + (new_diff + 1) / 2, avg - new_diff / 2);
+        let (new_p1, new_p2) = if p1 >= p2 { (high, low) } else { (low, high) };
 
         // Update host pixels
         host[idx1] = new_p1 as u8;
         host[idx2] = new_p2 as u8;
 
         bit_index += actual_bits; // Increment only by the number of bits actually embedded
+
+        if let Some(report) = report.as_deref_mut() {
+            report.record_position(idx1, p1 as i128, new_p1 as i128);
+            report.record_position(idx2, p2 as i128, new_p2 as i128);
+            report.bits_embedded = bit_index;
+            if let Some(usage) = report.bin_usage.get_mut(bin_position) {
+                usage.pairs += 1;
+                usage.bits += actual_bits;
+            }
+        }
     }
 
     // Final check: if not all secret bits were embedded, return error
@@ -251,84 +382,226 @@ pub fn pvd_extract(
     host: &[u8],
     options: &PvdOptions,
     extraction_indices: &[usize],
+) -> Result<Vec<u8>, String> {
+    pvd_extract_iter(host, options, extraction_indices.iter().copied())
+}
+
+/// Extracts a secret message using PVD, reading the pixel pairs lazily from an [`EmbeddingLocator`].
+///
+/// See [`pvd_embed_with_locator`] for an example.
+pub fn pvd_extract_with_locator<L: EmbeddingLocator + ?Sized>(
+    host: &[u8],
+    options: &PvdOptions,
+    locator: &L,
+) -> Result<Vec<u8>, String> {
+    pvd_extract_iter(host, options, locator.iter_indices(host.len()))
+}
+
+/// Extracts a secret message using PVD with the pixel pairs formed by any index iterator.
+///
+/// See [`pvd_extract`] for the details of the extraction.
+pub fn pvd_extract_iter(
+    host: &[u8],
+    options: &PvdOptions,
+    extraction_indices: impl IntoIterator<Item = usize>,
 ) -> Result<Vec<u8>, String> {
     // Check that the bin configuration is not empty
     if options.bins.is_empty() {
         return Err("options.bins cannot be empty".into());
     }
 
-    let mut extracted_bytes = Vec::new();
-    let mut current_byte = 0u8;
-    let mut bits_in_current_byte = 0;
+    let mut extracted = BitWriter::new(options.secret_bit_order);
 
     // Iterate over the extraction indices two-by-two to form pixel pairs
-    for pair in extraction_indices.chunks(2) {
-        if pair.len() < 2 {
-            break; // Ignore last pixel if it doesn't form a pair
+    // Ignore last pixel if it doesn't form a pair
+    for (idx1, idx2) in pairs(extraction_indices.into_iter()) {
+        if let Some((hidden_value, bits_to_extract)) = read_pair(host, options, idx1, idx2)? {
+            push_value(&mut extracted, hidden_value, bits_to_extract, options);
         }
+    }
 
-        let idx1 = pair[0];
-        let idx2 = pair[1];
+    Ok(extracted.into_bytes())
+}
 
-        // Skip invalid indices
-        if idx1 >= host.len() || idx2 >= host.len() {
-            continue;
-        }
+/// Number of pixel pairs per chunk of [`pvd_extract_parallel`].
+#[cfg(feature = "parallel")]
+const PARALLEL_PAIRS: usize = 1 << 15;
 
-        let p1 = host[idx1] as i32;
-        let p2 = host[idx2] as i32;
+/// Extracts a secret message using PVD, like [`pvd_extract`], on the rayon thread pool.
+///
+/// The pairs are split into chunks of whole pairs. A first pass counts the bits of every
+/// chunk, which gives the bit offset where each chunk starts; a second pass extracts the
+/// chunks from those offsets, and their bytes are merged. The result is byte-identical to
+/// [`pvd_extract`], and so is the error when a difference fits no bin.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::pvd::{PvdOptions, pvd_extract, pvd_extract_parallel};
+/// let host: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).collect();
+/// let indices: Vec<usize> = (0..host.len()).collect();
+/// let options = PvdOptions::default();
+/// assert_eq!(
+///     pvd_extract_parallel(&host, &options, &indices).unwrap(),
+///     pvd_extract(&host, &options, &indices).unwrap()
+/// );
+/// ```
+#[cfg(feature = "parallel")]
+pub fn pvd_extract_parallel<'a>(
+    host: &'a [u8],
+    options: &'a PvdOptions,
+    extraction_indices: &'a [usize],
+) -> Result<Vec<u8>, String> {
+    use rayon::prelude::*;
 
-        let diff = p1 - p2;
-        let diff_abs = diff.abs();
+    if options.bins.is_empty() {
+        return Err("options.bins cannot be empty".into());
+    }
 
-        // Find the bin corresponding to the absolute difference
-        let bin_option = options
-            .bins
-            .iter()
-            .find(|&&(min_bin, max_bin)| diff_abs >= min_bin && diff_abs <= max_bin);
-
-        let (min_bin, max_bin) = match bin_option {
-            Some(b) => *b,
-            None => {
-                return Err(format!(
-                    "Difference {} at positions idx1={} (pixel: {}) and idx2={} (pixel: {}) does not fit any bin",
-                    diff_abs, idx1, p1, idx2, p2
-                ));
+    let paired = extraction_indices.len() / 2 * 2;
+    let chunks: Vec<&[usize]> = extraction_indices[..paired]
+        .chunks(2 * PARALLEL_PAIRS)
+        .collect();
+    let read_chunk = |chunk: &'a [usize]| {
+        chunk
+            .chunks_exact(2)
+            .filter_map(|pair| read_pair(host, options, pair[0], pair[1]).transpose())
+    };
+
+    // First pass: bits of every chunk; the first error in chunk order is the sequential one
+    let counts: Vec<Result<usize, String>> = chunks
+        .par_iter()
+        .map(|chunk| {
+            read_chunk(chunk)
+                .map(|pair| pair.map(|(_, bits)| bits))
+                .sum()
+        })
+        .collect();
+    let mut offsets = Vec::with_capacity(chunks.len());
+    let mut total_bits = 0;
+    for count in counts {
+        offsets.push(total_bits);
+        total_bits += count?;
+    }
+
+    // Second pass: every chunk starts at its own bit offset inside its first byte
+    let packed: Vec<Vec<u8>> = chunks
+        .par_iter()
+        .zip(&offsets)
+        .map(|(chunk, &offset)| {
+            let mut writer = BitWriter::with_phase(options.secret_bit_order, offset % 8);
+            for (hidden_value, bits) in read_chunk(chunk).flatten() {
+                push_value(&mut writer, hidden_value, bits, options);
             }
-        };
+            writer.into_bytes()
+        })
+        .collect();
+
+    let mut extracted = vec![0u8; total_bits.div_ceil(8)];
+    for (bytes, offset) in packed.iter().zip(offsets) {
+        for (out, byte) in extracted[offset / 8..].iter_mut().zip(bytes) {
+            *out |= byte;
+        }
+    }
+    Ok(extracted)
+}
+
+impl StegoMethod for PvdOptions {
+    /// Calls [`pvd_capacity_iter`] with the pairs formed by the locator indices.
+    fn capacity(&self, host: &[u8], locator: &dyn EmbeddingLocator) -> usize {
+        pvd_capacity_iter(host, self, locator.iter_indices(host.len()))
+    }
+
+    /// Calls [`pvd_embed_with_locator`].
+    fn embed(
+        &self,
+        host: &mut [u8],
+        secret: &[u8],
+        locator: &dyn EmbeddingLocator,
+    ) -> Result<usize, String> {
+        pvd_embed_with_locator(host, secret, self, locator)
+    }
+
+    /// Calls [`pvd_extract_with_locator`].
+    fn extract(&self, host: &[u8], locator: &dyn EmbeddingLocator) -> Result<Vec<u8>, String> {
+        pvd_extract_with_locator(host, self, locator)
+    }
+}
 
-        let range_size = (max_bin - min_bin + 1) as usize;
+/// Number of bits hidden in a pair whose difference falls in the bin `[min_bin, max_bin]`:
+/// `floor(log2(max_bin - min_bin + 1))`.
+fn bin_bits(min_bin: i32, max_bin: i32) -> usize {
+    (max_bin - min_bin + 1).max(1).ilog2() as usize
+}
 
-        // Calculate the number of bits encoded in this bin
-        let bits_to_extract = (range_size as f64).log2().floor() as usize;
+/// Reads the value hidden in a pixel pair and its number of bits, or `None` if an index is
+/// out of the host or if the pair was skipped because it could overflow.
+fn read_pair(
+    host: &[u8],
+    options: &PvdOptions,
+    idx1: usize,
+    idx2: usize,
+) -> Result<Option<(u32, usize)>, String> {
+    // Skip invalid indices
+    if idx1 >= host.len() || idx2 >= host.len() {
+        return Ok(None);
+    }
 
-        // Extract the hidden value from the difference
-        let hidden_value = (diff_abs - min_bin) as u32;
+    let p1 = host[idx1] as i32;
+    let p2 = host[idx2] as i32;
 
-        // Extract bits from the hidden value starting from the most significant bit
-        for i in (0..bits_to_extract).rev() {
-            let bit = ((hidden_value >> i) & 1) as u8;
+    let diff = p1 - p2;
+    let diff_abs = diff.abs();
 
-            // Shift current_byte to the left and add the extracted bit
-            current_byte = (current_byte << 1) | bit;
-            bits_in_current_byte += 1;
+    // Find the bin corresponding to the absolute difference
+    let bin_option = options
+        .bins
+        .iter()
+        .find(|&&(min_bin, max_bin)| diff_abs >= min_bin && diff_abs <= max_bin);
 
-            // Once we have 8 bits, push the byte to the output vector
-            if bits_in_current_byte == 8 {
-                extracted_bytes.push(current_byte);
-                current_byte = 0;
-                bits_in_current_byte = 0;
-            }
+    let (min_bin, max_bin) = match bin_option {
+        Some(b) => *b,
+        None => {
+            return Err(format!(
+                "Difference {} at positions idx1={} (pixel: {}) and idx2={} (pixel: {}) does not fit any bin",
+                diff_abs, idx1, p1, idx2, p2
+            ));
         }
+    };
+
+    // Pairs that could overflow were skipped by the embedding
+    if overflows(p1, p2, max_bin) {
+        return Ok(None);
     }
 
-    // If the last byte is not fully filled with bits, pad with zeros on the right
-    if bits_in_current_byte > 0 {
-        current_byte <<= 8 - bits_in_current_byte;
-        extracted_bytes.push(current_byte);
+    // Calculate the number of bits encoded in this bin
+    let bits_to_extract = bin_bits(min_bin, max_bin);
+
+    // Extract the hidden value from the difference
+    Ok(Some(((diff_abs - min_bin) as u32, bits_to_extract)))
+}
+
+/// Returns whether a pixel of the pair could leave `0..=255` when the difference of the pair
+/// is set to a value of the bin ending at `max_bin`.
+///
+/// Embedding keeps the floor of the average of the pair and the bin of its difference, so
+/// the test gives the same answer on the cover pair and on the stego pair: the extractor
+/// skips the same pairs as the embedder.
+fn overflows(p1: i32, p2: i32, max_bin: i32) -> bool {
+    let avg = (p1 + p2) / 2;
+    avg - max_bin / 2 < 0 || avg + (max_bin + 1) / 2 > 255
+}
+
+/// Pushes the bits of a hidden value in chunk order; the last byte is padded with zeros.
+fn push_value(extracted: &mut BitWriter, hidden_value: u32, bits: usize, options: &PvdOptions) {
+    for i in 0..bits {
+        let bit = (hidden_value >> options.chunk_bit_order.shift(i, bits)) & 1;
+        extracted.push(bit as u8);
     }
+}
 
-    Ok(extracted_bytes)
+/// Groups consecutive indices two by two into pixel pairs, dropping an incomplete last pair.
+fn pairs(mut indices: impl Iterator<Item = usize>) -> impl Iterator<Item = (usize, usize)> {
+    core::iter::from_fn(move || Some((indices.next()?, indices.next()?)))
 }
 
 #[cfg(test)]
@@ -391,7 +664,7 @@ mod tests {
     fn test_pvd_embed_error_empty_bins() {
         let mut host = vec![100u8, 110];
         let secret = b"X";
-        let options = PvdOptions { bins: vec![] };
+        let options = PvdOptions { bins: vec![], ..PvdOptions::default() };
         let indices = vec![0, 1];
         let result = pvd_embed(&mut host, secret, &options, &indices);
         assert!(result.is_err());
@@ -404,6 +677,7 @@ mod tests {
         let secret = b"!";
         let options = PvdOptions {
             bins: vec![(0, 1), (2, 3)], // does not include diff = 240
+            ..PvdOptions::default()
         };
         let indices = vec![0, 1];
 
@@ -416,7 +690,7 @@ mod tests {
     #[test]
     fn test_pvd_extract_empty_bins() {
         let host = vec![100, 110, 120, 130];
-        let options = PvdOptions { bins: vec![] };
+        let options = PvdOptions { bins: vec![], ..PvdOptions::default() };
         let indices = vec![0, 1, 2, 3];
 
         let result = pvd_extract(&host, &options, &indices);
@@ -429,6 +703,7 @@ mod tests {
         // Bins that do not cover difference of 20
         let options = PvdOptions {
             bins: vec![(0, 5), (6, 10)],
+            ..PvdOptions::default()
         };
         let host = vec![50, 30]; // difference = 20
         let indices = vec![0, 1];
@@ -441,7 +716,7 @@ mod tests {
     #[test]
     fn test_pvd_extract_success() {
         // Setup bins so difference = 4 fits (0..7)
-        let options = PvdOptions { bins: vec![(0, 7)] };
+        let options = PvdOptions { bins: vec![(0, 7)], ..PvdOptions::default() };
         // host pixels chosen so diff = p1 - p2 = 4
         // diff_abs = 4 fits bin 0..7, bits_to_extract = floor(log2(8))=3 bits
         let host = vec![120, 116];
@@ -458,7 +733,7 @@ mod tests {
     #[test]
     fn test_pvd_extract_multiple_pairs() {
         // Bins 0..7 with 3 bits per pair
-        let options = PvdOptions { bins: vec![(0, 7)] };
+        let options = PvdOptions { bins: vec![(0, 7)], ..PvdOptions::default() };
         // Two pairs:
         // pair 1: diff = 5 => bits = 101
         // pair 2: diff = 3 => bits = 011
@@ -474,7 +749,7 @@ mod tests {
 
     #[test]
     fn test_pvd_extract_odd_number_of_indices() {
-        let options = PvdOptions { bins: vec![(0, 7)] };
+        let options = PvdOptions { bins: vec![(0, 7)], ..PvdOptions::default() };
         // Host with 3 pixels (odd number)
         let host = vec![130, 125, 140];
         // indices with odd length
@@ -492,7 +767,7 @@ mod tests {
     #[test]
     fn test_pvd_embed_and_extract_full_cycle() {
         // Message to be hidden
-        let mut host = vec![50, 80, 60, 100, 10, 50, 150, 210, 14, 58, 23, 47];
+        let mut host = vec![50, 80, 60, 100, 40, 80, 150, 210, 14, 58, 23, 47];
         let secret_message = b"ABC"; 
         let locator: LinearTraversal = LinearTraversal;
         let embedding_indices: Vec<usize> = locator.iter_indices(host.len()).collect();
@@ -511,4 +786,192 @@ mod tests {
         // We only verify that the extracted message starts with the original secret message.
         assert!(extract_result.unwrap().starts_with(secret_message));
     }
+
+    #[test]
+    fn test_pvd_capacity_matches_embedded_bits() {
+        let mut host = vec![50, 80, 60, 100, 10, 50, 150, 210, 14, 58, 23, 47];
+        let options = PvdOptions::default();
+        let locator = LinearTraversal;
+
+        // Fill the whole capacity through the StegoMethod interface
+        let capacity = options.capacity(&host, &locator);
+        let secret = vec![0xA5u8; capacity / 8];
+        let embedded = options.embed(&mut host, &secret, &locator).unwrap();
+        assert_eq!(embedded, secret.len() * 8);
+        assert!(options.extract(&host, &locator).unwrap().starts_with(&secret));
+    }
+
+    #[test]
+    fn test_pvd_with_locator_matches_slice_version() {
+        let cover = vec![50u8, 80, 60, 100, 10, 50, 150, 210, 14, 58, 23, 47];
+        let options = PvdOptions::default();
+
+        let mut host_slice = cover.clone();
+        let indices: Vec<usize> = LinearTraversal.iter_indices(cover.len()).collect();
+        let bits_slice = pvd_embed(&mut host_slice, b"AB", &options, &indices).unwrap();
+
+        let mut host_lazy = cover.clone();
+        let bits_lazy = pvd_embed_with_locator(&mut host_lazy, b"AB", &options, &LinearTraversal).unwrap();
+
+        assert_eq!(bits_lazy, bits_slice);
+        assert_eq!(host_lazy, host_slice);
+        assert_eq!(
+            pvd_extract_with_locator(&host_lazy, &options, &LinearTraversal).unwrap(),
+            pvd_extract(&host_slice, &options, &indices).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pvd_embed_with_report() {
+        use crate::embedding_locator::PositionListTraversal;
+        use crate::report::SkipReason;
+
+        // Yields the positions as-is, without filtering out-of-bounds indices
+        struct RawPositions<'a>(&'a [usize]);
+        impl EmbeddingLocator for RawPositions<'_> {
+            fn iter_indices(&self, _host_len: usize) -> Box<dyn Iterator<Item = usize> + '_> {
+                Box::new(self.0.iter().copied())
+            }
+        }
+
+        let original = vec![50u8, 80, 60, 100, 40, 80, 150, 210, 14, 58, 23, 47];
+        let options = PvdOptions::default();
+        let positions = [0, 99, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        let locator = PositionListTraversal { positions: &positions[2..] };
+
+        let mut plain = original.clone();
+        pvd_embed_with_locator(&mut plain, b"Hi", &options, &locator).unwrap();
+
+        let mut host = original.clone();
+        let report = pvd_embed_with_report(&mut host, b"Hi", &options, &RawPositions(&positions)).unwrap();
+
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(report.skipped[0].indices, vec![0, 99]);
+        assert_eq!(report.skipped[0].reason, SkipReason::OutOfBounds);
+        // (210, 14) sits in the largest bin, which cannot be centred on an average of 112
+        assert_eq!(report.skipped[1].indices, vec![7, 8]);
+        assert_eq!(report.skipped[1].reason, SkipReason::Overflow);
+        assert_eq!(host, plain);
+
+        let changed: Vec<usize> = report
+            .positions_used
+            .iter()
+            .copied()
+            .filter(|&i| host[i] != original[i])
+            .collect();
+        let distortion: u64 = original
+            .iter()
+            .zip(&host)
+            .map(|(&a, &b)| a.abs_diff(b) as u64)
+            .sum();
+        assert_eq!(report.positions_changed, changed);
+        assert_eq!(report.total_abs_distortion, distortion);
+        assert_eq!(report.bits_embedded, 16);
+        assert_eq!(report.bin_usage.len(), options.bins.len());
+        assert_eq!(report.bin_usage.iter().map(|usage| usage.bits).sum::<usize>(), 16);
+        assert_eq!(
+            report.bin_usage.iter().map(|usage| usage.pairs).sum::<usize>() * 2,
+            report.positions_used.len()
+        );
+        assert_eq!(report.last_index, report.positions_used.last().copied());
+    }
+
+    #[test]
+    fn test_pvd_skips_pairs_near_range_ends() {
+        use crate::report::SkipReason;
+
+        // (255, 250) and (0, 5) would leave 0..=255 for some difference of their bin
+        let cover = vec![255u8, 250, 0, 5, 10, 20, 30, 45, 200, 100];
+        let options = PvdOptions::default();
+        let indices: Vec<usize> = (0..cover.len()).collect();
+        assert_eq!(pvd_capacity_iter(&cover, &options, indices.iter().copied()), 12);
+
+        let mut host = cover.clone();
+        let report = pvd_embed_with_report(&mut host, &[0xFF], &options, &LinearTraversal).unwrap();
+        let skipped: Vec<_> = report
+            .skipped
+            .iter()
+            .map(|skip| (skip.indices.clone(), skip.reason))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![(vec![0, 1], SkipReason::Overflow), (vec![2, 3], SkipReason::Overflow)]
+        );
+        assert_eq!(&host[..4], &cover[..4]);
+        assert_eq!(pvd_extract(&host, &options, &indices).unwrap()[0], 0xFF);
+
+        // Clamping (255, 250) used to corrupt the first byte without reporting it
+        let mut host = vec![255u8, 250, 10, 20, 30, 45, 200, 100];
+        let indices: Vec<usize> = (0..host.len()).collect();
+        assert_eq!(pvd_embed(&mut host, &[0xFF], &options, &indices), Ok(8));
+        assert_eq!(pvd_extract(&host, &options, &indices).unwrap()[0], 0xFF);
+    }
+
+    #[test]
+    fn test_pvd_bit_orders_round_trip() {
+        use crate::bit_order::BitOrder;
+
+        let cover = vec![50u8, 80, 60, 100, 10, 50, 150, 210, 14, 58, 23, 47, 90, 200];
+        let mut outputs = Vec::new();
+        for secret_bit_order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            for chunk_bit_order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+                let options = PvdOptions {
+                    secret_bit_order,
+                    chunk_bit_order,
+                    ..PvdOptions::default()
+                };
+                let mut host = cover.clone();
+                pvd_embed_with_locator(&mut host, b"Hi", &options, &LinearTraversal).unwrap();
+                let extracted =
+                    pvd_extract_with_locator(&host, &options, &LinearTraversal).unwrap();
+                assert!(extracted.starts_with(b"Hi"));
+                outputs.push(host);
+            }
+        }
+        // The orders actually change the layout
+        assert_ne!(outputs[0], outputs[1]);
+        assert_ne!(outputs[0], outputs[2]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_pvd_extract_parallel_matches_sequential() {
+        use crate::bit_order::BitOrder;
+        use crate::test_utils::{TestRng, textured_cover};
+
+        let host = textured_cover(400, 400, 48);
+        let mut shuffled = TestRng::new(48).permutation(host.len());
+        // An odd count and out-of-range indices over several chunks
+        shuffled.truncate(150_001);
+        shuffled.insert(70_001, host.len() + 3);
+        let linear: Vec<usize> = (0..host.len()).collect();
+
+        for (indices, order) in [
+            (&linear, BitOrder::MsbFirst),
+            (&shuffled, BitOrder::LsbFirst),
+        ] {
+            let options = PvdOptions {
+                secret_bit_order: order,
+                chunk_bit_order: order,
+                ..PvdOptions::default()
+            };
+            let expected = pvd_extract(&host, &options, indices).unwrap();
+            assert_eq!(
+                pvd_extract_parallel(&host, &options, indices).unwrap(),
+                expected
+            );
+        }
+
+        // The first difference out of the bins is reported, as sequentially
+        let mut flat = vec![10u8; 200_000];
+        flat[150_000] = 200;
+        flat[190_001] = 200;
+        let options = PvdOptions {
+            bins: vec![(0, 1), (2, 3)],
+            ..PvdOptions::default()
+        };
+        let indices: Vec<usize> = (0..flat.len()).collect();
+        let err = pvd_extract_parallel(&flat, &options, &indices).unwrap_err();
+        assert_eq!(err, pvd_extract(&flat, &options, &indices).unwrap_err());
+    }
 }
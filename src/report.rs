@@ -0,0 +1,83 @@
+use alloc::vec::Vec;
+
+/// Reason why a position (or a pair of positions) was skipped during embedding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// An index returned by the locator is outside the host.
+    OutOfBounds,
+    /// Embedding would push a sample outside its valid range.
+    Overflow,
+}
+
+/// Positions skipped during embedding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedPositions {
+    /// The skipped index, or both indices of a skipped pair.
+    pub indices: Vec<usize>,
+    /// Why the positions were skipped.
+    pub reason: SkipReason,
+}
+
+/// Usage of one PVD bin during embedding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BinUsage {
+    /// The bin range `(min, max)`, as configured in `PvdOptions::bins`.
+    pub bin: (i32, i32),
+    /// Number of pairs whose difference fell in this bin.
+    pub pairs: usize,
+    /// Number of secret bits embedded in these pairs.
+    pub bits: usize,
+}
+
+/// Description of what changed in the host during embedding.
+///
+/// Returned by the `*_with_report` embedding functions, for audit logs and for comparing
+/// methods on the same host.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::bitplane::{bitplane_embed_with_report, BitplaneOptions};
+/// use stegano_rs::embedding_locator::LinearTraversal;
+///
+/// let mut host = vec![0u8; 16];
+/// let report = bitplane_embed_with_report(&mut host, &[0b1000_0001], &BitplaneOptions::default(), &LinearTraversal)
+///     .unwrap();
+///
+/// assert_eq!(report.bits_embedded, 8);
+/// assert_eq!(report.positions_used, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+/// assert_eq!(report.positions_changed, vec![0, 7]);
+/// assert_eq!(report.last_index, Some(7));
+/// assert_eq!(report.total_abs_distortion, 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmbedReport {
+    /// Number of secret bits embedded.
+    pub bits_embedded: usize,
+    /// Host positions that carry secret bits, in embedding order.
+    pub positions_used: Vec<usize>,
+    /// Host positions whose value actually changed, in embedding order.
+    pub positions_changed: Vec<usize>,
+    /// Last index consumed from the locator, if any.
+    pub last_index: Option<usize>,
+    /// Per-bin usage, in the order of `PvdOptions::bins` (empty for bitplane embedding).
+    pub bin_usage: Vec<BinUsage>,
+    /// Positions that were skipped, with the reason.
+    pub skipped: Vec<SkippedPositions>,
+    /// Sum of the absolute differences between the original and the new host values.
+    pub total_abs_distortion: u64,
+}
+
+impl EmbedReport {
+    /// Records that `index` carries secret bits and changed from `before` to `after`.
+    pub(crate) fn record_position(&mut self, index: usize, before: i128, after: i128) {
+        self.positions_used.push(index);
+        if before != after {
+            self.positions_changed.push(index);
+            self.total_abs_distortion += before.abs_diff(after) as u64;
+        }
+    }
+
+    pub(crate) fn record_skip(&mut self, indices: Vec<usize>, reason: SkipReason) {
+        self.skipped.push(SkippedPositions { indices, reason });
+    }
+}
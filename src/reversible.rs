@@ -0,0 +1,239 @@
+//! Reversible data hiding: the exact original cover is recovered together with the secret.
+//!
+//! Regular methods destroy the bits they overwrite. Reversible methods store enough side
+//! information (overhead) in the payload to undo the embedding bit-exactly, which is
+//! required for medical or legal images.
+//!
+//! Available methods:
+//! - [`histogram_shifting`]: histogram shifting (Ni, Shi, Ansari and Su).
+//! - [`difference_expansion`]: difference expansion of pixel pairs (Tian).
+//! - [`prediction_error`]: prediction-error expansion on 2-D images (MED, GAP or rhombus
+//!   predictor, two-pass checkerboard).
+
+use alloc::{string::String, vec::Vec};
+
+use crate::bit_order::{BitOrder, BitWriter, read_bit};
+
+pub mod difference_expansion;
+pub mod histogram_shifting;
+pub mod prediction_error;
+
+/// Result of [`ReversibleMethod::restore_cover`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Restored {
+    /// The bit-exact original cover.
+    pub cover: Vec<u8>,
+    /// The embedded secret.
+    pub secret: Vec<u8>,
+}
+
+/// Common interface of the reversible methods.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::reversible::ReversibleMethod;
+/// use stegano_rs::reversible::histogram_shifting::HistogramShifting;
+///
+/// let cover: Vec<u8> = (0..1024).map(|i| (100 + (i % 7) * 3) as u8).collect();
+/// let mut stego = cover.clone();
+///
+/// let method: Box<dyn ReversibleMethod> = Box::new(HistogramShifting);
+/// method.embed(&mut stego, b"Hi").unwrap();
+///
+/// let restored = method.restore_cover(&stego).unwrap();
+/// assert_eq!(restored.secret, b"Hi");
+/// assert_eq!(restored.cover, cover);
+/// ```
+pub trait ReversibleMethod {
+    /// Returns the number of secret bits that can be embedded into `host`, once the
+    /// overhead has been accounted for.
+    fn capacity(&self, host: &[u8]) -> usize;
+
+    /// Embeds `secret` into `host`. The host is left untouched when an error is returned.
+    fn embed(&self, host: &mut [u8], secret: &[u8]) -> Result<(), String>;
+
+    /// Extracts the secret from a stego buffer.
+    fn extract(&self, host: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(self.restore_cover(host)?.secret)
+    }
+
+    /// Extracts the secret and rebuilds the bit-exact original cover.
+    fn restore_cover(&self, host: &[u8]) -> Result<Restored, String>;
+}
+
+/// Builds a payload bit by bit. Multi-byte fields are written big-endian, MSB first.
+pub(crate) struct PayloadWriter {
+    bits: BitWriter,
+    len: usize,
+}
+
+impl PayloadWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            bits: BitWriter::new(BitOrder::MsbFirst),
+            len: 0,
+        }
+    }
+
+    pub(crate) fn bit(&mut self, bit: u8) {
+        self.bits.push(bit);
+        self.len += 1;
+    }
+
+    pub(crate) fn bits(&mut self, value: u64, count: usize) {
+        for i in (0..count).rev() {
+            self.bit(((value >> i) & 1) as u8);
+        }
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.bits(value as u64, 32);
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.bits(byte as u64, 8);
+        }
+    }
+
+    /// Number of bits written so far.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the payload bits, MSB first. The last byte is padded with zeros.
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bits.into_bytes()
+    }
+}
+
+/// Reads the fields of an extracted payload, bit by bit.
+pub(crate) struct PayloadReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PayloadReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub(crate) fn bit(&mut self) -> Result<u8, String> {
+        if self.pos >= self.data.len() * 8 {
+            return Err("Truncated payload: the host does not contain reversible data".into());
+        }
+        let bit = read_bit(self.data, self.pos, BitOrder::MsbFirst);
+        self.pos += 1;
+        Ok(bit)
+    }
+
+    pub(crate) fn bits(&mut self, count: usize) -> Result<u64, String> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.bit()? as u64;
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, String> {
+        Ok(self.bits(32)? as u32)
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<Vec<u8>, String> {
+        if len > (self.data.len() * 8 - self.pos) / 8 {
+            return Err("Truncated payload: the host does not contain reversible data".into());
+        }
+        (0..len).map(|_| Ok(self.bits(8)? as u8)).collect()
+    }
+}
+
+/// Returns the bits of `bytes`, MSB first.
+pub(crate) fn bits_msb_first(bytes: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    bytes
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+}
+
+/// Writes a location map run-length encoded: the first value on one bit, then the length
+/// of each run as an Elias gamma code.
+pub(crate) fn write_location_map(writer: &mut PayloadWriter, map: &[bool]) {
+    let Some(&first) = map.first() else {
+        return;
+    };
+    writer.bit(first as u8);
+
+    let mut run = 0u64;
+    let mut current = first;
+    for &value in map {
+        if value == current {
+            run += 1;
+        } else {
+            write_gamma(writer, run);
+            current = value;
+            run = 1;
+        }
+    }
+    write_gamma(writer, run);
+}
+
+/// Reads a location map of `len` entries written by [`write_location_map`].
+pub(crate) fn read_location_map(
+    reader: &mut PayloadReader,
+    len: usize,
+) -> Result<Vec<bool>, String> {
+    let mut map = Vec::with_capacity(len);
+    if len == 0 {
+        return Ok(map);
+    }
+
+    let mut current = reader.bit()? == 1;
+    while map.len() < len {
+        let run = read_gamma(reader)? as usize;
+        if run > len - map.len() {
+            return Err("Invalid location map: runs exceed the number of entries".into());
+        }
+        map.extend(core::iter::repeat_n(current, run));
+        current = !current;
+    }
+    Ok(map)
+}
+
+fn write_gamma(writer: &mut PayloadWriter, value: u64) {
+    let bits = 64 - value.leading_zeros() as usize;
+    writer.bits(0, bits - 1);
+    writer.bits(value, bits);
+}
+
+fn read_gamma(reader: &mut PayloadReader) -> Result<u64, String> {
+    let mut zeros = 0;
+    while reader.bit()? == 0 {
+        zeros += 1;
+        if zeros >= 64 {
+            return Err("Invalid location map: run length too large".into());
+        }
+    }
+    Ok((1 << zeros) | reader.bits(zeros)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_map_round_trip() {
+        let mut map = vec![false; 1000];
+        map[3] = true;
+        map[500..520].fill(true);
+        map[999] = true;
+
+        let mut writer = PayloadWriter::new();
+        write_location_map(&mut writer, &map);
+        writer.u32(0xDEAD_BEEF);
+        assert!(writer.len() < 100);
+
+        let bytes = writer.into_bytes();
+        let mut reader = PayloadReader::new(&bytes);
+        assert_eq!(read_location_map(&mut reader, map.len()).unwrap(), map);
+        assert_eq!(reader.u32().unwrap(), 0xDEAD_BEEF);
+    }
+}
@@ -0,0 +1,247 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use super::{
+    PayloadReader, PayloadWriter, Restored, ReversibleMethod, read_location_map, write_location_map,
+};
+use crate::bit_order::{BitOrder, BitWriter};
+
+/// Reversible embedding by difference expansion (Tian, 2003).
+///
+/// Like [`pvd_embed`](crate::pvd::pvd_embed), the host is split into pairs of consecutive
+/// samples `(x, y)` (a last unpaired sample is left untouched). Each pair is transformed into
+/// its integer average `l = ⌊(x + y) / 2⌋` and difference `h = x - y`, and one bit `b` is
+/// hidden in the difference, keeping the average:
+/// - *expandable* pairs are expanded: `h' = 2h + b`,
+/// - other *changeable* pairs get the bit in the LSB of the difference: `h' = 2⌊h/2⌋ + b`,
+///   their original LSB being saved in the payload,
+/// - pairs that would overflow `0..=255` in both cases are left untouched.
+///
+/// Changeable pairs stay changeable after embedding, so the decoder finds the same carriers.
+/// The bitstream carried by their difference LSBs holds the run-length compressed location
+/// map (which changeable pairs were expanded), the saved LSBs, the secret length and the
+/// secret.
+///
+/// Only pairs with `|h| <= threshold` are expanded: a lower threshold reduces the distortion
+/// but also the capacity.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::reversible::ReversibleMethod;
+/// use stegano_rs::reversible::difference_expansion::DifferenceExpansion;
+///
+/// let cover: Vec<u8> = (0..512).map(|i| (100 + (i % 16) / 2) as u8).collect();
+/// let mut stego = cover.clone();
+/// let method = DifferenceExpansion::default();
+///
+/// method.embed(&mut stego, b"Tian").unwrap();
+/// let restored = method.restore_cover(&stego).unwrap();
+/// assert_eq!(restored.secret, b"Tian");
+/// assert_eq!(restored.cover, cover);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DifferenceExpansion {
+    /// Largest absolute difference of an expanded pair.
+    pub threshold: u8,
+}
+
+impl Default for DifferenceExpansion {
+    /// Expands every expandable pair (`threshold` = 255).
+    fn default() -> Self {
+        Self { threshold: 255 }
+    }
+}
+
+/// Returns `true` if a pair with average `l` and difference `h` stays in `0..=255`.
+fn fits(l: i32, h: i32) -> bool {
+    h.abs() <= (2 * (255 - l)).min(2 * l + 1)
+}
+
+fn is_expandable(l: i32, h: i32) -> bool {
+    fits(l, 2 * h) && fits(l, 2 * h + 1)
+}
+
+fn is_changeable(l: i32, h: i32) -> bool {
+    fits(l, 2 * (h >> 1)) && fits(l, 2 * (h >> 1) + 1)
+}
+
+/// Average and difference of a pair.
+fn transform(x: u8, y: u8) -> (i32, i32) {
+    ((x as i32 + y as i32) >> 1, x as i32 - y as i32)
+}
+
+/// Pair with average `l` and difference `h`.
+fn inverse(l: i32, h: i32) -> (u8, u8) {
+    ((l + ((h + 1) >> 1)) as u8, (l - (h >> 1)) as u8)
+}
+
+/// Indices of the changeable pairs, as `(first sample, l, h)`.
+fn changeable_pairs(host: &[u8]) -> Vec<(usize, i32, i32)> {
+    host.chunks_exact(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let (l, h) = transform(pair[0], pair[1]);
+            (2 * i, l, h)
+        })
+        .filter(|&(_, l, h)| is_changeable(l, h))
+        .collect()
+}
+
+impl DifferenceExpansion {
+    /// Builds the location map and the overhead bits that precede the secret.
+    fn overhead(&self, pairs: &[(usize, i32, i32)]) -> (Vec<bool>, PayloadWriter) {
+        let map: Vec<bool> = pairs
+            .iter()
+            .map(|&(_, l, h)| is_expandable(l, h) && h.unsigned_abs() <= self.threshold as u32)
+            .collect();
+
+        let mut writer = PayloadWriter::new();
+        write_location_map(&mut writer, &map);
+        for (&(_, _, h), &expanded) in pairs.iter().zip(&map) {
+            if !expanded {
+                writer.bit((h & 1) as u8);
+            }
+        }
+        (map, writer)
+    }
+}
+
+impl ReversibleMethod for DifferenceExpansion {
+    fn capacity(&self, host: &[u8]) -> usize {
+        let pairs = changeable_pairs(host);
+        let (_, overhead) = self.overhead(&pairs);
+        pairs.len().saturating_sub(overhead.len() + 32)
+    }
+
+    /// # Errors
+    /// Returns an error if the changeable pairs cannot hold the overhead and the secret.
+    fn embed(&self, host: &mut [u8], secret: &[u8]) -> Result<(), String> {
+        let pairs = changeable_pairs(host);
+        let (map, mut payload) = self.overhead(&pairs);
+
+        let secret_len = u32::try_from(secret.len())
+            .map_err(|_| "Secret too large: its length is stored on 32 bits".to_string())?;
+        payload.u32(secret_len);
+        payload.bytes(secret);
+
+        if payload.len() > pairs.len() {
+            return Err(format!(
+                "Not enough capacity to embed the secret: capacity={} bits, message={} bits",
+                self.capacity(host),
+                secret.len() * 8
+            ));
+        }
+
+        // Changeable pairs after the payload carry 0 bits
+        let payload_len = payload.len();
+        let bytes = payload.into_bytes();
+        let bits = super::bits_msb_first(&bytes)
+            .take(payload_len)
+            .chain(core::iter::repeat(0));
+        for ((&(idx, l, h), &expanded), bit) in pairs.iter().zip(&map).zip(bits) {
+            let bit = bit as i32;
+            let new_h = if expanded {
+                2 * h + bit
+            } else {
+                2 * (h >> 1) + bit
+            };
+            (host[idx], host[idx + 1]) = inverse(l, new_h);
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the host does not contain data embedded by difference expansion.
+    fn restore_cover(&self, host: &[u8]) -> Result<Restored, String> {
+        let pairs = changeable_pairs(host);
+
+        let mut bits = BitWriter::new(BitOrder::MsbFirst);
+        for &(_, _, h) in &pairs {
+            bits.push((h & 1) as u8);
+        }
+        let bits = bits.into_bytes();
+        let mut reader = PayloadReader::new(&bits);
+
+        let map = read_location_map(&mut reader, pairs.len())?;
+        let mut cover = host.to_vec();
+        for (&(idx, l, h), &expanded) in pairs.iter().zip(&map) {
+            let original_h = if expanded {
+                h >> 1
+            } else {
+                2 * (h >> 1) + reader.bit()? as i32
+            };
+            (cover[idx], cover[idx + 1]) = inverse(l, original_h);
+        }
+
+        let secret_len = reader.u32()? as usize;
+        let secret = reader.bytes(secret_len)?;
+        Ok(Restored { cover, secret })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{TestRng, natural_cover};
+
+    #[test]
+    fn test_round_trip_restores_cover() {
+        let cover = natural_cover(64, 64, 21);
+        let secret = TestRng::new(8).bytes(200);
+        let method = DifferenceExpansion::default();
+        let mut stego = cover.clone();
+
+        assert!(method.capacity(&cover) >= secret.len() * 8);
+        method.embed(&mut stego, &secret).unwrap();
+        assert_ne!(stego, cover);
+
+        let restored = method.restore_cover(&stego).unwrap();
+        assert_eq!(restored.secret, secret);
+        assert_eq!(restored.cover, cover);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_threshold_reduces_distortion() {
+        use crate::metrics::mse;
+
+        let cover = natural_cover(64, 64, 4);
+        let secret = TestRng::new(1).bytes(32);
+
+        let mut distortions = Vec::new();
+        for threshold in [255, 6] {
+            let method = DifferenceExpansion { threshold };
+            let mut stego = cover.clone();
+            method.embed(&mut stego, &secret).unwrap();
+            assert_eq!(method.restore_cover(&stego).unwrap().cover, cover);
+            distortions.push(mse(&cover, &stego).unwrap());
+        }
+        assert!(distortions[1] < distortions[0]);
+    }
+
+    #[test]
+    fn test_saturated_pairs_are_left_untouched() {
+        // (0, 255) and (255, 255) are not changeable; the odd last sample is never used
+        let mut cover = natural_cover(32, 32, 2);
+        cover[..4].copy_from_slice(&[0, 255, 255, 255]);
+        cover.push(77);
+        let mut stego = cover.clone();
+        let method = DifferenceExpansion::default();
+
+        method.embed(&mut stego, b"edge").unwrap();
+        assert_eq!(&stego[..4], &cover[..4]);
+        assert_eq!(stego.last(), Some(&77));
+        assert_eq!(method.restore_cover(&stego).unwrap().cover, cover);
+    }
+
+    #[test]
+    fn test_not_enough_capacity() {
+        let mut host = vec![0u8, 255, 255, 255, 12, 13];
+        let res = DifferenceExpansion::default().embed(&mut host, b"x");
+        assert!(res.unwrap_err().starts_with("Not enough capacity"));
+        assert_eq!(host, vec![0, 255, 255, 255, 12, 13]);
+    }
+}
@@ -0,0 +1,300 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use super::{PayloadReader, Restored, ReversibleMethod, bits_msb_first};
+use crate::bit_order::{BitOrder, BitWriter};
+
+/// Number of leading samples whose LSBs hold the peak and zero values.
+const HEADER_SAMPLES: usize = 16;
+
+/// Fixed overhead in bits: original header LSBs, location map size and secret length.
+const FIXED_OVERHEAD_BITS: usize = HEADER_SAMPLES + 32 + 32;
+
+/// Reversible embedding by histogram shifting (Ni, Shi, Ansari and Su, 2006).
+///
+/// The histogram of the host is computed, and a peak value `P` (most frequent) and a zero
+/// value `Z` (least frequent) are selected. Values strictly between `P` and `Z` are shifted
+/// by one towards `Z`, which frees the bin next to `P`: each sample equal to `P` then
+/// carries one bit (`P` for 0, `P ± 1` for 1). The capacity is the height of the peak bin.
+///
+/// Layout:
+/// - The LSBs of the first 16 samples hold `P` and `Z` (8 bits each, MSB first). These
+///   samples are not used for the histogram.
+/// - The payload, embedded in the peak samples, holds the original LSBs of these 16
+///   samples, the location map, the secret length and the secret.
+/// - When `Z` is not empty, the positions of the samples equal to `Z` are recorded in the
+///   location map (32 bits each), so they can be told apart from shifted samples when the
+///   cover is restored.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::reversible::ReversibleMethod;
+/// use stegano_rs::reversible::histogram_shifting::HistogramShifting;
+///
+/// let cover: Vec<u8> = (0..4096).map(|i| (120 + (i % 5) * 2) as u8).collect();
+/// let mut stego = cover.clone();
+///
+/// let capacity = HistogramShifting.capacity(&cover);
+/// assert!(capacity >= 8 * 4);
+/// HistogramShifting.embed(&mut stego, b"Scan").unwrap();
+///
+/// assert_eq!(HistogramShifting.extract(&stego).unwrap(), b"Scan");
+/// assert_eq!(HistogramShifting.restore_cover(&stego).unwrap().cover, cover);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HistogramShifting;
+
+/// Peak and zero values selected for a host, with the samples equal to the zero value.
+struct ShiftPlan {
+    peak: u8,
+    zero: u8,
+    location_map: Vec<u32>,
+    carriers: usize,
+}
+
+impl ShiftPlan {
+    fn new(body: &[u8]) -> Self {
+        let mut histogram = [0usize; 256];
+        for &v in body {
+            histogram[v as usize] += 1;
+        }
+
+        // Most frequent value, the lowest one on ties
+        let peak = (0..256).rev().max_by_key(|&v| histogram[v]).unwrap_or(0);
+
+        // Least frequent value, the closest to the peak on ties. A non-empty zero bin next to
+        // the peak cannot be used: its samples would be read as carriers.
+        let zero = (0..256)
+            .filter(|&v| v != peak && (v.abs_diff(peak) >= 2 || histogram[v] == 0))
+            .min_by_key(|&v| (histogram[v], v.abs_diff(peak)))
+            .unwrap_or(0);
+
+        let location_map = body
+            .iter()
+            .enumerate()
+            .filter(|&(_, &v)| v as usize == zero)
+            .map(|(i, _)| i as u32)
+            .collect();
+
+        Self {
+            peak: peak as u8,
+            zero: zero as u8,
+            location_map,
+            carriers: histogram[peak],
+        }
+    }
+
+    /// Direction of the shift, towards the zero value.
+    fn direction(&self) -> i16 {
+        if self.zero > self.peak { 1 } else { -1 }
+    }
+
+    /// Returns `true` if `v` lies strictly between the peak and the zero value.
+    fn is_between(&self, v: u8) -> bool {
+        v > self.peak.min(self.zero) && v < self.peak.max(self.zero)
+    }
+
+    fn capacity(&self) -> usize {
+        self.carriers
+            .saturating_sub(FIXED_OVERHEAD_BITS + 32 * self.location_map.len())
+    }
+}
+
+fn check_host_len(len: usize) -> Result<(), String> {
+    if len <= HEADER_SAMPLES {
+        return Err(format!(
+            "Host too small: histogram shifting needs more than {} samples",
+            HEADER_SAMPLES
+        ));
+    }
+    if len - HEADER_SAMPLES > u32::MAX as usize {
+        return Err("Host too large: positions are stored on 32 bits".into());
+    }
+    Ok(())
+}
+
+impl ReversibleMethod for HistogramShifting {
+    fn capacity(&self, host: &[u8]) -> usize {
+        if check_host_len(host.len()).is_err() {
+            return 0;
+        }
+        ShiftPlan::new(&host[HEADER_SAMPLES..]).capacity()
+    }
+
+    /// # Errors
+    /// Returns an error if the host has 16 samples or less, or if the peak bin is too small
+    /// to hold the overhead and the secret.
+    fn embed(&self, host: &mut [u8], secret: &[u8]) -> Result<(), String> {
+        check_host_len(host.len())?;
+        let (header, body) = host.split_at_mut(HEADER_SAMPLES);
+        let plan = ShiftPlan::new(body);
+
+        let secret_len = u32::try_from(secret.len())
+            .map_err(|_| "Secret too large: its length is stored on 32 bits".to_string())?;
+        if secret.len() * 8 > plan.capacity() {
+            return Err(format!(
+                "Not enough capacity to embed the secret: capacity={} bits, message={} bits",
+                plan.capacity(),
+                secret.len() * 8
+            ));
+        }
+
+        // Payload: original header LSBs, location map, secret length, secret
+        let mut header_lsbs = BitWriter::new(BitOrder::MsbFirst);
+        for &v in header.iter() {
+            header_lsbs.push(v & 1);
+        }
+        let mut payload = header_lsbs.into_bytes();
+        payload.extend_from_slice(&(plan.location_map.len() as u32).to_be_bytes());
+        for &pos in &plan.location_map {
+            payload.extend_from_slice(&pos.to_be_bytes());
+        }
+        payload.extend_from_slice(&secret_len.to_be_bytes());
+        payload.extend_from_slice(secret);
+
+        let direction = plan.direction();
+        let mut bits = bits_msb_first(&payload);
+        for v in body.iter_mut() {
+            if plan.is_between(*v) {
+                *v = (*v as i16 + direction) as u8;
+            } else if *v == plan.peak {
+                // Peak samples left after the payload keep their value (bit 0)
+                let bit = bits.next().unwrap_or(0);
+                *v = (*v as i16 + direction * bit as i16) as u8;
+            }
+        }
+
+        for (sample, bit) in header
+            .iter_mut()
+            .zip(bits_msb_first(&[plan.peak, plan.zero]))
+        {
+            *sample = (*sample & !1) | bit;
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the host does not contain data embedded by histogram shifting.
+    fn restore_cover(&self, host: &[u8]) -> Result<Restored, String> {
+        check_host_len(host.len())?;
+        let mut cover = host.to_vec();
+        let (header, body) = cover.split_at_mut(HEADER_SAMPLES);
+
+        let mut values = BitWriter::new(BitOrder::MsbFirst);
+        for &v in header.iter() {
+            values.push(v & 1);
+        }
+        let values = values.into_bytes();
+        let (peak, zero) = (values[0], values[1]);
+        if peak == zero {
+            return Err("Invalid header: peak and zero values are equal".into());
+        }
+        let plan = ShiftPlan {
+            peak,
+            zero,
+            location_map: Vec::new(),
+            carriers: 0,
+        };
+        let direction = plan.direction();
+        let marked = (peak as i16 + direction) as u8;
+
+        // Read the carriers and undo the shift
+        let mut bits = BitWriter::new(BitOrder::MsbFirst);
+        for v in body.iter_mut() {
+            if *v == peak {
+                bits.push(0);
+            } else if *v == marked {
+                bits.push(1);
+                *v = peak;
+            } else if plan.is_between(*v) || *v == zero {
+                *v = (*v as i16 - direction) as u8;
+            }
+        }
+        let bits = bits.into_bytes();
+
+        let mut reader = PayloadReader::new(&bits);
+        let original_lsbs = reader.bytes(HEADER_SAMPLES / 8)?;
+        let map_len = reader.u32()? as usize;
+        for _ in 0..map_len {
+            let pos = reader.u32()? as usize;
+            if pos >= body.len() {
+                return Err(format!(
+                    "Invalid location map: position {} is out of range",
+                    pos
+                ));
+            }
+            body[pos] = zero;
+        }
+        let secret_len = reader.u32()? as usize;
+        let secret = reader.bytes(secret_len)?;
+
+        for (sample, bit) in header.iter_mut().zip(bits_msb_first(&original_lsbs)) {
+            *sample = (*sample & !1) | bit;
+        }
+        Ok(Restored { cover, secret })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{TestRng, natural_cover};
+
+    #[test]
+    fn test_round_trip_restores_cover() {
+        let cover = natural_cover(128, 128, 11);
+        let secret = TestRng::new(3).bytes(40);
+        let mut stego = cover.clone();
+
+        assert!(HistogramShifting.capacity(&cover) >= secret.len() * 8);
+        HistogramShifting.embed(&mut stego, &secret).unwrap();
+        assert_ne!(stego, cover);
+        assert!(stego.iter().zip(&cover).all(|(&a, &b)| a.abs_diff(b) <= 1));
+
+        let restored = HistogramShifting.restore_cover(&stego).unwrap();
+        assert_eq!(restored.secret, secret);
+        assert_eq!(restored.cover, cover);
+    }
+
+    #[test]
+    fn test_full_histogram_uses_location_map() {
+        // Every value is present: the zero bin is not empty
+        let mut cover: Vec<u8> = (0..=255).collect();
+        cover.extend(core::iter::repeat_n(100u8, 1000));
+        cover.extend((0..=255).rev());
+        cover.extend(core::iter::repeat_n(103u8, 500));
+
+        let plan = ShiftPlan::new(&cover[HEADER_SAMPLES..]);
+        assert_eq!(plan.peak, 100);
+        assert!(!plan.location_map.is_empty());
+
+        let mut stego = cover.clone();
+        HistogramShifting
+            .embed(&mut stego, b"location map")
+            .unwrap();
+        let restored = HistogramShifting.restore_cover(&stego).unwrap();
+        assert_eq!(restored.secret, b"location map");
+        assert_eq!(restored.cover, cover);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut small = [0u8; 16];
+        assert!(HistogramShifting.embed(&mut small, b"x").is_err());
+        assert_eq!(HistogramShifting.capacity(&small), 0);
+
+        let cover = natural_cover(32, 32, 5);
+        let mut stego = cover.clone();
+        let too_long = vec![0u8; HistogramShifting.capacity(&cover) / 8 + 1];
+        assert!(
+            HistogramShifting
+                .embed(&mut stego, &too_long)
+                .unwrap_err()
+                .starts_with("Not enough capacity")
+        );
+        assert_eq!(stego, cover);
+    }
+}
@@ -0,0 +1,466 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use super::{
+    PayloadReader, PayloadWriter, Restored, ReversibleMethod, bits_msb_first, read_location_map,
+    write_location_map,
+};
+use crate::bit_order::{BitOrder, BitWriter};
+use crate::geometry::ImageGeometry;
+
+/// Width of the border that is never predicted. Its LSBs hold the header.
+const BORDER: usize = 2;
+
+/// Predictor used to estimate each sample from its neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Predictor {
+    /// Median edge detector of LOCO-I / JPEG-LS (west, north and north-west neighbours).
+    Med,
+    /// Gradient adjusted predictor of CALIC.
+    Gap,
+    /// Rounded mean of the four west, east, north and south neighbours (rhombus pattern).
+    Rhombus,
+}
+
+impl Predictor {
+    fn code(self) -> u64 {
+        match self {
+            Predictor::Med => 0,
+            Predictor::Gap => 1,
+            Predictor::Rhombus => 2,
+        }
+    }
+
+    fn from_code(code: u64) -> Result<Self, String> {
+        match code {
+            0 => Ok(Predictor::Med),
+            1 => Ok(Predictor::Gap),
+            2 => Ok(Predictor::Rhombus),
+            _ => Err(format!("Invalid header: unknown predictor {}", code)),
+        }
+    }
+
+    /// Predicts the sample at `(x, y)`; `at(dx, dy)` returns the neighbour at `(x + dx, y + dy)`.
+    fn predict(self, at: impl Fn(isize, isize) -> i32) -> i32 {
+        let (w, n, nw) = (at(-1, 0), at(0, -1), at(-1, -1));
+        match self {
+            Predictor::Med => {
+                if nw >= w.max(n) {
+                    w.min(n)
+                } else if nw <= w.min(n) {
+                    w.max(n)
+                } else {
+                    w + n - nw
+                }
+            }
+            Predictor::Gap => {
+                let (ww, nn, ne, nne) = (at(-2, 0), at(0, -2), at(1, -1), at(1, -2));
+                let dh = (w - ww).abs() + (n - nw).abs() + (n - ne).abs();
+                let dv = (w - nw).abs() + (n - nn).abs() + (ne - nne).abs();
+                if dv - dh > 80 {
+                    return w;
+                }
+                if dh - dv > 80 {
+                    return n;
+                }
+                let t = (w + n).div_euclid(2) + (ne - nw).div_euclid(4);
+                if dv - dh > 32 {
+                    (t + w).div_euclid(2)
+                } else if dv - dh > 8 {
+                    (3 * t + w).div_euclid(4)
+                } else if dh - dv > 32 {
+                    (t + n).div_euclid(2)
+                } else if dh - dv > 8 {
+                    (3 * t + n).div_euclid(4)
+                } else {
+                    t
+                }
+            }
+            Predictor::Rhombus => (w + n + at(1, 0) + at(0, 1) + 2) >> 2,
+        }
+    }
+}
+
+/// Reversible embedding by prediction-error expansion (Thodi and Rodríguez), on 2-D images.
+///
+/// Each sample `x` is predicted from its neighbours (`p`), and the prediction error
+/// `e = x - p` is modified:
+/// - `-T <= e < T` is expanded and carries one bit `b`: `e' = 2e + b`,
+/// - larger errors are shifted by `T` away from zero, so they cannot be confused with
+///   expanded ones.
+///
+/// The image is processed in two passes over a checkerboard (rhombus pattern): the first pass
+/// modifies the samples with `x + y` even, predicted from the other samples, then the second
+/// pass modifies the samples with `x + y` odd. Every channel is processed independently.
+///
+/// A border of 2 pixels is never predicted. The LSBs of the border hold the header: the
+/// threshold `T`, the predictor, and the run-length compressed location map of the samples
+/// that could overflow (`x < T` or `x > 255 - T`), which are left untouched. The original
+/// LSBs of the border are saved in the payload with the secret. The decoder reads the
+/// threshold and the predictor from the header, only the geometry must match.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+/// use stegano_rs::reversible::ReversibleMethod;
+/// use stegano_rs::reversible::prediction_error::{PredictionErrorExpansion, Predictor};
+///
+/// let geometry = ImageGeometry::new(32, 32, 1);
+/// let cover: Vec<u8> = (0..geometry.len()).map(|i| (90 + (i % 32) + (i / 32)) as u8).collect();
+/// let method = PredictionErrorExpansion {
+///     predictor: Predictor::Gap,
+///     ..PredictionErrorExpansion::new(geometry)
+/// };
+///
+/// let mut stego = cover.clone();
+/// method.embed(&mut stego, b"PEE").unwrap();
+/// let restored = method.restore_cover(&stego).unwrap();
+/// assert_eq!(restored.secret, b"PEE");
+/// assert_eq!(restored.cover, cover);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct PredictionErrorExpansion {
+    /// Layout of the host image.
+    pub geometry: ImageGeometry,
+    /// Predictor used by the embedding.
+    pub predictor: Predictor,
+    /// Prediction errors in `-threshold..threshold` carry one bit (between 1 and 127).
+    pub threshold: u8,
+}
+
+impl PredictionErrorExpansion {
+    /// Returns the default settings for an image: MED predictor, threshold 2.
+    pub fn new(geometry: ImageGeometry) -> Self {
+        Self {
+            geometry,
+            predictor: Predictor::Med,
+            threshold: 2,
+        }
+    }
+
+    fn check(&self, host_len: usize) -> Result<(), String> {
+        self.geometry.check(host_len)?;
+        if self.geometry.width <= 2 * BORDER || self.geometry.height <= 2 * BORDER {
+            return Err(format!(
+                "Image too small: prediction-error expansion needs more than {}x{} pixels",
+                2 * BORDER,
+                2 * BORDER
+            ));
+        }
+        if self.threshold == 0 || self.threshold > 127 {
+            return Err("threshold must be between 1 and 127".into());
+        }
+        Ok(())
+    }
+
+    /// Samples of each pass, in raster order and channel by channel.
+    fn pass_indices(&self, pass: usize) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        let g = self.geometry;
+        (0..g.channels).flat_map(move |c| {
+            (BORDER..g.height - BORDER).flat_map(move |y| {
+                (BORDER..g.width - BORDER)
+                    .filter(move |x| (x + y) % 2 == pass)
+                    .map(move |x| (x, y, c))
+            })
+        })
+    }
+
+    /// Indices of the border samples, which hold the header in their LSBs.
+    fn border_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        let g = self.geometry;
+        (0..g.height).flat_map(move |y| {
+            (0..g.width)
+                .filter(move |&x| {
+                    x < BORDER || y < BORDER || x >= g.width - BORDER || y >= g.height - BORDER
+                })
+                .flat_map(move |x| (0..g.channels).map(move |c| g.index(x, y, c)))
+        })
+    }
+
+    fn predict(&self, image: &[u8], predictor: Predictor, x: usize, y: usize, c: usize) -> i32 {
+        predictor.predict(|dx, dy| {
+            let nx = (x as isize + dx) as usize;
+            let ny = (y as isize + dy) as usize;
+            image[self.geometry.index(nx, ny, c)] as i32
+        })
+    }
+
+    /// Builds the header: threshold, predictor and location map of the excluded samples.
+    fn header(&self, host: &[u8]) -> (Vec<bool>, PayloadWriter) {
+        let t = self.threshold as i32;
+        let excluded: Vec<bool> = (0..2)
+            .flat_map(|pass| self.pass_indices(pass))
+            .map(|(x, y, c)| {
+                let v = host[self.geometry.index(x, y, c)] as i32;
+                v < t || v > 255 - t
+            })
+            .collect();
+
+        let mut header = PayloadWriter::new();
+        header.bits(self.threshold as u64, 8);
+        header.bits(self.predictor.code(), 2);
+        write_location_map(&mut header, &excluded);
+        (excluded, header)
+    }
+
+    /// Embeds `payload` into a copy of the host whose border already holds the header.
+    /// Returns the number of payload bits embedded.
+    fn expand(
+        &self,
+        image: &mut [u8],
+        excluded: &[bool],
+        payload: &[u8],
+        payload_len: usize,
+    ) -> usize {
+        let t = self.threshold as i32;
+        let mut bits = bits_msb_first(payload).take(payload_len);
+        let mut embedded = 0;
+        let mut excluded = excluded.iter();
+
+        for pass in 0..2 {
+            // Predictions only depend on the image before the pass
+            let snapshot = image.to_vec();
+            for (x, y, c) in self.pass_indices(pass) {
+                if *excluded.next().unwrap_or(&true) {
+                    continue;
+                }
+                let idx = self.geometry.index(x, y, c);
+                let p = self.predict(&snapshot, self.predictor, x, y, c);
+                let e = snapshot[idx] as i32 - p;
+                let new_e = if (-t..t).contains(&e) {
+                    let bit = bits.next().map_or(0, |bit| {
+                        embedded += 1;
+                        bit as i32
+                    });
+                    2 * e + bit
+                } else if e >= t {
+                    e + t
+                } else {
+                    e - t
+                };
+                image[idx] = (p + new_e) as u8;
+            }
+        }
+        embedded
+    }
+
+    /// Counts the carriers with a dry run that never runs out of bits, minus the overhead.
+    fn capacity_checked(&self, host: &[u8]) -> Result<usize, String> {
+        self.check(host.len())?;
+        let (excluded, header) = self.header(host);
+        let zeros = vec![0u8; host.len().div_ceil(8)];
+        let carriers = self.expand(&mut host.to_vec(), &excluded, &zeros, host.len());
+        // The payload starts with the original border LSBs and the secret length
+        Ok(carriers.saturating_sub(header.len() + 32))
+    }
+}
+
+impl ReversibleMethod for PredictionErrorExpansion {
+    /// Estimated with a dry run: the predictions of the second pass depend on the bits
+    /// embedded by the first one, so the actual capacity can differ slightly.
+    fn capacity(&self, host: &[u8]) -> usize {
+        self.capacity_checked(host).unwrap_or(0)
+    }
+
+    /// # Errors
+    /// Returns an error if the geometry does not match the host, if the border is too small
+    /// for the header, or if the expandable samples cannot hold the payload.
+    fn embed(&self, host: &mut [u8], secret: &[u8]) -> Result<(), String> {
+        self.check(host.len())?;
+        let (excluded, header) = self.header(host);
+
+        let border: Vec<usize> = self.border_indices().collect();
+        if header.len() > border.len() {
+            return Err(format!(
+                "Header too large for the border: {} bits, {} available",
+                header.len(),
+                border.len()
+            ));
+        }
+
+        let secret_len = u32::try_from(secret.len())
+            .map_err(|_| "Secret too large: its length is stored on 32 bits".to_string())?;
+        let mut payload = PayloadWriter::new();
+        for &idx in &border[..header.len()] {
+            payload.bit(host[idx] & 1);
+        }
+        payload.u32(secret_len);
+        payload.bytes(secret);
+
+        // The header is written first: the border is a neighbour of the predicted samples
+        let mut image = host.to_vec();
+        let header_len = header.len();
+        let header = header.into_bytes();
+        for (&idx, bit) in border.iter().zip(bits_msb_first(&header).take(header_len)) {
+            image[idx] = (image[idx] & !1) | bit;
+        }
+
+        let payload_len = payload.len();
+        let embedded = self.expand(&mut image, &excluded, &payload.into_bytes(), payload_len);
+        if embedded < payload_len {
+            return Err(format!(
+                "Not enough capacity to embed the secret: embedded {}/{} payload bits",
+                embedded, payload_len
+            ));
+        }
+
+        host.copy_from_slice(&image);
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the geometry does not match the host, or if the host does not
+    /// contain data embedded by prediction-error expansion.
+    fn restore_cover(&self, host: &[u8]) -> Result<Restored, String> {
+        self.check(host.len())?;
+        let border: Vec<usize> = self.border_indices().collect();
+
+        let mut header_bits = BitWriter::new(BitOrder::MsbFirst);
+        for &idx in &border {
+            header_bits.push(host[idx] & 1);
+        }
+        let header_bytes = header_bits.into_bytes();
+        let mut header = PayloadReader::new(&header_bytes);
+        let t = header.bits(8)? as i32;
+        if t == 0 || t > 127 {
+            return Err(format!("Invalid header: threshold {} is out of range", t));
+        }
+        let predictor = Predictor::from_code(header.bits(2)?)?;
+        let samples: Vec<(usize, usize, usize)> =
+            (0..2).flat_map(|pass| self.pass_indices(pass)).collect();
+        let excluded = read_location_map(&mut header, samples.len())?;
+        let header_len = 10 + {
+            // Length of the location map, re-encoded to know where the header ends
+            let mut writer = PayloadWriter::new();
+            write_location_map(&mut writer, &excluded);
+            writer.len()
+        };
+
+        // Undo the second pass, then the first one, in raster order
+        let mut image = host.to_vec();
+        let first_pass_len = self.pass_indices(0).count();
+        let mut pass_bits = [Vec::new(), Vec::new()];
+        for pass in (0..2).rev() {
+            let range = if pass == 0 {
+                0..first_pass_len
+            } else {
+                first_pass_len..samples.len()
+            };
+            for i in range {
+                if excluded[i] {
+                    continue;
+                }
+                let (x, y, c) = samples[i];
+                let idx = self.geometry.index(x, y, c);
+                let p = self.predict(&image, predictor, x, y, c);
+                let e = image[idx] as i32 - p;
+                let original_e = if (-2 * t..2 * t).contains(&e) {
+                    pass_bits[pass].push((e & 1) as u8);
+                    e >> 1
+                } else if e >= 2 * t {
+                    e - t
+                } else {
+                    e + t
+                };
+                let original = p + original_e;
+                if !(0..=255).contains(&original) {
+                    return Err("Invalid data: restored sample out of range".into());
+                }
+                image[idx] = original as u8;
+            }
+        }
+
+        let mut bits = BitWriter::new(BitOrder::MsbFirst);
+        for &bit in pass_bits[0].iter().chain(&pass_bits[1]) {
+            bits.push(bit);
+        }
+        let bits = bits.into_bytes();
+        let mut payload = PayloadReader::new(&bits);
+        for &idx in &border[..header_len] {
+            image[idx] = (image[idx] & !1) | payload.bit()?;
+        }
+        let secret_len = payload.u32()? as usize;
+        let secret = payload.bytes(secret_len)?;
+        Ok(Restored {
+            cover: image,
+            secret,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{TestRng, natural_cover};
+
+    #[test]
+    fn test_round_trip_all_predictors() {
+        let geometry = ImageGeometry::new(64, 64, 1);
+        let cover = natural_cover(64, 64, 17);
+        let secret = TestRng::new(5).bytes(48);
+
+        for predictor in [Predictor::Med, Predictor::Gap, Predictor::Rhombus] {
+            let method = PredictionErrorExpansion {
+                predictor,
+                threshold: 3,
+                ..PredictionErrorExpansion::new(geometry)
+            };
+            let mut stego = cover.clone();
+            method.embed(&mut stego, &secret).unwrap();
+            assert!(stego.iter().zip(&cover).all(|(&a, &b)| a.abs_diff(b) <= 3));
+
+            let restored = method.restore_cover(&stego).unwrap();
+            assert_eq!(restored.secret, secret, "{:?}", predictor);
+            assert_eq!(restored.cover, cover, "{:?}", predictor);
+        }
+    }
+
+    #[test]
+    fn test_overflow_samples_use_location_map() {
+        // Saturated areas in an RGB image
+        let geometry = ImageGeometry::new(32, 32, 3);
+        let mut cover = natural_cover(96, 32, 3);
+        cover[300..420].fill(255);
+        cover[1500..1600].fill(0);
+        let method = PredictionErrorExpansion {
+            threshold: 4,
+            ..PredictionErrorExpansion::new(geometry)
+        };
+
+        let mut stego = cover.clone();
+        method.embed(&mut stego, b"saturated").unwrap();
+        // Saturated samples are excluded, only the border LSBs (header) may change
+        for (i, &v) in stego.iter().enumerate().take(420).skip(300) {
+            if (BORDER..32 - BORDER).contains(&(i / 3 % 32)) {
+                assert_eq!(v, 255);
+            }
+        }
+
+        let restored = method.restore_cover(&stego).unwrap();
+        assert_eq!(restored.secret, b"saturated");
+        assert_eq!(restored.cover, cover);
+    }
+
+    #[test]
+    fn test_capacity_and_errors() {
+        let geometry = ImageGeometry::new(32, 32, 1);
+        let cover = natural_cover(32, 32, 9);
+        let method = PredictionErrorExpansion::new(geometry);
+
+        let capacity = method.capacity(&cover);
+        assert!(capacity > 0);
+        let mut stego = cover.clone();
+        let res = method.embed(&mut stego, &vec![0u8; capacity / 8 + 16]);
+        assert!(res.unwrap_err().starts_with("Not enough capacity"));
+        assert_eq!(stego, cover);
+
+        let small = PredictionErrorExpansion::new(ImageGeometry::new(4, 4, 1));
+        assert!(small.embed(&mut [0u8; 16], b"x").is_err());
+        let wrong_len = method.embed(&mut [0u8; 10], b"x");
+        assert!(wrong_len.unwrap_err().starts_with("Buffer length 10"));
+    }
+}
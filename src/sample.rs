@@ -0,0 +1,93 @@
+/// A host sample that bitplane operations can read and write.
+///
+/// A `Sample` is a fixed-width integer (8-bit pixel, 16-bit PNG channel, 32-bit audio
+/// sample, ...). Bitplane strategies work on the raw two's complement bit pattern of the
+/// sample, so signed samples are handled exactly like unsigned ones of the same width.
+/// The width is the one of the integer type: the MSB strategy writes the top bits of the
+/// container, which are padding for data stored in a wider type (such as 24-bit audio in
+/// an `i32`).
+///
+/// # Example
+///
+/// ```rust
+/// use stegano_rs::sample::Sample;
+///
+/// assert_eq!(<u16 as Sample>::BITS, 16);
+/// assert_eq!((-1i16).to_raw(), 0xFFFF);
+/// assert_eq!(i16::from_raw(0xFFFE), -2);
+/// ```
+pub trait Sample: Copy + Default + PartialEq + core::fmt::Debug {
+    /// Width of the sample in bits.
+    const BITS: u8;
+
+    /// Returns the raw bit pattern of the sample, zero-extended to 64 bits.
+    fn to_raw(self) -> u64;
+
+    /// Builds a sample from a raw bit pattern. Bits above `BITS` are ignored.
+    fn from_raw(raw: u64) -> Self;
+
+    /// Returns the numeric value of the sample (signed samples keep their sign).
+    fn to_i128(self) -> i128;
+
+    /// Returns a mask with the `bits` lowest bits set (all bits when `bits >= 64`).
+    fn low_mask(bits: u8) -> u64 {
+        if bits >= 64 {
+            u64::MAX
+        } else {
+            (1u64 << bits) - 1
+        }
+    }
+}
+
+macro_rules! impl_sample {
+    ($($t:ty => $unsigned:ty),* $(,)?) => {
+        $(
+            impl Sample for $t {
+                const BITS: u8 = <$t>::BITS as u8;
+
+                fn to_raw(self) -> u64 {
+                    self as $unsigned as u64
+                }
+
+                fn from_raw(raw: u64) -> Self {
+                    raw as $unsigned as $t
+                }
+
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+            }
+        )*
+    };
+}
+
+impl_sample!(
+    u8 => u8,
+    u16 => u16,
+    u32 => u32,
+    u64 => u64,
+    i8 => u8,
+    i16 => u16,
+    i32 => u32,
+    i64 => u64,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_round_trip() {
+        assert_eq!(u8::from_raw(0xAB), 0xAB);
+        assert_eq!(u16::from_raw(0x1_2345), 0x2345); // truncated to 16 bits
+        assert_eq!((-3i32).to_raw(), 0xFFFF_FFFD);
+        assert_eq!(i32::from_raw(0xFFFF_FFFD), -3);
+        assert_eq!(i8::from_raw((-128i8).to_raw()), -128);
+    }
+
+    #[test]
+    fn test_low_mask() {
+        assert_eq!(u8::low_mask(3), 0b111);
+        assert_eq!(u64::low_mask(64), u64::MAX);
+    }
+}
@@ -0,0 +1,32 @@
+//! Steganalysis tools to evaluate how detectable the output of the embedding methods is.
+//!
+//! These detectors are meant to test our own stego output: run them on a cover and on the
+//! corresponding stego buffer and compare the results.
+//!
+//! Available detectors:
+//! - [`chi_square`]: Westfeld–Pfitzmann chi-square attack on pairs of values, for sequential
+//!   LSB replacement.
+//! - [`rs_analysis`]: RS analysis (Fridrich, Goljan and Du), estimates the LSB message length.
+//! - [`sample_pair`]: Sample Pair Analysis (Dumitrescu, Wu and Wang), estimates the LSB
+//!   message length.
+//! - [`pvd_histogram`]: detects the steps left by `pvd_embed` in the histogram of pixel
+//!   differences.
+
+pub mod chi_square;
+pub mod pvd_histogram;
+pub mod rs_analysis;
+pub mod sample_pair;
+
+mod stats;
+
+/// Estimated relative length of an LSB message.
+///
+/// A value of 0 means no message, and 1 means that every sample carries one message bit.
+/// Estimates are statistical: they can be slightly negative on covers or slightly above 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LsbLengthEstimate {
+    /// Estimate over all channels.
+    pub message_length: f64,
+    /// Estimate for each channel.
+    pub per_channel: Vec<f64>,
+}
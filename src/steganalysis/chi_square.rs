@@ -0,0 +1,243 @@
+use crate::embedding_locator::EmbeddingLocator;
+
+use super::stats::chi_square_cdf;
+
+/// Options for the chi-square attack.
+///
+/// # Fields
+///
+/// - `step`:  
+///   Number of samples added to the scanned prefix between two points of the curve.
+///   Must be greater than 0.
+///
+/// - `min_pair_count`:  
+///   Minimum number of samples a pair of values `(2k, 2k + 1)` must contain to be taken
+///   into account. Pairs with very few samples make the chi-square statistic unreliable.
+#[derive(Debug, Clone, Copy)]
+pub struct ChiSquareOptions {
+    /// Number of samples between two points of the probability curve.
+    pub step: usize,
+    /// Minimum number of samples in a pair of values for it to be used.
+    pub min_pair_count: usize,
+}
+
+impl Default for ChiSquareOptions {
+    /// Returns `ChiSquareOptions` with:
+    /// - `step` = 1024,
+    /// - `min_pair_count` = 10 (an expected frequency of at least 5 per value).
+    fn default() -> Self {
+        Self {
+            step: 1024,
+            min_pair_count: 10,
+        }
+    }
+}
+
+/// One point of the chi-square probability curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiSquarePoint {
+    /// Number of samples of the scanned prefix.
+    pub samples: usize,
+    /// Probability that the prefix contains LSB-embedded data, between 0 and 1.
+    pub probability: f64,
+}
+
+/// Runs the Westfeld–Pfitzmann chi-square attack on a host buffer.
+///
+/// LSB replacement only swaps a value `2k` with `2k + 1` and the other way round, so a
+/// fully embedded region tends to have the same number of samples in both values of each
+/// pair (PoV). The attack measures how close the histogram of the scanned samples is to
+/// this equalized histogram.
+///
+/// The samples are scanned in the order returned by `locator`, and the embedding
+/// probability is computed every `options.step` samples over the whole prefix scanned so
+/// far. Sequential embedding (e.g. `LinearTraversal` with `embed_lsb`) shows up as a
+/// probability close to 1 over the embedded prefix, followed by a sharp drop where the
+/// message ends.
+///
+/// # Arguments
+/// - `host`: The buffer to analyze.
+/// - `locator`: The order in which the samples are scanned. Indices outside the host are ignored.
+/// - `options`: The curve resolution and the minimum size of a pair of values.
+///
+/// # Returns
+/// The probability curve, one point per `options.step` samples (plus one point for the
+/// final partial step), or an error string if `options.step` is 0.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::embedding_locator::LinearTraversal;
+/// use stegano_rs::steganalysis::chi_square::{chi_square_attack, ChiSquareOptions};
+///
+/// // A histogram where the values of each pair are equally frequent looks embedded
+/// let host: Vec<u8> = (0..4096).map(|i| (i % 64) as u8).collect();
+/// let options = ChiSquareOptions { step: 1024, ..ChiSquareOptions::default() };
+/// let curve = chi_square_attack(&host, &LinearTraversal, &options).unwrap();
+///
+/// assert_eq!(curve.len(), 4);
+/// assert!(curve.iter().all(|point| point.probability > 0.99));
+/// ```
+pub fn chi_square_attack<L: EmbeddingLocator + ?Sized>(
+    host: &[u8],
+    locator: &L,
+    options: &ChiSquareOptions,
+) -> Result<Vec<ChiSquarePoint>, String> {
+    if options.step == 0 {
+        return Err("options.step must be greater than 0".into());
+    }
+
+    let mut histogram = [0usize; 256];
+    let mut curve = Vec::new();
+    let mut samples = 0;
+
+    for idx in locator.iter_indices(host.len()) {
+        if idx >= host.len() {
+            continue; // Skip invalid indices
+        }
+
+        histogram[host[idx] as usize] += 1;
+        samples += 1;
+
+        if samples % options.step == 0 {
+            curve.push(ChiSquarePoint {
+                samples,
+                probability: embedding_probability(&histogram, options.min_pair_count),
+            });
+        }
+    }
+
+    // Last partial step
+    if samples % options.step != 0 {
+        curve.push(ChiSquarePoint {
+            samples,
+            probability: embedding_probability(&histogram, options.min_pair_count),
+        });
+    }
+
+    Ok(curve)
+}
+
+/// Computes the probability of embedding for a histogram of 8-bit values.
+///
+/// Returns 0 when fewer than two pairs of values have enough samples to run the test.
+pub fn embedding_probability(histogram: &[usize; 256], min_pair_count: usize) -> f64 {
+    let mut statistic = 0.0;
+    let mut categories = 0;
+
+    for pair in histogram.chunks_exact(2) {
+        let total = pair[0] + pair[1];
+        if total == 0 || total < min_pair_count {
+            continue;
+        }
+
+        // Under embedding, both values of the pair are expected to be equally frequent
+        let expected = total as f64 / 2.0;
+        let observed = pair[0] as f64;
+        statistic += (observed - expected).powi(2) / expected;
+        categories += 1;
+    }
+
+    if categories < 2 {
+        return 0.0;
+    }
+
+    1.0 - chi_square_cdf(statistic, categories - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitplane::{BitplaneOptions, bitplane_embed_with_locator};
+    use crate::embedding_locator::LinearTraversal;
+    use crate::test_utils::{TestRng, natural_cover};
+
+    const WIDTH: usize = 256;
+    const HEIGHT: usize = 128;
+
+    #[test]
+    fn test_cover_is_not_detected() {
+        let cover = natural_cover(WIDTH, HEIGHT, 7);
+        let curve =
+            chi_square_attack(&cover, &LinearTraversal, &ChiSquareOptions::default()).unwrap();
+
+        assert_eq!(curve.len(), WIDTH * HEIGHT / 1024);
+        assert!(
+            curve.iter().all(|point| point.probability < 0.05),
+            "{:?}",
+            curve
+        );
+    }
+
+    #[test]
+    fn test_sequential_lsb_embedding_is_detected() {
+        let mut host = natural_cover(WIDTH, HEIGHT, 7);
+        let mut rng = TestRng::new(42);
+
+        // Random message filling the first half of the host
+        let secret = rng.bytes(host.len() / 2 / 8);
+        bitplane_embed_with_locator(
+            &mut host,
+            &secret,
+            &BitplaneOptions::default(),
+            &LinearTraversal,
+        )
+        .unwrap();
+
+        let curve =
+            chi_square_attack(&host, &LinearTraversal, &ChiSquareOptions::default()).unwrap();
+        let half = curve.len() / 2;
+
+        // Embedded prefix is flagged, and the probability drops once the message ends
+        assert!(
+            curve[..half].iter().all(|point| point.probability > 0.9),
+            "{:?}",
+            curve
+        );
+        assert!(
+            curve[half + 2..]
+                .iter()
+                .all(|point| point.probability < 0.05),
+            "{:?}",
+            curve
+        );
+    }
+
+    #[test]
+    fn test_keyed_lsb_matching_is_not_detected() {
+        let mut host = natural_cover(WIDTH, HEIGHT, 7);
+        let mut rng = TestRng::new(42);
+
+        // LSB matching (+-1 on mismatch) at key-permuted positions, over half the host
+        let positions = rng.permutation(host.len());
+        for &idx in &positions[..host.len() / 2] {
+            let bit = (rng.next_u64() & 1) as u8;
+            if host[idx] & 1 != bit {
+                host[idx] = match host[idx] {
+                    0 => 1,
+                    255 => 254,
+                    value if rng.next_u64() & 1 == 0 => value - 1,
+                    value => value + 1,
+                };
+            }
+        }
+
+        // Without the key, the attacker scans the host sequentially
+        let curve =
+            chi_square_attack(&host, &LinearTraversal, &ChiSquareOptions::default()).unwrap();
+        assert!(
+            curve.iter().all(|point| point.probability < 0.05),
+            "{:?}",
+            curve
+        );
+    }
+
+    #[test]
+    fn test_zero_step_error() {
+        let options = ChiSquareOptions {
+            step: 0,
+            ..ChiSquareOptions::default()
+        };
+        let res = chi_square_attack(&[0u8; 16], &LinearTraversal, &options);
+        assert_eq!(res.unwrap_err(), "options.step must be greater than 0");
+    }
+}
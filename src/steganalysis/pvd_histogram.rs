@@ -0,0 +1,219 @@
+use crate::embedding_locator::EmbeddingLocator;
+use crate::pvd::PvdOptions;
+
+/// Number of histogram values averaged on each side of a bin boundary.
+const EDGE_WIDTH: i32 = 2;
+/// Number of consecutive log-slopes used on each side of a boundary to model the cover.
+const SLOPE_WIDTH: i32 = 3;
+/// Minimum number of pairs around a boundary for it to be scored.
+const MIN_BOUNDARY_COUNT: f64 = 20.0;
+
+/// Step of the difference histogram at the boundary between two adjacent PVD bins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundaryStep {
+    /// Last difference of the lower bin (the upper bin starts at `boundary + 1`).
+    pub boundary: i32,
+    /// Log-ratio between the end of the lower bin and the start of the upper bin.
+    pub step: f64,
+    /// Mean log-slope of the histogram inside the two bins, next to the boundary.
+    pub interior_slope: f64,
+    /// How far the step departs from the smooth cover model: `|step - interior_slope|`.
+    pub excess: f64,
+}
+
+/// Result of the PVD histogram analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PvdHistogramReport {
+    /// Number of pairs for each absolute difference `0..=255`.
+    pub histogram: Vec<usize>,
+    /// Scored boundaries between adjacent bins, in increasing order.
+    pub boundaries: Vec<BoundaryStep>,
+    /// Mean excess over the scored boundaries. Higher means more detectable.
+    pub score: f64,
+}
+
+/// Computes the histogram of absolute pixel differences of the pairs formed by `locator`.
+///
+/// Pairs are formed two by two from the locator indices, exactly like in `pvd_embed`.
+pub fn difference_histogram<L: EmbeddingLocator + ?Sized>(host: &[u8], locator: &L) -> Vec<usize> {
+    let mut histogram = vec![0usize; 256];
+    let mut indices = locator
+        .iter_indices(host.len())
+        .filter(|&idx| idx < host.len());
+
+    while let (Some(idx1), Some(idx2)) = (indices.next(), indices.next()) {
+        histogram[(host[idx1] as i32 - host[idx2] as i32).unsigned_abs() as usize] += 1;
+    }
+
+    histogram
+}
+
+/// Scores how much the pixel difference histogram departs from a smooth cover.
+///
+/// `pvd_embed` replaces the difference of each pair by `min_bin + secret`, which makes the
+/// histogram flat inside each bin. Since natural images have a smoothly decreasing
+/// difference histogram, this leaves a visible step at every bin boundary.
+///
+/// The cover model assumes that the histogram is smooth across boundaries: the log-ratio
+/// between the last values of a bin and the first values of the next one should be close
+/// to the local log-slope inside the two bins. The `excess` of a boundary is the distance
+/// between both, and the score is the mean excess over the boundaries that have enough
+/// pairs around them. Comparing the score of a cover and of its stego version helps tuning
+/// bin presets against detectability.
+///
+/// # Arguments
+/// - `host`: The buffer to analyze.
+/// - `options`: The PVD bin table to test.
+/// - `locator`: The locator used to form the pairs.
+///
+/// # Returns
+/// The histogram, the scored boundaries and the overall score, or an error string if the
+/// bin table is empty.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::embedding_locator::LinearTraversal;
+/// use stegano_rs::pvd::PvdOptions;
+/// use stegano_rs::steganalysis::pvd_histogram::pvd_histogram_analysis;
+///
+/// let host: Vec<u8> = (0..4096).map(|i| ((i * 37) % 251) as u8).collect();
+/// let report = pvd_histogram_analysis(&host, &PvdOptions::default(), &LinearTraversal).unwrap();
+/// assert_eq!(report.histogram.iter().sum::<usize>(), 2048);
+/// ```
+pub fn pvd_histogram_analysis<L: EmbeddingLocator + ?Sized>(
+    host: &[u8],
+    options: &PvdOptions,
+    locator: &L,
+) -> Result<PvdHistogramReport, String> {
+    let histogram = difference_histogram(host, locator);
+    analyze_difference_histogram(histogram, options)
+}
+
+/// Scores an already computed difference histogram against a PVD bin table.
+///
+/// See [`pvd_histogram_analysis`].
+pub fn analyze_difference_histogram(
+    histogram: Vec<usize>,
+    options: &PvdOptions,
+) -> Result<PvdHistogramReport, String> {
+    if options.bins.is_empty() {
+        return Err("options.bins cannot be empty".into());
+    }
+    if histogram.len() != 256 {
+        return Err("histogram must contain 256 values".into());
+    }
+
+    let mut bins = options.bins.clone();
+    bins.sort_unstable();
+
+    let count = |value: i32| histogram[value.clamp(0, 255) as usize] as f64;
+    let log_slope = |value: i32| ((count(value) + 1.0) / (count(value + 1) + 1.0)).ln();
+
+    let mut boundaries = Vec::new();
+    for pair in bins.windows(2) {
+        let ((low_min, low_max), (high_min, high_max)) = (pair[0], pair[1]);
+        // Only adjacent bins inside the histogram range have a boundary
+        if low_max + 1 != high_min || low_min < 0 || high_max > 255 {
+            continue;
+        }
+
+        let low_edge = EDGE_WIDTH.min(low_max - low_min + 1);
+        let high_edge = EDGE_WIDTH.min(high_max - high_min + 1);
+        let left = (low_max - low_edge + 1..=low_max).map(count).sum::<f64>() / low_edge as f64;
+        let right = (high_min..high_min + high_edge).map(count).sum::<f64>() / high_edge as f64;
+        if left + right < MIN_BOUNDARY_COUNT {
+            continue;
+        }
+
+        let slopes: Vec<f64> = ((low_max - SLOPE_WIDTH).max(low_min)..low_max)
+            .chain(high_min..(high_min + SLOPE_WIDTH).min(high_max))
+            .map(log_slope)
+            .collect();
+        let interior_slope = if slopes.is_empty() {
+            0.0
+        } else {
+            slopes.iter().sum::<f64>() / slopes.len() as f64
+        };
+
+        let step = ((left + 1.0) / (right + 1.0)).ln();
+        boundaries.push(BoundaryStep {
+            boundary: low_max,
+            step,
+            interior_slope,
+            excess: (step - interior_slope).abs(),
+        });
+    }
+
+    let score = if boundaries.is_empty() {
+        0.0
+    } else {
+        boundaries.iter().map(|b| b.excess).sum::<f64>() / boundaries.len() as f64
+    };
+
+    Ok(PvdHistogramReport {
+        histogram,
+        boundaries,
+        score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding_locator::LinearTraversal;
+    use crate::pvd::{pvd_capacity_iter, pvd_embed_with_locator};
+    use crate::test_utils::{TestRng, textured_cover};
+
+    fn score_at_rate(rate: f64, options: &PvdOptions) -> f64 {
+        let mut host = textured_cover(256, 256, 9);
+        let capacity = pvd_capacity_iter(&host, options, 0..host.len());
+        let secret = TestRng::new(1).bytes((capacity as f64 * rate / 8.0) as usize);
+        pvd_embed_with_locator(&mut host, &secret, options, &LinearTraversal).unwrap();
+
+        pvd_histogram_analysis(&host, options, &LinearTraversal)
+            .unwrap()
+            .score
+    }
+
+    #[test]
+    fn test_score_increases_with_payload() {
+        let options = PvdOptions::default();
+        let scores: Vec<f64> = [0.0, 0.5, 1.0]
+            .iter()
+            .map(|&rate| score_at_rate(rate, &options))
+            .collect();
+
+        assert!(scores[0] < 0.2, "{:?}", scores);
+        assert!(scores[1] > scores[0] * 2.0, "{:?}", scores);
+        assert!(scores[2] > 0.6, "{:?}", scores);
+    }
+
+    #[test]
+    fn test_embedded_histogram_is_flat_inside_bins() {
+        let options = PvdOptions::default();
+        let mut host = textured_cover(256, 256, 9);
+        let capacity = pvd_capacity_iter(&host, &options, 0..host.len());
+        let secret = TestRng::new(1).bytes(capacity / 8);
+        pvd_embed_with_locator(&mut host, &secret, &options, &LinearTraversal).unwrap();
+
+        let report = pvd_histogram_analysis(&host, &options, &LinearTraversal).unwrap();
+        for boundary in &report.boundaries {
+            assert!(boundary.interior_slope.abs() < 0.1, "{:?}", boundary);
+        }
+    }
+
+    #[test]
+    fn test_difference_histogram_ignores_incomplete_pair() {
+        let histogram = difference_histogram(&[10u8, 14, 200, 190, 7], &LinearTraversal);
+        assert_eq!(histogram[4], 1);
+        assert_eq!(histogram[10], 1);
+        assert_eq!(histogram.iter().sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn test_empty_bins_error() {
+        let options = PvdOptions { bins: vec![], ..PvdOptions::default() };
+        let res = pvd_histogram_analysis(&[0u8; 4], &options, &LinearTraversal);
+        assert_eq!(res.unwrap_err(), "options.bins cannot be empty");
+    }
+}
@@ -0,0 +1,358 @@
+use crate::geometry::ImageGeometry;
+
+use super::LsbLengthEstimate;
+
+/// Options for RS analysis.
+///
+/// # Fields
+///
+/// - `mask`:  
+///   The flipping mask applied to each group of horizontally adjacent samples. Each entry
+///   must be `1` (apply `F1`: `2k <-> 2k + 1`), `-1` (apply `F-1`: `2k - 1 <-> 2k`) or `0`
+///   (leave the sample unchanged). The group size is the length of the mask.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsOptions {
+    /// Flipping mask, one entry in `{-1, 0, 1}` per sample of a group.
+    pub mask: Vec<i8>,
+}
+
+impl Default for RsOptions {
+    /// Returns `RsOptions` with the mask `[0, 1, 1, 0]` recommended by Fridrich et al.
+    fn default() -> Self {
+        Self {
+            mask: vec![0, 1, 1, 0],
+        }
+    }
+}
+
+/// Relative number of regular and singular groups for one mask.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RsCounts {
+    /// Relative number of regular groups (flipping increases the noise).
+    pub regular: f64,
+    /// Relative number of singular groups (flipping decreases the noise).
+    pub singular: f64,
+}
+
+/// Group statistics measured by RS analysis, on the analyzed buffer and on the same
+/// buffer with all LSBs flipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RsStatistics {
+    /// `R_M` and `S_M` on the analyzed buffer.
+    pub positive: RsCounts,
+    /// `R_-M` and `S_-M` on the analyzed buffer.
+    pub negative: RsCounts,
+    /// `R_M` and `S_M` once all LSBs are flipped.
+    pub flipped_positive: RsCounts,
+    /// `R_-M` and `S_-M` once all LSBs are flipped.
+    pub flipped_negative: RsCounts,
+}
+
+impl RsStatistics {
+    /// Estimates the relative message length from the group statistics.
+    ///
+    /// Solves the quadratic equation of Fridrich, Goljan and Du for the root with the
+    /// smallest absolute value.
+    pub fn message_length(&self) -> f64 {
+        let d0 = self.positive.regular - self.positive.singular;
+        let d1 = self.flipped_positive.regular - self.flipped_positive.singular;
+        let dn0 = self.negative.regular - self.negative.singular;
+        let dn1 = self.flipped_negative.regular - self.flipped_negative.singular;
+
+        let a = 2.0 * (d1 + d0);
+        let b = dn0 - dn1 - d1 - 3.0 * d0;
+        let c = d0 - dn0;
+
+        let z = if a.abs() < f64::EPSILON {
+            if b.abs() < f64::EPSILON {
+                return 0.0;
+            }
+            -c / b
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                -b / (2.0 * a)
+            } else {
+                let root1 = (-b + discriminant.sqrt()) / (2.0 * a);
+                let root2 = (-b - discriminant.sqrt()) / (2.0 * a);
+                if root1.abs() < root2.abs() {
+                    root1
+                } else {
+                    root2
+                }
+            }
+        };
+
+        z / (z - 0.5)
+    }
+}
+
+/// Estimates the relative LSB message length with RS analysis (Fridrich, Goljan and Du).
+///
+/// Each channel is split into groups of `options.mask.len()` horizontally adjacent samples.
+/// A group is regular when flipping its LSBs with the mask makes it noisier (sum of
+/// absolute differences between neighbours), and singular when it makes it smoother.
+/// Random LSB embedding pushes the relative numbers of regular and singular groups
+/// together for the mask `M`, while they move apart for `-M`; the message length is
+/// recovered from these curves.
+///
+/// The estimate is the relative length of a message embedded with one bit per sample at
+/// randomly scattered positions: 0 for a cover, 1 when every LSB carries a message bit.
+/// It is a statistical estimate and is typically within a few percent on natural images.
+///
+/// # Arguments
+/// - `host`: The 8-bit buffer to analyze.
+/// - `geometry`: The layout of the buffer.
+/// - `options`: The flipping mask.
+///
+/// # Returns
+/// The estimate over all channels and per channel, or an error string if the geometry
+/// does not match the buffer or the mask is invalid.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+/// use stegano_rs::steganalysis::rs_analysis::{rs_analysis, RsOptions};
+///
+/// let geometry = ImageGeometry::new(64, 64, 1);
+/// let host: Vec<u8> = (0..64 * 64).map(|i| ((i % 64) * 2 + (i / 64) % 7) as u8).collect();
+/// let estimate = rs_analysis(&host, &geometry, &RsOptions::default()).unwrap();
+/// assert_eq!(estimate.per_channel.len(), 1);
+/// ```
+pub fn rs_analysis(
+    host: &[u8],
+    geometry: &ImageGeometry,
+    options: &RsOptions,
+) -> Result<LsbLengthEstimate, String> {
+    let statistics = rs_statistics(host, geometry, options)?;
+
+    let per_channel: Vec<f64> = statistics
+        .per_channel
+        .iter()
+        .map(|s| s.message_length())
+        .collect();
+    Ok(LsbLengthEstimate {
+        message_length: statistics.total.message_length(),
+        per_channel,
+    })
+}
+
+/// Group statistics of RS analysis, over all channels and per channel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RsReport {
+    /// Statistics over all channels.
+    pub total: RsStatistics,
+    /// Statistics of each channel.
+    pub per_channel: Vec<RsStatistics>,
+}
+
+/// Computes the regular and singular group statistics used by [`rs_analysis`].
+///
+/// This is useful to plot the RS diagram or to inspect how a method affects the groups.
+pub fn rs_statistics(
+    host: &[u8],
+    geometry: &ImageGeometry,
+    options: &RsOptions,
+) -> Result<RsReport, String> {
+    geometry.check(host.len())?;
+    validate_mask(&options.mask, geometry)?;
+
+    let negative_mask: Vec<i8> = options.mask.iter().map(|m| -m).collect();
+    let group_size = options.mask.len();
+
+    let mut total = [GroupCounter::default(); 4];
+    let mut per_channel = Vec::with_capacity(geometry.channels);
+
+    for channel in 0..geometry.channels {
+        let mut counters = [GroupCounter::default(); 4];
+        let mut group = vec![0i32; group_size];
+
+        for y in 0..geometry.height {
+            for start in (0..=geometry.width - group_size).step_by(group_size) {
+                for (i, value) in group.iter_mut().enumerate() {
+                    *value = host[geometry.index(start + i, y, channel)] as i32;
+                }
+
+                counters[0].add(&group, &options.mask, false);
+                counters[1].add(&group, &negative_mask, false);
+                counters[2].add(&group, &options.mask, true);
+                counters[3].add(&group, &negative_mask, true);
+            }
+        }
+
+        for (sum, counter) in total.iter_mut().zip(counters.iter()) {
+            sum.merge(counter);
+        }
+        per_channel.push(statistics_from(&counters));
+    }
+
+    Ok(RsReport {
+        total: statistics_from(&total),
+        per_channel,
+    })
+}
+
+/// Counts regular, singular and total groups for one mask.
+#[derive(Debug, Clone, Copy, Default)]
+struct GroupCounter {
+    regular: usize,
+    singular: usize,
+    groups: usize,
+}
+
+impl GroupCounter {
+    /// Classifies `group` (with all LSBs flipped first if `flip_all`) under `mask`.
+    fn add(&mut self, group: &[i32], mask: &[i8], flip_all: bool) {
+        let mut before = 0;
+        let mut after = 0;
+        let mut previous: Option<(i32, i32)> = None;
+
+        for (&value, &m) in group.iter().zip(mask) {
+            let value = if flip_all { value ^ 1 } else { value };
+            let flipped = flip(value, m);
+            if let Some((prev_value, prev_flipped)) = previous {
+                before += (value - prev_value).abs();
+                after += (flipped - prev_flipped).abs();
+            }
+            previous = Some((value, flipped));
+        }
+
+        self.groups += 1;
+        if after > before {
+            self.regular += 1;
+        } else if after < before {
+            self.singular += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &GroupCounter) {
+        self.regular += other.regular;
+        self.singular += other.singular;
+        self.groups += other.groups;
+    }
+
+    fn counts(&self) -> RsCounts {
+        if self.groups == 0 {
+            return RsCounts::default();
+        }
+        RsCounts {
+            regular: self.regular as f64 / self.groups as f64,
+            singular: self.singular as f64 / self.groups as f64,
+        }
+    }
+}
+
+/// Applies the flipping function `F1`, `F-1` or `F0` to a sample value.
+fn flip(value: i32, mask: i8) -> i32 {
+    match mask {
+        1 => value ^ 1,
+        -1 => ((value + 1) ^ 1) - 1,
+        _ => value,
+    }
+}
+
+fn statistics_from(counters: &[GroupCounter; 4]) -> RsStatistics {
+    RsStatistics {
+        positive: counters[0].counts(),
+        negative: counters[1].counts(),
+        flipped_positive: counters[2].counts(),
+        flipped_negative: counters[3].counts(),
+    }
+}
+
+fn validate_mask(mask: &[i8], geometry: &ImageGeometry) -> Result<(), String> {
+    if mask.len() < 2 {
+        return Err("options.mask must contain at least 2 entries".into());
+    }
+    if mask.iter().any(|m| !(-1..=1).contains(m)) {
+        return Err("options.mask entries must be -1, 0 or 1".into());
+    }
+    if mask.iter().all(|&m| m == 0) {
+        return Err("options.mask must contain at least one non-zero entry".into());
+    }
+    if mask.len() > geometry.width {
+        return Err(format!(
+            "options.mask length {} is larger than the image width {}",
+            mask.len(),
+            geometry.width
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitplane::{BitplaneOptions, bitplane_embed_with_locator};
+    use crate::embedding_locator::PositionListTraversal;
+    use crate::test_utils::{TestRng, natural_cover};
+
+    const WIDTH: usize = 256;
+    const HEIGHT: usize = 256;
+
+    /// Embeds a random message filling `rate` of the LSB capacity at random positions.
+    fn stego_at_rate(rate: f64) -> Vec<u8> {
+        let mut host = natural_cover(WIDTH, HEIGHT, 3);
+        let mut rng = TestRng::new(11);
+        let positions = rng.permutation(host.len());
+        let secret = rng.bytes((host.len() as f64 * rate / 8.0) as usize);
+
+        let locator = PositionListTraversal {
+            positions: &positions,
+        };
+        bitplane_embed_with_locator(&mut host, &secret, &BitplaneOptions::default(), &locator)
+            .unwrap();
+        host
+    }
+
+    #[test]
+    fn test_rs_analysis_estimates_embedding_rate() {
+        let geometry = ImageGeometry::new(WIDTH, HEIGHT, 1);
+        for rate in [0.0, 0.25, 0.5, 1.0] {
+            let stego = stego_at_rate(rate);
+            let estimate = rs_analysis(&stego, &geometry, &RsOptions::default()).unwrap();
+            assert!(
+                (estimate.message_length - rate).abs() < 0.12,
+                "rate {}: estimated {}",
+                rate,
+                estimate.message_length
+            );
+        }
+    }
+
+    #[test]
+    fn test_rs_statistics_cover_symmetry() {
+        // On a cover, R_M ~ R_-M and S_M ~ S_-M, with more regular than singular groups
+        let geometry = ImageGeometry::new(WIDTH, HEIGHT, 1);
+        let report = rs_statistics(&stego_at_rate(0.0), &geometry, &RsOptions::default()).unwrap();
+        let stats = report.total;
+        assert!((stats.positive.regular - stats.negative.regular).abs() < 0.03);
+        assert!((stats.positive.singular - stats.negative.singular).abs() < 0.03);
+        assert!(stats.positive.regular > stats.positive.singular);
+    }
+
+    #[test]
+    fn test_rs_analysis_per_channel() {
+        // Interleave a cover and a fully embedded copy as two channels
+        let cover = stego_at_rate(0.0);
+        let stego = stego_at_rate(1.0);
+        let host: Vec<u8> = cover
+            .iter()
+            .zip(&stego)
+            .flat_map(|(&c, &s)| [c, s])
+            .collect();
+
+        let geometry = ImageGeometry::new(WIDTH, HEIGHT, 2);
+        let estimate = rs_analysis(&host, &geometry, &RsOptions::default()).unwrap();
+        assert!(estimate.per_channel[0].abs() < 0.12);
+        assert!((estimate.per_channel[1] - 1.0).abs() < 0.12);
+    }
+
+    #[test]
+    fn test_rs_analysis_invalid_mask() {
+        let geometry = ImageGeometry::new(4, 1, 1);
+        let options = RsOptions { mask: vec![0, 2] };
+        let res = rs_analysis(&[0u8; 4], &geometry, &options);
+        assert_eq!(res.unwrap_err(), "options.mask entries must be -1, 0 or 1");
+    }
+}
@@ -0,0 +1,198 @@
+use crate::geometry::ImageGeometry;
+
+use super::LsbLengthEstimate;
+
+/// Counts of the sample pair sets used by Sample Pair Analysis.
+///
+/// For a pair of neighbouring samples `(u, v)`:
+/// - `x`: `v` even and `u < v`, or `v` odd and `u > v`,
+/// - `y`: `v` even and `u > v`, or `v` odd and `u < v`,
+/// - `z`: `u == v`,
+/// - `w`: pairs of `y` that only differ in their LSB (`u / 2 == v / 2`),
+/// - `pairs`: total number of pairs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SamplePairCounts {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+    pub w: usize,
+    pub pairs: usize,
+}
+
+impl SamplePairCounts {
+    fn add(&mut self, u: u8, v: u8) {
+        self.pairs += 1;
+        if u == v {
+            self.z += 1;
+        } else if (v & 1 == 0) == (u < v) {
+            self.x += 1;
+        } else {
+            self.y += 1;
+            if u / 2 == v / 2 {
+                self.w += 1;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &SamplePairCounts) {
+        self.x += other.x;
+        self.y += other.y;
+        self.z += other.z;
+        self.w += other.w;
+        self.pairs += other.pairs;
+    }
+
+    /// Estimates the relative message length from the pair counts.
+    ///
+    /// Returns the smaller root of `(w + z) / 2 * p^2 + (2x - pairs) * p + y - x = 0`.
+    pub fn message_length(&self) -> f64 {
+        let a = (self.w + self.z) as f64 / 2.0;
+        let b = 2.0 * self.x as f64 - self.pairs as f64;
+        let c = self.y as f64 - self.x as f64;
+
+        if a == 0.0 {
+            if b == 0.0 {
+                return 0.0;
+            }
+            return -c / b;
+        }
+
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return -b / (2.0 * a);
+        }
+        let root1 = (-b + discriminant.sqrt()) / (2.0 * a);
+        let root2 = (-b - discriminant.sqrt()) / (2.0 * a);
+        root1.min(root2)
+    }
+}
+
+/// Estimates the relative LSB message length with Sample Pair Analysis
+/// (Dumitrescu, Wu and Wang).
+///
+/// Every pair of horizontally or vertically adjacent samples of the same channel is
+/// classified into the sets described in [`SamplePairCounts`]. In natural images the sets
+/// `x` and `y` have about the same size; LSB replacement moves pairs between them in a
+/// way that depends on the message length, which is recovered by solving a quadratic
+/// equation.
+///
+/// The estimate is the relative length of a message embedded with one bit per sample at
+/// randomly scattered positions: 0 for a cover, 1 when every LSB carries a message bit.
+///
+/// # Arguments
+/// - `host`: The 8-bit buffer to analyze.
+/// - `geometry`: The layout of the buffer.
+///
+/// # Returns
+/// The estimate over all channels and per channel, or an error string if the geometry
+/// does not match the buffer.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+/// use stegano_rs::steganalysis::sample_pair::sample_pair_analysis;
+///
+/// let geometry = ImageGeometry::new(32, 32, 3);
+/// let host: Vec<u8> = (0..geometry.len()).map(|i| (i / 7 % 200) as u8).collect();
+/// let estimate = sample_pair_analysis(&host, &geometry).unwrap();
+/// assert_eq!(estimate.per_channel.len(), 3);
+/// ```
+pub fn sample_pair_analysis(
+    host: &[u8],
+    geometry: &ImageGeometry,
+) -> Result<LsbLengthEstimate, String> {
+    let counts = sample_pair_counts(host, geometry)?;
+
+    let mut total = SamplePairCounts::default();
+    for channel_counts in &counts {
+        total.merge(channel_counts);
+    }
+
+    Ok(LsbLengthEstimate {
+        message_length: total.message_length(),
+        per_channel: counts.iter().map(|c| c.message_length()).collect(),
+    })
+}
+
+/// Computes the sample pair counts of each channel.
+pub fn sample_pair_counts(
+    host: &[u8],
+    geometry: &ImageGeometry,
+) -> Result<Vec<SamplePairCounts>, String> {
+    geometry.check(host.len())?;
+
+    let mut per_channel = vec![SamplePairCounts::default(); geometry.channels];
+    for (channel, counts) in per_channel.iter_mut().enumerate() {
+        for y in 0..geometry.height {
+            for x in 0..geometry.width {
+                let u = host[geometry.index(x, y, channel)];
+                if x + 1 < geometry.width {
+                    counts.add(u, host[geometry.index(x + 1, y, channel)]);
+                }
+                if y + 1 < geometry.height {
+                    counts.add(u, host[geometry.index(x, y + 1, channel)]);
+                }
+            }
+        }
+    }
+
+    Ok(per_channel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitplane::{BitplaneOptions, bitplane_embed_with_locator};
+    use crate::embedding_locator::PositionListTraversal;
+    use crate::test_utils::{TestRng, natural_cover};
+
+    const WIDTH: usize = 256;
+    const HEIGHT: usize = 256;
+
+    #[test]
+    fn test_sample_pair_analysis_estimates_embedding_rate() {
+        let geometry = ImageGeometry::new(WIDTH, HEIGHT, 1);
+        for rate in [0.0, 0.25, 0.5, 1.0] {
+            let mut host = natural_cover(WIDTH, HEIGHT, 3);
+            let mut rng = TestRng::new(5);
+            let positions = rng.permutation(host.len());
+            let secret = rng.bytes((host.len() as f64 * rate / 8.0) as usize);
+            let locator = PositionListTraversal {
+                positions: &positions,
+            };
+            bitplane_embed_with_locator(&mut host, &secret, &BitplaneOptions::default(), &locator)
+                .unwrap();
+
+            let estimate = sample_pair_analysis(&host, &geometry).unwrap();
+            assert!(
+                (estimate.message_length - rate).abs() < 0.1,
+                "rate {}: estimated {}",
+                rate,
+                estimate.message_length
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_pair_counts() {
+        // Pairs: (2, 3) -> y and w, (3, 3) -> z, (3, 8) -> x
+        let geometry = ImageGeometry::new(4, 1, 1);
+        let counts = sample_pair_counts(&[2, 3, 3, 8], &geometry).unwrap();
+        assert_eq!(
+            counts[0],
+            SamplePairCounts {
+                x: 1,
+                y: 1,
+                z: 1,
+                w: 1,
+                pairs: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_sample_pair_geometry_mismatch() {
+        let geometry = ImageGeometry::new(4, 4, 1);
+        assert!(sample_pair_analysis(&[0u8; 15], &geometry).is_err());
+    }
+}
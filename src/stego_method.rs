@@ -0,0 +1,115 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::embedding_locator::EmbeddingLocator;
+use crate::sample::Sample;
+
+/// Common interface shared by all steganographic methods.
+///
+/// Every method hides a secret into a host buffer along the positions given by an
+/// [`EmbeddingLocator`], and can read it back from the same positions. Implementing this
+/// trait makes a method interchangeable with the built-in ones, so pipelines can work
+/// with a `Box<dyn StegoMethod>` instead of matching on each method.
+///
+/// Built-in implementations:
+/// - [`BitplaneOptions`](crate::bitplane::BitplaneOptions) (any [`Sample`] type)
+/// - [`PvdOptions`](crate::pvd::PvdOptions) (`u8` hosts)
+///
+/// # Example
+///
+/// ```rust
+/// use stegano_rs::bitplane::BitplaneOptions;
+/// use stegano_rs::embedding_locator::{EmbeddingLocator, LinearTraversal};
+/// use stegano_rs::pvd::PvdOptions;
+/// use stegano_rs::stego_method::StegoMethod;
+///
+/// let methods: Vec<Box<dyn StegoMethod>> = vec![
+///     Box::new(BitplaneOptions::default()),
+///     Box::new(PvdOptions::default()),
+/// ];
+///
+/// for method in &methods {
+///     let mut host = vec![50u8, 80, 60, 100, 10, 50, 150, 210, 14, 58, 23, 47, 90, 91, 33, 200];
+///     let locator = LinearTraversal;
+///     assert!(method.capacity(&host, &locator) >= 16);
+///
+///     let bits = method.embed(&mut host, b"Hi", &locator).unwrap();
+///     assert_eq!(bits, 16);
+///     assert!(method.extract(&host, &locator).unwrap().starts_with(b"Hi"));
+/// }
+/// ```
+///
+/// A custom method only has to implement the three functions:
+///
+/// ```rust
+/// use stegano_rs::embedding_locator::EmbeddingLocator;
+/// use stegano_rs::stego_method::StegoMethod;
+///
+/// /// Stores one secret bit per host byte in its parity.
+/// struct Parity;
+///
+/// impl StegoMethod for Parity {
+///     fn capacity(&self, host: &[u8], locator: &dyn EmbeddingLocator) -> usize {
+///         locator.iter_indices(host.len()).filter(|&i| i < host.len()).count()
+///     }
+///
+///     fn embed(
+///         &self,
+///         host: &mut [u8],
+///         secret: &[u8],
+///         locator: &dyn EmbeddingLocator,
+///     ) -> Result<usize, String> {
+///         if self.capacity(host, locator) < secret.len() * 8 {
+///             return Err("Not enough space in host".into());
+///         }
+///         let bits = secret.iter().flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1));
+///         let mut count = 0;
+///         let len = host.len();
+///         let indices = locator.iter_indices(len).filter(move |&i| i < len);
+///         for (idx, bit) in indices.zip(bits) {
+///             host[idx] = (host[idx] & !1) | bit;
+///             count += 1;
+///         }
+///         Ok(count)
+///     }
+///
+///     fn extract(
+///         &self,
+///         host: &[u8],
+///         locator: &dyn EmbeddingLocator,
+///     ) -> Result<Vec<u8>, String> {
+///         let bits: Vec<u8> = locator
+///             .iter_indices(host.len())
+///             .filter(|&i| i < host.len())
+///             .map(|i| host[i] & 1)
+///             .collect();
+///         Ok(bits.chunks_exact(8).map(|c| c.iter().fold(0, |acc, b| (acc << 1) | b)).collect())
+///     }
+/// }
+///
+/// let method: Box<dyn StegoMethod> = Box::new(Parity);
+/// ```
+pub trait StegoMethod<S: Sample = u8> {
+    /// Returns the number of secret bits that can be hidden in `host` at the positions
+    /// returned by `locator`.
+    ///
+    /// For content-adaptive methods such as PVD, the capacity depends on the current
+    /// host values.
+    fn capacity(&self, host: &[S], locator: &dyn EmbeddingLocator) -> usize;
+
+    /// Embeds `secret` into `host` at the positions returned by `locator`.
+    ///
+    /// # Returns
+    /// The number of secret bits embedded on success, or an error string otherwise.
+    fn embed(
+        &self,
+        host: &mut [S],
+        secret: &[u8],
+        locator: &dyn EmbeddingLocator,
+    ) -> Result<usize, String>;
+
+    /// Extracts the hidden bits from `host` at the positions returned by `locator`.
+    ///
+    /// The returned bytes may contain trailing data after the secret, since no end
+    /// marker is stored by the built-in methods.
+    fn extract(&self, host: &[S], locator: &dyn EmbeddingLocator) -> Result<Vec<u8>, String>;
+}
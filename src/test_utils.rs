@@ -0,0 +1,80 @@
+//! Helpers shared by the unit tests: a small deterministic PRNG and synthetic covers.
+// Some helpers are only used by the tests of the `std` modules
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+/// Xorshift64* generator, good enough to produce reproducible test data.
+pub(crate) struct TestRng(u64);
+
+impl TestRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value uniformly distributed in `0..n`.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+
+    /// Returns `0..n` in a random order.
+    pub(crate) fn permutation(&mut self, n: usize) -> Vec<usize> {
+        let mut positions: Vec<usize> = (0..n).collect();
+        for i in (1..n).rev() {
+            positions.swap(i, self.below(i + 1));
+        }
+        positions
+    }
+}
+
+/// Builds a grayscale `width` x `height` cover that behaves like a natural photograph:
+/// smooth content, sensor-like noise, and a contrast stretch that leaves the typical
+/// comb-shaped histogram of processed images.
+pub(crate) fn natural_cover(width: usize, height: usize, seed: u64) -> Vec<u8> {
+    let mut rng = TestRng::new(seed);
+    let mut cover = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (fx, fy) = (x as f64, y as f64);
+            let base = 70.0
+                + 30.0 * (fx / 23.0).sin() * (fy / 31.0).cos()
+                + 20.0 * ((fx + fy) / 57.0).sin()
+                + 0.1 * fx;
+            let noise = rng.below(5) as f64 - 2.0;
+            cover.push(
+                ((base + noise).round() * 1.5 - 20.0)
+                    .round()
+                    .clamp(0.0, 255.0) as u8,
+            );
+        }
+    }
+    cover
+}
+
+/// Builds a grayscale `width` x `height` cover with strong texture: smooth content plus
+/// Laplacian noise, which gives a smooth and wide histogram of pixel differences.
+pub(crate) fn textured_cover(width: usize, height: usize, seed: u64) -> Vec<u8> {
+    let mut rng = TestRng::new(seed);
+    let mut cover = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (fx, fy) = (x as f64, y as f64);
+            let base = 128.0
+                + 50.0 * (fx / 19.0).sin() * (fy / 29.0).cos()
+                + 30.0 * ((fx + 2.0 * fy) / 41.0).sin();
+            let u = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+            let noise = -6.0 * (1.0 - 2.0 * u.abs()).ln() * u.signum();
+            cover.push((base + noise).round().clamp(0.0, 255.0) as u8);
+        }
+    }
+    cover
+}
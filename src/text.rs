@@ -0,0 +1,13 @@
+//! Text steganography: hiding a payload in a UTF-8 cover text.
+//!
+//! Text carriers have no redundant low-order bits: the payload is carried by characters
+//! that are invisible when the text is displayed. The visible text is left unchanged, so
+//! the extractor only needs the stego text.
+//!
+//! Available methods:
+//! - [`zero_width`]: zero-width Unicode characters inserted between words or characters.
+//! - [`whitespace`]: spaces and tabs at the end of the lines (SNOW-style), optionally
+//!   compressed.
+
+pub mod whitespace;
+pub mod zero_width;
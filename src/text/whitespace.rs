@@ -0,0 +1,274 @@
+//! Trailing whitespace, in the style of SNOW: the payload is written as spaces and tabs at
+//! the end of the lines.
+//!
+//! The payload bits are read MSB first in groups of 3; a group of value `v` is written as
+//! `v` spaces followed by a tab, so every group takes between 1 and 8 characters. The
+//! groups are spread evenly over the lines, at most
+//! [`groups_per_line`](WhitespaceOptions::groups_per_line) per line. Trailing whitespace is
+//! invisible in most viewers and, unlike zero-width characters, survives editors and
+//! copy-paste that only keep printable ASCII.
+//!
+//! The secret can be compressed with zlib before embedding, and framed with a
+//! [`LengthPrefix`]. Without a prefix, the extractor returns every complete byte found.
+//! Line endings (`\n` or `\r\n`) are kept; any trailing whitespace already in the cover is
+//! removed first.
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::bit_order::{BitOrder, BitWriter, read_bit};
+use crate::deflate::{zlib_compress, zlib_decompress};
+use crate::framing::LengthPrefix;
+
+/// Options of the whitespace methods. Embedding and extraction must use the same options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhitespaceOptions {
+    /// Maximum number of 3-bit groups written at the end of a line.
+    pub groups_per_line: usize,
+    /// Compresses the secret with zlib before embedding.
+    pub compress: bool,
+    /// Length prefix written in front of the (compressed) secret, if any.
+    pub prefix: Option<LengthPrefix>,
+}
+
+impl Default for WhitespaceOptions {
+    /// Returns 8 groups (24 bits) per line, no compression and the default length prefix.
+    fn default() -> Self {
+        Self {
+            groups_per_line: 8,
+            compress: false,
+            prefix: Some(LengthPrefix::default()),
+        }
+    }
+}
+
+const TRAILING: [char; 2] = [' ', '\t'];
+
+/// Splits a text into lines, each returned as its content and its line ending.
+fn lines(text: &str) -> Vec<(&str, &str)> {
+    text.split_inclusive('\n')
+        .map(|line| {
+            let ending = if line.ends_with("\r\n") {
+                2
+            } else if line.ends_with('\n') {
+                1
+            } else {
+                0
+            };
+            line.split_at(line.len() - ending)
+        })
+        .collect()
+}
+
+/// Returns the number of payload bits the cover can hold, excluding the length prefix.
+///
+/// With [`compress`](WhitespaceOptions::compress), this is the size of the compressed
+/// secret, which depends on the secret.
+pub fn whitespace_capacity(cover: &str, options: &WhitespaceOptions) -> usize {
+    let prefix_bits = options.prefix.map_or(0, |prefix| prefix.size * 8);
+    lines(cover)
+        .len()
+        .saturating_mul(options.groups_per_line)
+        .saturating_mul(3)
+        .saturating_sub(prefix_bits)
+}
+
+/// Hides a secret in the trailing whitespace of a cover text and returns the stego text.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::text::whitespace::{
+///     WhitespaceOptions, strip_trailing_whitespace, whitespace_embed, whitespace_extract,
+/// };
+///
+/// let cover = "fn main() {\n    println!(\"Hello\");\n}\n";
+/// let options = WhitespaceOptions::default();
+/// let stego = whitespace_embed(cover, b"Hi", &options).unwrap();
+/// assert_eq!(strip_trailing_whitespace(&stego), cover);
+/// assert_eq!(whitespace_extract(&stego, &options).unwrap(), b"Hi");
+/// ```
+///
+/// # Errors
+/// Returns an error if the length does not fit in the prefix, or if the cover does not have
+/// enough lines for the secret.
+pub fn whitespace_embed(
+    cover: &str,
+    secret: &[u8],
+    options: &WhitespaceOptions,
+) -> Result<String, String> {
+    let payload = if options.compress {
+        zlib_compress(secret)
+    } else {
+        secret.to_vec()
+    };
+    let framed = match options.prefix {
+        Some(prefix) => prefix.frame(&payload)?,
+        None => payload,
+    };
+
+    let bits = framed.len() * 8;
+    let groups: Vec<u8> = (0..bits.div_ceil(3))
+        .map(|group| {
+            (0..3).fold(0, |value, i| {
+                let pos = group * 3 + i;
+                let bit = if pos < bits {
+                    read_bit(&framed, pos, BitOrder::MsbFirst)
+                } else {
+                    0
+                };
+                value << 1 | bit
+            })
+        })
+        .collect();
+
+    let lines = lines(cover);
+    let available = lines.len().saturating_mul(options.groups_per_line);
+    if groups.len() > available {
+        return Err(format!(
+            "Not enough space in cover text to hide the secret message: capacity={}, needed={}",
+            available * 3,
+            groups.len() * 3
+        ));
+    }
+
+    // Line i takes the groups [i * n / l, (i + 1) * n / l), at most ceil(n / l) each
+    let mut stego = String::with_capacity(cover.len() + groups.len() * 5);
+    for (i, (content, ending)) in lines.iter().enumerate() {
+        stego.push_str(content.trim_end_matches(TRAILING));
+        let range = i * groups.len() / lines.len()..(i + 1) * groups.len() / lines.len();
+        for &value in &groups[range] {
+            stego.extend(core::iter::repeat_n(' ', value as usize));
+            stego.push('\t');
+        }
+        stego.push_str(ending);
+    }
+    Ok(stego)
+}
+
+/// Extracts a secret embedded with [`whitespace_embed`], with the same options.
+///
+/// # Errors
+/// Returns an error if a run of more than 7 spaces ends with a tab, if the length prefix is
+/// missing or announces more data than the text holds, or if the compressed data is invalid.
+pub fn whitespace_extract(stego: &str, options: &WhitespaceOptions) -> Result<Vec<u8>, String> {
+    let mut writer = BitWriter::new(BitOrder::MsbFirst);
+    let mut groups = 0;
+    for (content, _) in lines(stego) {
+        let trailing = &content[content.trim_end_matches(TRAILING).len()..];
+        let mut spaces = 0;
+        for c in trailing.chars() {
+            if c == ' ' {
+                spaces += 1;
+                continue;
+            }
+            if spaces > 7 {
+                return Err(format!(
+                    "Invalid whitespace encoding: {} spaces before a tab",
+                    spaces
+                ));
+            }
+            for i in (0..3).rev() {
+                writer.push((spaces >> i) as u8 & 1);
+            }
+            groups += 1;
+            spaces = 0;
+        }
+    }
+
+    let mut data = writer.into_bytes();
+    data.truncate(groups * 3 / 8);
+    let payload = match options.prefix {
+        Some(prefix) => prefix.unframe(&data)?,
+        None => data,
+    };
+    if options.compress {
+        zlib_decompress(&payload)
+    } else {
+        Ok(payload)
+    }
+}
+
+/// Removes the trailing spaces and tabs of every line, keeping the line endings.
+pub fn strip_trailing_whitespace(text: &str) -> String {
+    lines(text)
+        .into_iter()
+        .flat_map(|(content, ending)| [content.trim_end_matches(TRAILING), ending])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestRng;
+
+    fn cover(lines: usize) -> String {
+        (0..lines)
+            .map(|i| format!("    let value_{} = compute({}, \"tab\\there\");\n", i, i))
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_for_each_option() {
+        let cover = cover(40);
+        for (seed, (compress, prefix)) in [
+            (false, Some(LengthPrefix::default())),
+            (true, Some(LengthPrefix::default())),
+            (false, None),
+            (true, None),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let options = WhitespaceOptions {
+                compress,
+                prefix,
+                ..WhitespaceOptions::default()
+            };
+            let secret = if compress {
+                b"to be or not to be, that is the question; ".repeat(8)
+            } else {
+                TestRng::new(seed as u64 + 1).bytes(whitespace_capacity(&cover, &options) / 8)
+            };
+
+            let stego = whitespace_embed(&cover, &secret, &options).unwrap();
+            assert_eq!(strip_trailing_whitespace(&stego), cover);
+            let extracted = whitespace_extract(&stego, &options).unwrap();
+            assert_eq!(extracted, secret, "{:?}", options);
+        }
+    }
+
+    #[test]
+    fn test_encoding_and_line_endings() {
+        let options = WhitespaceOptions {
+            groups_per_line: 2,
+            prefix: None,
+            ..WhitespaceOptions::default()
+        };
+        // 0xFA 0x05 = 111 110 100 000 010 1(00): 6 groups over 3 lines, 2 per line
+        let stego = whitespace_embed("a  \r\nb\t\nc", &[0xFA, 0x05], &options).unwrap();
+        assert_eq!(stego, "a       \t      \t\r\nb    \t\t\nc  \t    \t");
+        assert_eq!(whitespace_extract(&stego, &options).unwrap(), [0xFA, 0x05]);
+        assert_eq!(strip_trailing_whitespace(&stego), "a\r\nb\nc");
+    }
+
+    #[test]
+    fn test_capacity_and_errors() {
+        let cover = cover(10);
+        let options = WhitespaceOptions::default();
+        assert_eq!(whitespace_capacity(&cover, &options), 10 * 8 * 3 - 32);
+        let too_long = vec![0u8; whitespace_capacity(&cover, &options) / 8 + 1];
+        assert!(
+            whitespace_embed(&cover, &too_long, &options)
+                .unwrap_err()
+                .starts_with("Not enough space")
+        );
+
+        assert!(whitespace_extract(&cover, &options).is_err());
+        assert!(whitespace_extract("line        \t\n", &options).is_err());
+        let compressed = WhitespaceOptions {
+            compress: true,
+            ..options
+        };
+        let stego = whitespace_embed(&cover, b"not compressed", &options).unwrap();
+        assert!(whitespace_extract(&stego, &compressed).is_err());
+    }
+}
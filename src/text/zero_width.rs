@@ -0,0 +1,242 @@
+//! Zero-width characters: the payload is written with four invisible Unicode characters.
+//!
+//! Each hidden character carries 2 bits: ZERO WIDTH SPACE (`00`), ZERO WIDTH NON-JOINER
+//! (`01`), ZERO WIDTH JOINER (`10`) and WORD JOINER (`11`). The secret is framed with a
+//! 4-byte big-endian length prefix ([`LengthPrefix::default`]), serialized MSB first, and
+//! the hidden characters are spread evenly over the slots chosen by [`ZeroWidthPlacement`].
+//!
+//! Extraction reads the hidden characters in order and ignores everything else, so it does
+//! not need the placement that was used for embedding.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::bit_order::{BitOrder, BitWriter, read_bit};
+use crate::framing::LengthPrefix;
+
+/// Hidden characters, indexed by the 2 bits they carry.
+pub const ZERO_WIDTH_CHARS: [char; 4] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}'];
+
+/// Where the hidden characters are inserted in the cover text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ZeroWidthPlacement {
+    /// At the end of every word, before the whitespace that follows it.
+    #[default]
+    BetweenWords,
+    /// Between two consecutive alphanumeric characters, inside words. This gives more
+    /// slots but breaks plain-text search on the stego text.
+    BetweenCharacters,
+    /// In a single slot at the end of the text. `max_per_slot` must leave room for the
+    /// 16 characters of the length prefix.
+    End,
+}
+
+/// Options of the zero-width methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZeroWidthOptions {
+    /// Where the hidden characters are inserted.
+    pub placement: ZeroWidthPlacement,
+    /// Maximum number of hidden characters in a single slot.
+    pub max_per_slot: usize,
+}
+
+impl Default for ZeroWidthOptions {
+    /// Returns [`ZeroWidthPlacement::BetweenWords`] with at most 8 characters (2 bytes) per
+    /// slot.
+    fn default() -> Self {
+        Self {
+            placement: ZeroWidthPlacement::BetweenWords,
+            max_per_slot: 8,
+        }
+    }
+}
+
+fn is_zero_width(c: char) -> bool {
+    ZERO_WIDTH_CHARS.contains(&c)
+}
+
+/// Byte offsets in `cover` where hidden characters can be inserted.
+fn slots(cover: &str, placement: ZeroWidthPlacement) -> Vec<usize> {
+    let chars: Vec<(usize, char)> = cover.char_indices().collect();
+    match placement {
+        ZeroWidthPlacement::BetweenWords => chars
+            .windows(2)
+            .filter(|w| !w[0].1.is_whitespace() && w[1].1.is_whitespace())
+            .map(|w| w[1].0)
+            .collect(),
+        ZeroWidthPlacement::BetweenCharacters => chars
+            .windows(2)
+            .filter(|w| w[0].1.is_alphanumeric() && w[1].1.is_alphanumeric())
+            .map(|w| w[1].0)
+            .collect(),
+        ZeroWidthPlacement::End => vec![cover.len()],
+    }
+}
+
+/// Returns the number of secret bits the cover can hold, excluding the length prefix.
+pub fn zero_width_capacity(cover: &str, options: &ZeroWidthOptions) -> usize {
+    let prefix_bits = LengthPrefix::default().size * 8;
+    slots(cover, options.placement)
+        .len()
+        .saturating_mul(options.max_per_slot)
+        .saturating_mul(2)
+        .saturating_sub(prefix_bits)
+}
+
+/// Hides a secret in a cover text and returns the stego text.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::text::zero_width::{
+///     ZeroWidthOptions, strip_zero_width, zero_width_embed, zero_width_extract,
+/// };
+///
+/// let cover = "The quick brown fox jumps over the lazy dog, again and again.";
+/// let stego = zero_width_embed(cover, b"Hi", &ZeroWidthOptions::default()).unwrap();
+/// assert_ne!(stego, cover);
+/// assert_eq!(strip_zero_width(&stego), cover);
+/// assert_eq!(zero_width_extract(&stego).unwrap(), b"Hi");
+/// ```
+///
+/// # Errors
+/// Returns an error if the cover already contains zero-width characters, or if it does not
+/// have enough slots for the secret.
+pub fn zero_width_embed(
+    cover: &str,
+    secret: &[u8],
+    options: &ZeroWidthOptions,
+) -> Result<String, String> {
+    if cover.chars().any(is_zero_width) {
+        return Err("Cover text already contains zero-width characters".into());
+    }
+    let framed = LengthPrefix::default().frame(secret)?;
+    let symbols: Vec<char> = (0..framed.len() * 4)
+        .map(|i| {
+            let high = read_bit(&framed, 2 * i, BitOrder::MsbFirst);
+            let low = read_bit(&framed, 2 * i + 1, BitOrder::MsbFirst);
+            ZERO_WIDTH_CHARS[(high << 1 | low) as usize]
+        })
+        .collect();
+
+    let slots = slots(cover, options.placement);
+    let available = slots.len().saturating_mul(options.max_per_slot);
+    if symbols.len() > available {
+        return Err(format!(
+            "Not enough space in cover text to hide the secret message: capacity={}, needed={}",
+            available * 2,
+            symbols.len() * 2
+        ));
+    }
+
+    // Slot i takes the symbols [i * n / s, (i + 1) * n / s), at most ceil(n / s) each
+    let mut stego = String::with_capacity(cover.len() + symbols.len() * 3);
+    let mut copied = 0;
+    for (i, &offset) in slots.iter().enumerate() {
+        let range = i * symbols.len() / slots.len()..(i + 1) * symbols.len() / slots.len();
+        stego.push_str(&cover[copied..offset]);
+        stego.extend(&symbols[range]);
+        copied = offset;
+    }
+    stego.push_str(&cover[copied..]);
+    Ok(stego)
+}
+
+/// Extracts a secret embedded with [`zero_width_embed`], ignoring the visible text.
+///
+/// # Errors
+/// Returns an error if the text holds no complete length prefix, or if the prefix
+/// announces more data than the text holds.
+pub fn zero_width_extract(stego: &str) -> Result<Vec<u8>, String> {
+    let mut writer = BitWriter::new(BitOrder::MsbFirst);
+    for c in stego.chars() {
+        if let Some(symbol) = ZERO_WIDTH_CHARS.iter().position(|&z| z == c) {
+            writer.push((symbol >> 1) as u8);
+            writer.push(symbol as u8);
+        }
+    }
+    LengthPrefix::default().unframe(&writer.into_bytes())
+}
+
+/// Removes every hidden character from a text, which restores the cover text.
+pub fn strip_zero_width(text: &str) -> String {
+    text.chars().filter(|&c| !is_zero_width(c)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestRng;
+
+    const COVER: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
+        eiusmod tempor incididunt ut labore et dolore magna aliqua.\n\
+        Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut \
+        aliquip ex ea commodo consequat. Größe, café — naïve façade.";
+
+    #[test]
+    fn test_round_trip_for_each_placement() {
+        for (seed, placement) in [
+            ZeroWidthPlacement::BetweenWords,
+            ZeroWidthPlacement::BetweenCharacters,
+            ZeroWidthPlacement::End,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            // A single slot at the end needs room for the length prefix
+            let options = ZeroWidthOptions {
+                placement,
+                max_per_slot: 64,
+            };
+            let capacity = zero_width_capacity(COVER, &options);
+            let secret = TestRng::new(seed as u64 + 1).bytes(capacity / 8);
+
+            let stego = zero_width_embed(COVER, &secret, &options).unwrap();
+            assert_eq!(strip_zero_width(&stego), COVER, "{:?}", placement);
+            assert_eq!(
+                zero_width_extract(&stego).unwrap(),
+                secret,
+                "{:?}",
+                placement
+            );
+        }
+    }
+
+    #[test]
+    fn test_placement_and_spreading() {
+        let options = ZeroWidthOptions::default();
+        let stego = zero_width_embed("one two three four", b"", &options).unwrap();
+        // 16 symbols for the prefix, over the 3 word ends: 5, 5 and 6
+        let runs: Vec<usize> = stego
+            .split(|c: char| !is_zero_width(c))
+            .map(|run| run.chars().count())
+            .filter(|&n| n > 0)
+            .collect();
+        assert_eq!(runs, vec![5, 5, 6]);
+        assert!(stego.starts_with("one\u{200B}"));
+        assert!(stego.ends_with(" four"));
+
+        let inside = ZeroWidthOptions {
+            placement: ZeroWidthPlacement::BetweenCharacters,
+            ..options
+        };
+        let stego = zero_width_embed("ab, cd", b"", &inside).unwrap();
+        assert!(stego.starts_with('a') && stego.contains("b, c") && stego.ends_with('d'));
+    }
+
+    #[test]
+    fn test_errors() {
+        let options = ZeroWidthOptions::default();
+        let too_long = vec![0u8; zero_width_capacity(COVER, &options) / 8 + 1];
+        assert!(
+            zero_width_embed(COVER, &too_long, &options)
+                .unwrap_err()
+                .starts_with("Not enough space")
+        );
+        assert!(zero_width_embed("a\u{200D}b c", b"x", &options).is_err());
+        assert_eq!(zero_width_capacity("single", &options), 0);
+
+        let stego = zero_width_embed(COVER, b"secret", &options).unwrap();
+        let truncated: String = stego.chars().take(COVER.len() / 2).collect();
+        assert!(zero_width_extract(&truncated).is_err());
+        assert!(zero_width_extract(COVER).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Bit-plane slicing utilities to review carriers.
+//!
+//! Each function returns an 8-bit grayscale image where a set bit is white (255) and a
+//! cleared bit is black (0), so embedding artifacts can be seen directly. The images can be
+//! written out with [`BitPlaneImage::to_pgm`].
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::bitplane::extract_lsb;
+use crate::geometry::ImageGeometry;
+
+/// A binary grayscale image produced by bit-plane slicing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitPlaneImage {
+    /// Width of the image in pixels.
+    pub width: usize,
+    /// Height of the image in pixels.
+    pub height: usize,
+    /// One sample per pixel, either 0 or 255.
+    pub data: Vec<u8>,
+}
+
+impl BitPlaneImage {
+    /// Encodes the image as a binary PGM (`P5`) file.
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let mut pgm = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        pgm.extend_from_slice(&self.data);
+        pgm
+    }
+}
+
+/// Extracts bit `bit` (0 = LSB, 7 = MSB) of channel `channel` as a binary image.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+/// use stegano_rs::visualization::bit_plane;
+///
+/// // 2x1 RGB image
+/// let geometry = ImageGeometry::new(2, 1, 3);
+/// let host = [0b0000_0001, 0, 0, 0b1000_0000, 0, 0];
+///
+/// assert_eq!(bit_plane(&host, &geometry, 0, 0).unwrap().data, vec![255, 0]);
+/// assert_eq!(bit_plane(&host, &geometry, 0, 7).unwrap().data, vec![0, 255]);
+/// ```
+pub fn bit_plane(
+    host: &[u8],
+    geometry: &ImageGeometry,
+    channel: usize,
+    bit: u8,
+) -> Result<BitPlaneImage, String> {
+    check_plane(host, geometry, channel, bit)?;
+
+    Ok(slice(geometry, channel, |idx| plane_bit(host[idx], bit)))
+}
+
+/// Extracts the 8 bit planes of channel `channel`, from bit 0 (LSB) to bit 7 (MSB).
+pub fn bit_planes(
+    host: &[u8],
+    geometry: &ImageGeometry,
+    channel: usize,
+) -> Result<Vec<BitPlaneImage>, String> {
+    (0..8)
+        .map(|bit| bit_plane(host, geometry, channel, bit))
+        .collect()
+}
+
+/// Builds the XOR-difference of bit `bit` of channel `channel` between a cover and a stego
+/// buffer: white where the embedding flipped the bit.
+///
+/// # Example
+/// ```rust
+/// use stegano_rs::geometry::ImageGeometry;
+/// use stegano_rs::visualization::xor_plane;
+///
+/// let geometry = ImageGeometry::new(4, 1, 1);
+/// let plane = xor_plane(&[10, 11, 12, 13], &[11, 11, 12, 12], &geometry, 0, 0).unwrap();
+/// assert_eq!(plane.data, vec![255, 0, 0, 255]);
+/// ```
+pub fn xor_plane(
+    cover: &[u8],
+    stego: &[u8],
+    geometry: &ImageGeometry,
+    channel: usize,
+    bit: u8,
+) -> Result<BitPlaneImage, String> {
+    check_plane(cover, geometry, channel, bit)?;
+    if cover.len() != stego.len() {
+        return Err(format!(
+            "Cover and stego lengths differ: {} != {}",
+            cover.len(),
+            stego.len()
+        ));
+    }
+
+    Ok(slice(geometry, channel, |idx| {
+        plane_bit(cover[idx] ^ stego[idx], bit)
+    }))
+}
+
+/// Returns 255 if bit `bit` of `value` is set, 0 otherwise.
+fn plane_bit(value: u8, bit: u8) -> u8 {
+    extract_lsb(value >> bit, 1) * 255
+}
+
+/// Builds an image from the samples of `channel`, mapped through `pixel`.
+fn slice(geometry: &ImageGeometry, channel: usize, pixel: impl Fn(usize) -> u8) -> BitPlaneImage {
+    BitPlaneImage {
+        width: geometry.width,
+        height: geometry.height,
+        data: (0..geometry.pixels())
+            .map(|p| pixel(p * geometry.channels + channel))
+            .collect(),
+    }
+}
+
+fn check_plane(
+    host: &[u8],
+    geometry: &ImageGeometry,
+    channel: usize,
+    bit: u8,
+) -> Result<(), String> {
+    geometry.check(host.len())?;
+    if channel >= geometry.channels {
+        return Err(format!(
+            "channel {} is out of range for {} channels",
+            channel, geometry.channels
+        ));
+    }
+    if bit > 7 {
+        return Err("bit must be between 0 and 7".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitplane::{
+        BitplaneOptions, EmbedStrategy, ExtractStrategy, bitplane_embed_with_locator,
+    };
+    use crate::embedding_locator::LinearTraversal;
+
+    #[test]
+    fn test_bit_planes_rebuild_channel() {
+        let geometry = ImageGeometry::new(8, 4, 2);
+        let host: Vec<u8> = (0..geometry.len()).map(|i| (i * 37 % 256) as u8).collect();
+
+        for channel in 0..2 {
+            let planes = bit_planes(&host, &geometry, channel).unwrap();
+            assert_eq!(planes.len(), 8);
+            for p in 0..geometry.pixels() {
+                let rebuilt = planes
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |acc, (bit, plane)| acc | ((plane.data[p] & 1) << bit));
+                assert_eq!(rebuilt, host[p * 2 + channel]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_xor_planes_show_embedding() {
+        let geometry = ImageGeometry::new(16, 16, 1);
+        let cover = vec![0x55u8; geometry.len()];
+
+        // LSB embedding (cover LSBs are all 1) only shows in the bit 0 difference, over the embedded prefix
+        let mut stego = cover.clone();
+        bitplane_embed_with_locator(
+            &mut stego,
+            &[0x00; 4],
+            &BitplaneOptions::default(),
+            &LinearTraversal,
+        )
+        .unwrap();
+        let lsb = xor_plane(&cover, &stego, &geometry, 0, 0).unwrap();
+        assert_eq!(lsb.data.iter().filter(|&&v| v == 255).count(), 32);
+        assert!(lsb.data[32..].iter().all(|&v| v == 0));
+        assert!(
+            xor_plane(&cover, &stego, &geometry, 0, 1)
+                .unwrap()
+                .data
+                .iter()
+                .all(|&v| v == 0)
+        );
+
+        // MSB embedding shows in bit 7
+        let mut stego = cover.clone();
+        let options = BitplaneOptions {
+            bits_to_operate: 1,
+            embed_strategy: Some(EmbedStrategy::Msb),
+            extract_strategy: Some(ExtractStrategy::Msb),
+            ..BitplaneOptions::default()
+        };
+        bitplane_embed_with_locator(&mut stego, &[0xFF], &options, &LinearTraversal).unwrap();
+        let msb = xor_plane(&cover, &stego, &geometry, 0, 7).unwrap();
+        assert_eq!(msb.data.iter().filter(|&&v| v == 255).count(), 8);
+    }
+
+    #[test]
+    fn test_to_pgm() {
+        let geometry = ImageGeometry::new(2, 2, 1);
+        let plane = bit_plane(&[1, 0, 0, 1], &geometry, 0, 0).unwrap();
+        assert_eq!(plane.to_pgm(), b"P5\n2 2\n255\n\xff\x00\x00\xff".to_vec());
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let geometry = ImageGeometry::new(2, 2, 1);
+        assert_eq!(
+            bit_plane(&[0; 4], &geometry, 1, 0).unwrap_err(),
+            "channel 1 is out of range for 1 channels"
+        );
+        assert_eq!(
+            bit_plane(&[0; 4], &geometry, 0, 8).unwrap_err(),
+            "bit must be between 0 and 7"
+        );
+    }
+}
@@ -0,0 +1,52 @@
+/*
+ * Dumps the quantized DCT coefficients of a JPEG file, as read by libjpeg.
+ *
+ *   cc dump_coefficients.c -ljpeg -o dump_coefficients && ./dump_coefficients in.jpg out.coef
+ *
+ * Output: for each component, its blocks in row-major order (width_in_blocks x
+ * height_in_blocks), each block as 64 little-endian i16 in natural (row-major) order.
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <jpeglib.h>
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "usage: %s in.jpg out.coef\n", argv[0]);
+        return 1;
+    }
+    struct jpeg_decompress_struct cinfo;
+    struct jpeg_error_mgr jerr;
+    FILE *in = fopen(argv[1], "rb");
+    FILE *out = fopen(argv[2], "wb");
+    if (!in || !out) {
+        perror("fopen");
+        return 1;
+    }
+
+    cinfo.err = jpeg_std_error(&jerr);
+    jpeg_create_decompress(&cinfo);
+    jpeg_stdio_src(&cinfo, in);
+    jpeg_read_header(&cinfo, TRUE);
+    jvirt_barray_ptr *coefs = jpeg_read_coefficients(&cinfo);
+
+    for (int c = 0; c < cinfo.num_components; c++) {
+        jpeg_component_info *comp = &cinfo.comp_info[c];
+        for (JDIMENSION row = 0; row < comp->height_in_blocks; row++) {
+            JBLOCKARRAY blocks = (*cinfo.mem->access_virt_barray)((j_common_ptr)&cinfo, coefs[c], row, 1, FALSE);
+            for (JDIMENSION col = 0; col < comp->width_in_blocks; col++) {
+                for (int k = 0; k < DCTSIZE2; k++) {
+                    short v = blocks[0][col][k];
+                    fputc(v & 0xFF, out);
+                    fputc((v >> 8) & 0xFF, out);
+                }
+            }
+        }
+    }
+
+    jpeg_finish_decompress(&cinfo);
+    jpeg_destroy_decompress(&cinfo);
+    fclose(in);
+    fclose(out);
+    return 0;
+}
@@ -0,0 +1,65 @@
+/*
+ * Generates the baseline JPEG fixtures with libjpeg.
+ *
+ *   cc make_fixtures.c -ljpeg -lm -o make_fixtures && ./make_fixtures
+ */
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <jpeglib.h>
+
+static void write_jpeg(const char *path, int width, int height, int components,
+                       int quality, int optimize, int restart, int subsample) {
+    struct jpeg_compress_struct cinfo;
+    struct jpeg_error_mgr jerr;
+    FILE *out = fopen(path, "wb");
+    if (!out) {
+        perror(path);
+        exit(1);
+    }
+
+    cinfo.err = jpeg_std_error(&jerr);
+    jpeg_create_compress(&cinfo);
+    jpeg_stdio_dest(&cinfo, out);
+    cinfo.image_width = width;
+    cinfo.image_height = height;
+    cinfo.input_components = components;
+    cinfo.in_color_space = components == 3 ? JCS_RGB : JCS_GRAYSCALE;
+    jpeg_set_defaults(&cinfo);
+    jpeg_set_quality(&cinfo, quality, TRUE);
+    cinfo.optimize_coding = optimize;
+    cinfo.restart_interval = restart;
+    if (components == 3 && !subsample) {
+        cinfo.comp_info[0].h_samp_factor = 1;
+        cinfo.comp_info[0].v_samp_factor = 1;
+    }
+    jpeg_start_compress(&cinfo, TRUE);
+
+    JSAMPLE *row = malloc(width * components);
+    while (cinfo.next_scanline < cinfo.image_height) {
+        int y = cinfo.next_scanline;
+        for (int x = 0; x < width; x++) {
+            for (int c = 0; c < components; c++) {
+                double v = 128 + 60 * sin(x / 7.0 + c) * cos(y / 9.0) + 30 * sin((x + 2 * y) / 13.0)
+                         + ((x * 31 + y * 17 + c * 7) % 11) - 5;
+                row[x * components + c] = (JSAMPLE)(v < 0 ? 0 : v > 255 ? 255 : v);
+            }
+        }
+        jpeg_write_scanlines(&cinfo, &row, 1);
+    }
+    free(row);
+
+    jpeg_finish_compress(&cinfo);
+    jpeg_destroy_compress(&cinfo);
+    fclose(out);
+}
+
+int main(void) {
+    /* Grayscale, standard Huffman tables */
+    write_jpeg("gray.jpg", 64, 48, 1, 85, FALSE, 0, 0);
+    /* Color 4:2:0, odd size, optimized Huffman tables, restart markers */
+    write_jpeg("color_420.jpg", 75, 53, 3, 90, TRUE, 3, 1);
+    /* Color 4:4:4, standard tables */
+    write_jpeg("color_444.jpg", 40, 24, 3, 75, FALSE, 0, 0);
+    return 0;
+}
@@ -0,0 +1,69 @@
+#!/usr/bin/env python3
+"""Reference encoder for the golden files of the lsb_layout presets.
+
+Written independently of the crate: pixels row-major, R, G, B channels of each pixel
+(alpha skipped), one LSB per channel, payload prefixed with a 32-bit length. In zsteg
+notation the bit streams are b1,rgb,msb,xy (stego_rgb.ppm, stego_rgba.pam) and
+b1,rgb,lsb,xy (stego_rgb_lsb_first.ppm).
+
+The fixtures were not produced by an external stego tool.
+
+Run from this directory to regenerate the fixtures.
+"""
+import struct
+
+MESSAGE = b"Hello, partner! Row-major RGB LSB."
+
+
+def cover(width, height, channels):
+    return bytes(
+        (x * 16 + y * 7 + c * 50 + (x * y) % 13) % 256
+        for y in range(height)
+        for x in range(width)
+        for c in range(channels)
+    )
+
+
+def embed(pixels, channels, payload, lsb_first):
+    bits = []
+    for byte in payload:
+        order = range(8) if lsb_first else range(7, -1, -1)
+        bits.extend((byte >> i) & 1 for i in order)
+    out = bytearray(pixels)
+    slots = [p * channels + c for p in range(len(pixels) // channels) for c in range(3)]
+    assert len(bits) <= len(slots), "message too long"
+    for slot, bit in zip(slots, bits):
+        out[slot] = (out[slot] & 0xFE) | bit
+    return bytes(out)
+
+
+def write_ppm(path, width, height, pixels):
+    with open(path, "wb") as f:
+        f.write(b"P6\n%d %d\n255\n" % (width, height))
+        f.write(pixels)
+
+
+def write_pam(path, width, height, pixels):
+    with open(path, "wb") as f:
+        f.write(
+            b"P7\nWIDTH %d\nHEIGHT %d\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n"
+            % (width, height)
+        )
+        f.write(pixels)
+
+
+rgb = cover(16, 8, 3)
+write_ppm("cover.ppm", 16, 8, rgb)
+# 32-bit big-endian length in bytes, MSB-first bits
+write_ppm("stego_rgb.ppm", 16, 8, embed(rgb, 3, struct.pack(">I", len(MESSAGE)) + MESSAGE, False))
+# 32-bit little-endian length in bits, LSB-first bits
+write_ppm(
+    "stego_rgb_lsb_first.ppm",
+    16,
+    8,
+    embed(rgb, 3, struct.pack("<I", len(MESSAGE) * 8) + MESSAGE, True),
+)
+
+rgba = cover(16, 8, 4)
+write_pam("cover_rgba.pam", 16, 8, rgba)
+write_pam("stego_rgba.pam", 16, 8, embed(rgba, 4, struct.pack(">I", len(MESSAGE)) + MESSAGE, False))
@@ -0,0 +1,151 @@
+/*
+ * Generates the palette fixtures with giflib and libpng, with the raw palettes (.pal, RGB
+ * triples) and row-major indices (.idx) they hold.
+ *
+ *   cc make_fixtures.c -l:libgif.so.7 -lpng -lm -o make_fixtures && ./make_fixtures
+ *
+ * Only the runtime library of giflib 5 is needed: the few declarations used are below.
+ */
+#include <math.h>
+#include <png.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+typedef unsigned char GifByteType;
+typedef struct { GifByteType Red, Green, Blue; } GifColorType;
+typedef struct {
+    int ColorCount;
+    int BitsPerPixel;
+    bool SortFlag;
+    GifColorType *Colors;
+} ColorMapObject;
+typedef struct GifFileType GifFileType;
+
+GifFileType *EGifOpenFileName(const char *name, bool test_existence, int *error);
+int EGifSetGifVersion(GifFileType *gif, bool gif89);
+int EGifPutScreenDesc(GifFileType *gif, int width, int height, int color_res, int background,
+                      const ColorMapObject *map);
+int EGifPutExtension(GifFileType *gif, int code, int len, const void *extension);
+int EGifPutImageDesc(GifFileType *gif, int left, int top, int width, int height,
+                     bool interlace, const ColorMapObject *map);
+int EGifPutLine(GifFileType *gif, GifByteType *line, int len);
+int EGifCloseFile(GifFileType *gif, int *error);
+ColorMapObject *GifMakeMapObject(int count, const GifColorType *colors);
+
+static unsigned seed = 12345;
+
+static unsigned next_random(void) {
+    seed = seed * 1103515245 + 12345;
+    return (seed >> 16) & 0x7FFF;
+}
+
+/* Palette of `count` colours in a random order, so that index order is not colour order. */
+static void make_palette(unsigned char *palette, int count) {
+    for (int i = 0; i < count; i++) {
+        double t = (double)i / count;
+        palette[3 * i] = (unsigned char)(40 + 200 * t);
+        palette[3 * i + 1] = (unsigned char)(128 + 100 * sin(6.0 * t));
+        palette[3 * i + 2] = (unsigned char)(200 - 150 * t);
+    }
+    for (int i = count - 1; i > 0; i--) {
+        int j = next_random() % (i + 1);
+        for (int c = 0; c < 3; c++) {
+            unsigned char tmp = palette[3 * i + c];
+            palette[3 * i + c] = palette[3 * j + c];
+            palette[3 * j + c] = tmp;
+        }
+    }
+}
+
+static void make_indices(unsigned char *indices, int width, int height, int count) {
+    for (int y = 0; y < height; y++)
+        for (int x = 0; x < width; x++) {
+            double v = 0.5 + 0.25 * sin(x / 7.0) + 0.2 * cos(y / 5.0) + 0.05 * (next_random() % 3);
+            int i = (int)(v * count);
+            indices[y * width + x] = i < 0 ? 0 : i >= count ? count - 1 : i;
+        }
+}
+
+static void write_raw(const char *name, const char *ext, const unsigned char *data, size_t len) {
+    char path[256];
+    snprintf(path, sizeof path, "%s.%s", name, ext);
+    FILE *out = fopen(path, "wb");
+    fwrite(data, 1, len, out);
+    fclose(out);
+}
+
+static void write_gif(const char *name, int width, int height, int count, bool interlace) {
+    unsigned char palette[3 * 256];
+    unsigned char *indices = malloc(width * height);
+    make_palette(palette, count);
+    make_indices(indices, width, height, count);
+
+    char path[256];
+    snprintf(path, sizeof path, "%s.gif", name);
+    int error;
+    GifFileType *gif = EGifOpenFileName(path, false, &error);
+    int bits = 1;
+    while ((1 << bits) < count)
+        bits++;
+    ColorMapObject *map = GifMakeMapObject(1 << bits, (GifColorType *)palette);
+    EGifSetGifVersion(gif, true);
+    EGifPutScreenDesc(gif, width, height, bits, 0, map);
+    const char comment[] = "stegano-rs palette fixture";
+    EGifPutExtension(gif, 0xFE, sizeof comment - 1, comment);
+    EGifPutImageDesc(gif, 0, 0, width, height, interlace, NULL);
+    if (interlace) {
+        static const int start[] = {0, 4, 2, 1}, step[] = {8, 8, 4, 2};
+        for (int pass = 0; pass < 4; pass++)
+            for (int y = start[pass]; y < height; y += step[pass])
+                EGifPutLine(gif, indices + y * width, width);
+    } else {
+        for (int y = 0; y < height; y++)
+            EGifPutLine(gif, indices + y * width, width);
+    }
+    EGifCloseFile(gif, &error);
+
+    write_raw(name, "pal", palette, 3 * (1 << bits));
+    write_raw(name, "idx", indices, width * height);
+    free(indices);
+}
+
+static void write_png(const char *name, int width, int height, int count, int depth) {
+    unsigned char palette[3 * 256];
+    unsigned char *indices = malloc(width * height);
+    make_palette(palette, count);
+    make_indices(indices, width, height, count);
+
+    char path[256];
+    snprintf(path, sizeof path, "%s.png", name);
+    FILE *out = fopen(path, "wb");
+    png_structp png = png_create_write_struct(PNG_LIBPNG_VER_STRING, NULL, NULL, NULL);
+    png_infop info = png_create_info_struct(png);
+    png_init_io(png, out);
+    png_set_IHDR(png, info, width, height, depth, PNG_COLOR_TYPE_PALETTE, PNG_INTERLACE_NONE,
+                 PNG_COMPRESSION_TYPE_DEFAULT, PNG_FILTER_TYPE_DEFAULT);
+    png_set_PLTE(png, info, (png_colorp)palette, count);
+    png_byte alpha[1] = {0};
+    png_set_tRNS(png, info, alpha, 1, NULL);
+    png_text text = {PNG_TEXT_COMPRESSION_NONE, "Comment", "stegano-rs palette fixture"};
+    png_set_text(png, info, &text, 1);
+    png_write_info(png, info);
+    png_set_packing(png);
+    for (int y = 0; y < height; y++)
+        png_write_row(png, indices + y * width);
+    png_write_end(png, info);
+    png_destroy_write_struct(&png, &info);
+    fclose(out);
+
+    write_raw(name, "pal", palette, 3 * count);
+    write_raw(name, "idx", indices, width * height);
+    free(indices);
+}
+
+int main(void) {
+    write_gif("palette", 61, 37, 64, false);
+    write_gif("interlaced", 45, 29, 32, true);
+    write_png("palette8", 53, 29, 200, 8);
+    write_png("palette4", 33, 21, 16, 4);
+    return 0;
+}